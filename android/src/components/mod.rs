@@ -1,3 +0,0 @@
-pub mod file;
-pub mod geo;
-pub mod notification;
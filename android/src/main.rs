@@ -1,10 +1,57 @@
-use dioxus::prelude::*;
+use amp_ui::Platform;
+use amp_ui::components::notification::{LogNotifier, Notifier};
+use amp_ui::platform::data_dir_override;
+use std::path::PathBuf;
 
-pub mod components;
-pub mod ui;
+/// Android services; notifications and location are not bridged to the OS yet
+struct Android;
 
-use ui::App;
+impl Platform for Android {
+    fn data_dir(&self) -> PathBuf {
+        if let Some(dir) = data_dir_override() {
+            return dir;
+        }
+
+        // Internal storage of the app, as reported by the activity; a desktop build uses
+        // the user data directory
+        #[cfg(target_os = "android")]
+        let dir = files_dir().unwrap_or_else(|| PathBuf::from("/data/data/se.sjoegren.amp/files"));
+        #[cfg(not(target_os = "android"))]
+        let dir = dirs::data_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("amp");
+        dir
+    }
+
+    fn notifier(&self) -> Box<dyn Notifier> {
+        Box::new(LogNotifier::default())
+    }
+}
+
+/// `Context.getFilesDir()` of the running activity
+#[cfg(target_os = "android")]
+fn files_dir() -> Option<PathBuf> {
+    use jni::JavaVM;
+    use jni::objects::{JObject, JString};
+
+    let context = ndk_context::android_context();
+    // SAFETY: both pointers are provided by the Android glue and live as long as the app
+    let vm = unsafe { JavaVM::from_raw(context.vm().cast()) }.ok()?;
+    let mut env = vm.attach_current_thread().ok()?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+
+    let dir = env
+        .call_method(&activity, "getFilesDir", "()Ljava/io/File;", &[])
+        .and_then(|value| value.l())
+        .ok()?;
+    let path = env
+        .call_method(&dir, "getAbsolutePath", "()Ljava/lang/String;", &[])
+        .and_then(|value| value.l())
+        .ok()?;
+    let path: String = env.get_string(&JString::from(path)).ok()?.into();
+    Some(PathBuf::from(path))
+}
 
 fn main() {
-    launch(App);
+    amp_ui::launch(Android);
 }
@@ -52,14 +52,15 @@ default_platform = "mobile"
 asset_dir = "assets"
 
 [bundle]
+identifier = "se.sjoegren.amp"
 publisher = "Skäggbyrån Malmö"
 icon = ["assets/icon/icon-512.png"]
-resources = ["assets/data/adress_info.parquet"]
+resources = ["../ui/assets/data/adress_info.parquet"]
 
 [bundle.android]
 publisher = "Skäggbyrån Malmö"
 icon = ["assets/icon/icon-512.png"]
-resources = ["assets/data/adress_info.parquet"]
+resources = ["../ui/assets/data/adress_info.parquet"]
 min_sdk_version = 21
 target_sdk_version = 36
 orientation = "portrait"
@@ -0,0 +1,151 @@
+//! Microbenchmarks for index build, single-query latency and batch throughput
+//! Runs on synthetic cities so no real data is needed: `cargo bench -p amp-core`
+
+use amp_core::correlation_algorithms::{
+    CorrelationAlgo, DistanceBasedAlgo, GridNearestAlgo, KDTreeSpatialAlgo, OverlappingChunksAlgo,
+    RTreeSpatialAlgo, RaycastingAlgo,
+};
+use amp_core::structs::{AdressClean, MiljoeDataClean};
+use amp_core::synthetic::{CityOptions, SyntheticCity, generate_city};
+use amp_core::zone_index::ZoneIndex;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rayon::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use std::hint::black_box;
+
+/// City sizes in addresses; zones scale along, about 0.65 miljödata segments per address
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+/// Largest city the brute-force algorithms are run on
+const BRUTE_FORCE_MAX: usize = 10_000;
+/// Addresses correlated per batch iteration
+const BATCH: usize = 1_000;
+/// Search radii in meters for the cutoff benchmarks
+const CUTOFFS: [f64; 4] = [10.0, 25.0, 50.0, 100.0];
+
+type Build = fn(&[MiljoeDataClean]) -> Box<dyn CorrelationAlgo + Sync>;
+
+/// Every algorithm with its constructor, and whether it builds an index
+const ALGORITHMS: [(&str, Build, bool); 6] = [
+    ("distance-based", |_| Box::new(DistanceBasedAlgo), false),
+    ("raycasting", |_| Box::new(RaycastingAlgo), false),
+    (
+        "overlapping-chunks",
+        |zones| Box::new(OverlappingChunksAlgo::new(zones)),
+        true,
+    ),
+    (
+        "rtree",
+        |zones| Box::new(RTreeSpatialAlgo::new(zones)),
+        true,
+    ),
+    (
+        "kdtree",
+        |zones| Box::new(KDTreeSpatialAlgo::new(zones)),
+        true,
+    ),
+    ("grid", |zones| Box::new(GridNearestAlgo::new(zones)), true),
+];
+
+fn city(addresses: usize) -> SyntheticCity {
+    generate_city(&CityOptions {
+        addresses,
+        seed: 1,
+        ..CityOptions::default()
+    })
+}
+
+/// Algorithms worth running on a city of this size
+fn algorithms(size: usize) -> impl Iterator<Item = &'static (&'static str, Build, bool)> {
+    ALGORITHMS
+        .iter()
+        .filter(move |(_, _, indexed)| *indexed || size <= BRUTE_FORCE_MAX)
+}
+
+fn point(address: &AdressClean) -> [f64; 2] {
+    [
+        address.coordinates[0].to_f64().unwrap_or_default(),
+        address.coordinates[1].to_f64().unwrap_or_default(),
+    ]
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    for size in SIZES {
+        let city = city(size);
+        group.throughput(Throughput::Elements(city.miljodata.len() as u64));
+        for (name, build, _) in ALGORITHMS.iter().filter(|(_, _, indexed)| *indexed) {
+            group.bench_with_input(
+                BenchmarkId::new(*name, size),
+                &city.miljodata,
+                |b, zones| b.iter(|| build(black_box(zones))),
+            );
+        }
+        group.bench_with_input(
+            BenchmarkId::new("zone-index", size),
+            &city.miljodata,
+            |b, zones| b.iter_batched(|| zones.clone(), ZoneIndex::new, BatchSize::LargeInput),
+        );
+    }
+    group.finish();
+}
+
+fn bench_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("query");
+    for size in SIZES {
+        let city = city(size);
+        for (name, build, _) in algorithms(size) {
+            let algo = build(&city.miljodata);
+            let mut next = city.addresses.iter().cycle();
+            group.bench_function(BenchmarkId::new(*name, size), |b| {
+                b.iter(|| algo.correlate(black_box(next.next().unwrap()), &city.miljodata))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BATCH as u64));
+    for size in SIZES {
+        let city = city(size);
+        let batch = &city.addresses[..BATCH.min(city.addresses.len())];
+        for (name, build, _) in algorithms(size) {
+            let algo = build(&city.miljodata);
+            group.bench_function(BenchmarkId::new(*name, size), |b| {
+                b.iter(|| {
+                    batch
+                        .par_iter()
+                        .filter(|address| algo.correlate(address, &city.miljodata).is_some())
+                        .count()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Only the zone index takes a cutoff per query; the algorithms search a fixed 50 m,
+/// so they have no cutoff to vary
+fn bench_cutoff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cutoff");
+    let city = city(BRUTE_FORCE_MAX);
+    let index = ZoneIndex::new(city.miljodata.clone());
+    let points: Vec<[f64; 2]> = city.addresses.iter().map(point).collect();
+    for cutoff in CUTOFFS {
+        let mut next = points.iter().cycle();
+        group.bench_function(BenchmarkId::new("nearest", cutoff), |b| {
+            b.iter(|| index.nearest(*black_box(next.next().unwrap()), cutoff))
+        });
+        let mut next = points.iter().cycle();
+        group.bench_function(BenchmarkId::new("within", cutoff), |b| {
+            b.iter(|| index.within(*black_box(next.next().unwrap()), cutoff).len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_query, bench_batch, bench_cutoff);
+criterion_main!(benches);
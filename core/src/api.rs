@@ -1,7 +1,10 @@
+use crate::checksum::DataSource;
+use crate::geojson_stream::{FeatureStream, open_source};
 use crate::structs::{AdressClean, MiljoeDataClean};
-use geojson::{Feature, GeoJson};
+use geojson::Feature;
 use rust_decimal::Decimal;
-use std::fs;
+use std::io::BufRead;
+use std::path::Path;
 
 pub type ApiResult = (Vec<AdressClean>, Vec<MiljoeDataClean>, Vec<MiljoeDataClean>);
 
@@ -116,46 +119,53 @@ impl DataLoader {
         })
     }
 
+    /// Hours of a taxa string such as "Taxa 3: 8–20 (8–16)" in `tid` form; the
+    /// parenthesised range (Saturdays and days before holidays) is kept in parentheses
     fn extract_time_from_taxa(taxa_str: &str) -> String {
-        // Look for pattern like "8–22" or "8–20" (with en-dash U+2013)
-        // Split on en-dash and extract the time range
-        let parts: Vec<&str> = taxa_str.split('–').collect();
-
-        if parts.len() >= 2 {
-            // Find digits before and after the dash
-            if let Some(before_dash) = parts.first() {
-                // Get the last number from before the dash (the start time)
-                let start_time = before_dash.split_whitespace().last().and_then(|s| {
-                    s.chars()
-                        .rev()
-                        .take_while(|c| c.is_ascii_digit())
-                        .collect::<String>()
-                        .chars()
-                        .rev()
-                        .collect::<String>()
-                        .parse::<u32>()
-                        .ok()
-                });
-
-                if let Some(start) = start_time
-                    && let Some(after_dash) = parts.get(1)
-                {
-                    // Get the first number after the dash (the end time)
-                    let end_time = after_dash
-                        .chars()
-                        .take_while(|c| c.is_ascii_digit())
-                        .collect::<String>()
-                        .parse::<u32>()
-                        .ok();
-
-                    if let Some(end) = end_time {
-                        return format!("{:02}:00–{:02}:00", start, end);
-                    }
+        let (regular, eve) = match taxa_str.split_once('(') {
+            Some((regular, rest)) => (regular, rest.split(')').next()),
+            None => (taxa_str, None),
+        };
+
+        match Self::hour_range(regular) {
+            Some((start, end)) => {
+                let mut tid = format!("{:02}:00–{:02}:00", start, end);
+                if let Some((start, end)) = eve.and_then(Self::hour_range) {
+                    tid.push_str(&format!(" ({:02}:00–{:02}:00)", start, end));
                 }
+                tid
             }
+            None => "00:00–23:59".to_string(),
         }
+    }
 
-        "00:00–23:59".to_string()
+    /// First hour range like "8–22" (with en-dash U+2013) in a taxa string
+    fn hour_range(text: &str) -> Option<(u32, u32)> {
+        let (before_dash, after_dash) = text.split_once('–')?;
+
+        // Get the last number from before the dash (the start time)
+        let start = before_dash
+            .split_whitespace()
+            .last()?
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .chars()
+            .rev()
+            .collect::<String>()
+            .parse::<u32>()
+            .ok()?;
+
+        // Get the first number after the dash (the end time)
+        let end = after_dash
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<u32>()
+            .ok()?;
+
+        Some((start, end))
     }
 
     /// Parse a parking feature and return all its segments as separate MiljoeDataClean entries
@@ -243,20 +253,11 @@ impl DataLoader {
         results
     }
 
+    /// Load addresses from a GeoJSON file, `-` for stdin; gzip input is decompressed
     pub fn load_addresses(path: &str) -> Result<Vec<AdressClean>, Box<dyn std::error::Error>> {
         println!("Loading addresses from: {}", path);
-        let content = fs::read_to_string(path)?;
-        let geojson: GeoJson = content.parse()?;
-
-        let addresses: Vec<AdressClean> = if let GeoJson::FeatureCollection(collection) = geojson {
-            collection
-                .features
-                .into_iter()
-                .filter_map(Self::parse_address_feature)
-                .collect()
-        } else {
-            return Err("Invalid GeoJSON format for addresses".into());
-        };
+        let addresses = Self::load_addresses_from_reader(open_source(path)?)
+            .map_err(|e| format!("Invalid GeoJSON format for addresses: {}", e))?;
 
         println!("Loaded {} addresses", addresses.len());
 
@@ -268,27 +269,31 @@ impl DataLoader {
         Ok(addresses)
     }
 
+    /// Parse addresses one feature at a time from a GeoJSON FeatureCollection stream
+    pub fn load_addresses_from_reader<R: BufRead>(
+        reader: R,
+    ) -> Result<Vec<AdressClean>, Box<dyn std::error::Error>> {
+        let mut addresses = Vec::new();
+        for feature in FeatureStream::new(reader) {
+            if let Some(address) = Self::parse_address_feature(feature?) {
+                addresses.push(address);
+            }
+        }
+        Ok(addresses)
+    }
+
+    /// Load parking segments from a GeoJSON file, `-` for stdin; gzip input is decompressed
     pub fn load_parking(
         path: &str,
         dataset_name: &str,
     ) -> Result<Vec<MiljoeDataClean>, Box<dyn std::error::Error>> {
         println!("\nLoading {} from: {}", dataset_name, path);
-        let content = fs::read_to_string(path)?;
-        let geojson: GeoJson = content.parse()?;
 
         // Determine if this is avgifter (fees) data
         let is_avgifter = dataset_name.to_lowercase().contains("avgift");
 
-        let parking: Vec<MiljoeDataClean> = if let GeoJson::FeatureCollection(collection) = geojson
-        {
-            collection
-                .features
-                .into_iter()
-                .flat_map(|f| Self::parse_parking_feature(f, is_avgifter)) // flat_map to expand segments
-                .collect()
-        } else {
-            return Err(format!("Invalid GeoJSON format for {}", dataset_name).into());
-        };
+        let parking = Self::load_parking_from_reader(open_source(path)?, is_avgifter)
+            .map_err(|e| format!("Invalid GeoJSON format for {}: {}", dataset_name, e))?;
 
         println!("Loaded {} {} segments", parking.len(), dataset_name);
 
@@ -303,13 +308,35 @@ impl DataLoader {
 
         Ok(parking)
     }
+
+    /// Parse parking segments one feature at a time from a GeoJSON FeatureCollection stream
+    pub fn load_parking_from_reader<R: BufRead>(
+        reader: R,
+        is_avgifter: bool,
+    ) -> Result<Vec<MiljoeDataClean>, Box<dyn std::error::Error>> {
+        let mut parking = Vec::new();
+        for feature in FeatureStream::new(reader) {
+            // One entry per segment for MultiLineString features
+            parking.extend(Self::parse_parking_feature(feature?, is_avgifter));
+        }
+        Ok(parking)
+    }
 }
 
 pub fn api() -> Result<ApiResult, Box<dyn std::error::Error>> {
     // Load from local JSON files (Malmö open data)
-    let addresses = DataLoader::load_addresses("data/adresser.json")?;
-    let miljodata = DataLoader::load_parking("data/miljoparkeringar.json", "Miljödata")?;
-    let parkering = DataLoader::load_parking("data/parkeringsavgifter.json", "Parkering avgifter")?;
+    api_from_dir("data")
+}
+
+/// Load all three datasets from `data_dir` using the file names in [`DataSource`]
+pub fn api_from_dir(data_dir: &str) -> Result<ApiResult, Box<dyn std::error::Error>> {
+    let dir = Path::new(data_dir);
+    let addresses = DataLoader::load_addresses(&dataset_path(dir, DataSource::Adresser))?;
+    let miljodata = DataLoader::load_parking(&dataset_path(dir, DataSource::Miljo), "Miljödata")?;
+    let parkering = DataLoader::load_parking(
+        &dataset_path(dir, DataSource::Parkering),
+        "Parkering avgifter",
+    )?;
 
     println!("\n✓ Data loading complete");
     println!("  Total addresses: {}", addresses.len());
@@ -319,9 +346,67 @@ pub fn api() -> Result<ApiResult, Box<dyn std::error::Error>> {
     Ok((addresses, miljodata, parkering))
 }
 
+/// Same as [`api_from_dir`] without printing progress, for callers that own stdout
+pub fn api_from_dir_quiet(data_dir: &str) -> Result<ApiResult, Box<dyn std::error::Error>> {
+    let dir = Path::new(data_dir);
+    let open = |source| open_source(&dataset_path(dir, source));
+    let addresses = DataLoader::load_addresses_from_reader(open(DataSource::Adresser)?)
+        .map_err(|e| format!("Invalid GeoJSON format for addresses: {}", e))?;
+    let miljodata = DataLoader::load_parking_from_reader(open(DataSource::Miljo)?, false)
+        .map_err(|e| format!("Invalid GeoJSON format for Miljödata: {}", e))?;
+    let parkering = DataLoader::load_parking_from_reader(open(DataSource::Parkering)?, true)
+        .map_err(|e| format!("Invalid GeoJSON format for Parkering avgifter: {}", e))?;
+
+    Ok((addresses, miljodata, parkering))
+}
+
 pub fn api_miljo_only()
 -> Result<(Vec<AdressClean>, Vec<MiljoeDataClean>), Box<dyn std::error::Error>> {
-    let addresses = DataLoader::load_addresses("data/adresser.json")?;
-    let miljodata = DataLoader::load_parking("data/miljoparkeringar.json", "Miljödata")?;
+    let dir = Path::new("data");
+    let addresses = DataLoader::load_addresses(&dataset_path(dir, DataSource::Adresser))?;
+    let miljodata = DataLoader::load_parking(&dataset_path(dir, DataSource::Miljo), "Miljödata")?;
     Ok((addresses, miljodata))
 }
+
+fn dataset_path(dir: &Path, source: DataSource) -> String {
+    dir.join(source.file_name()).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_load_from_reader_expands_multilinestring() {
+        let input = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                { "type": "Feature",
+                  "geometry": { "type": "MultiLineString", "coordinates": [
+                      [[13.00, 55.60], [13.01, 55.60]],
+                      [[13.02, 55.60], [13.03, 55.60]]
+                  ] },
+                  "properties": { "value": "Städning", "tid": "0800 - 1200", "day": 1 } }
+            ]
+        }"#;
+
+        let parking = DataLoader::load_parking_from_reader(Cursor::new(input), false).unwrap();
+        assert_eq!(parking.len(), 2);
+        assert_eq!(parking[1].dag, 1);
+        assert_eq!(parking[1].tid, "0800 - 1200");
+    }
+
+    #[test]
+    fn test_extract_time_from_taxa_keeps_eve_hours() {
+        assert_eq!(
+            DataLoader::extract_time_from_taxa("Taxa 3: 8–20 (8–16)"),
+            "08:00–20:00 (08:00–16:00)"
+        );
+        assert_eq!(
+            DataLoader::extract_time_from_taxa("Taxa 1: 8–22"),
+            "08:00–22:00"
+        );
+        assert_eq!(DataLoader::extract_time_from_taxa("Okänd"), "00:00–23:59");
+    }
+}
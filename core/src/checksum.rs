@@ -1,11 +1,88 @@
 //! Checksum verification for data sources
 //! Checks if remote data has changed since last fetch
 
+use crate::diff::{FeatureDiff, diff_features};
 use chrono::Utc;
-use reqwest::Response;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+
+pub const MILJO_URL: &str = "https://opendata.malmo.se/@fastighets-och-gatukontoret/miljoparkering/73490f00-0d71-4b17-903c-f77ab7664a53";
+pub const PARKERING_URL: &str = "https://opendata.malmo.se/@fastighets-och-gatukontoret/parkeringsavgifter/1a6bd68b-30ca-40a5-9d62-01e2a566982e";
+pub const ADRESSER_URL: &str = "https://opendata.malmo.se/@stadsbyggnadskontoret/adresser/caf1cee8-9af2-4a75-8fb7-f1d7cb11daeb";
+
+/// The three datasets published on the Malmö open data portal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    Miljo,
+    Parkering,
+    Adresser,
+}
+
+impl DataSource {
+    pub const ALL: [DataSource; 3] = [
+        DataSource::Miljo,
+        DataSource::Parkering,
+        DataSource::Adresser,
+    ];
+
+    /// File name of the dataset inside the data directory
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            DataSource::Miljo => "miljoparkeringar.json",
+            DataSource::Parkering => "parkeringsavgifter.json",
+            DataSource::Adresser => "adresser.json",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataSource::Miljo => "Miljödata",
+            DataSource::Parkering => "Parkering avgifter",
+            DataSource::Adresser => "Adresser",
+        }
+    }
+}
+
+/// HTTP cache validators from the last successful fetch of a source
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Result of a conditional GET
+pub enum RemoteContent {
+    NotModified,
+    Modified {
+        body: Vec<u8>,
+        validators: CacheValidators,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceStatus {
+    Changed,
+    Unchanged,
+    Error(String),
+}
+
+/// Per-source outcome of [`DataChecksum::check_remote`]
+#[derive(Debug, Clone)]
+pub struct SourceReport {
+    pub source: DataSource,
+    pub status: SourceStatus,
+    /// Feature-level changes relative to the local copy, when one was available
+    pub diff: Option<FeatureDiff>,
+    /// Downloaded content, unless the server answered 304 or the request failed
+    pub body: Option<Vec<u8>>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DataChecksum {
@@ -16,6 +93,8 @@ pub struct DataChecksum {
     pub adresser_url: String,
     pub adresser_checksum: String,
     pub last_checked: String,
+    #[serde(default)]
+    pub validators: BTreeMap<DataSource, CacheValidators>,
 }
 
 impl DataChecksum {
@@ -29,6 +108,52 @@ impl DataChecksum {
             adresser_url,
             adresser_checksum: String::new(),
             last_checked: Utc::now().to_rfc3339(),
+            validators: BTreeMap::new(),
+        }
+    }
+
+    /// Checksum record pointing at the Malmö open data portal
+    pub fn malmo() -> Self {
+        Self::new(
+            MILJO_URL.to_string(),
+            PARKERING_URL.to_string(),
+            ADRESSER_URL.to_string(),
+        )
+    }
+
+    pub fn url(&self, source: DataSource) -> &str {
+        match source {
+            DataSource::Miljo => &self.miljo_url,
+            DataSource::Parkering => &self.parkering_url,
+            DataSource::Adresser => &self.adresser_url,
+        }
+    }
+
+    pub fn checksum(&self, source: DataSource) -> &str {
+        match source {
+            DataSource::Miljo => &self.miljo_checksum,
+            DataSource::Parkering => &self.parkering_checksum,
+            DataSource::Adresser => &self.adresser_checksum,
+        }
+    }
+
+    /// Point a source at another URL; cache validators of the old URL are dropped
+    pub fn set_url(&mut self, source: DataSource, url: String) {
+        if self.url(source) != url {
+            self.validators.remove(&source);
+        }
+        match source {
+            DataSource::Miljo => self.miljo_url = url,
+            DataSource::Parkering => self.parkering_url = url,
+            DataSource::Adresser => self.adresser_url = url,
+        }
+    }
+
+    pub fn set_checksum(&mut self, source: DataSource, checksum: String) {
+        match source {
+            DataSource::Miljo => self.miljo_checksum = checksum,
+            DataSource::Parkering => self.parkering_checksum = checksum,
+            DataSource::Adresser => self.adresser_checksum = checksum,
         }
     }
 
@@ -49,6 +174,100 @@ impl DataChecksum {
         Ok(format!("{:x}", hasher.finalize()))
     }
 
+    /// GET `url`, sending If-None-Match / If-Modified-Since from `validators`
+    /// `file://` URLs are read directly and always count as modified.
+    pub async fn fetch_conditional(
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<RemoteContent, Box<dyn std::error::Error>> {
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(RemoteContent::Modified {
+                body: fs::read(path)?,
+                validators: CacheValidators::default(),
+            });
+        }
+
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(RemoteContent::NotModified);
+        }
+        let response = response.error_for_status()?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let validators = CacheValidators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+
+        Ok(RemoteContent::Modified {
+            body: response.bytes().await?.to_vec(),
+            validators,
+        })
+    }
+
+    /// Check every source with a conditional GET and update checksums and validators
+    ///
+    /// Sources whose content changed are diffed against `<local_dir>/<file_name>`
+    /// when `local_dir` is given and the file exists. A failing source is reported
+    /// as [`SourceStatus::Error`] and keeps its previous checksum.
+    pub async fn check_remote(&mut self, local_dir: Option<&Path>) -> Vec<SourceReport> {
+        let mut reports = Vec::new();
+
+        for source in DataSource::ALL {
+            let validators = self.validators.get(&source).cloned().unwrap_or_default();
+            let url = self.url(source).to_string();
+
+            let (status, diff, body) = match Self::fetch_conditional(&url, &validators).await {
+                Ok(RemoteContent::NotModified) => (SourceStatus::Unchanged, None, None),
+                Ok(RemoteContent::Modified { body, validators }) => {
+                    let mut hasher = Sha256::new();
+                    hasher.update(&body);
+                    let checksum = format!("{:x}", hasher.finalize());
+                    self.validators.insert(source, validators);
+
+                    if checksum == self.checksum(source) {
+                        (SourceStatus::Unchanged, None, Some(body))
+                    } else {
+                        self.set_checksum(source, checksum);
+                        let diff = local_dir
+                            .map(|dir| dir.join(source.file_name()))
+                            .filter(|path| path.exists())
+                            .and_then(|path| {
+                                let local = BufReader::new(File::open(path).ok()?);
+                                diff_features(local, Cursor::new(&body)).ok()
+                            });
+                        (SourceStatus::Changed, diff, Some(body))
+                    }
+                }
+                Err(e) => (SourceStatus::Error(e.to_string()), None, None),
+            };
+
+            reports.push(SourceReport {
+                source,
+                status,
+                diff,
+                body,
+            });
+        }
+
+        self.last_checked = Utc::now().to_rfc3339();
+        reports
+    }
+
     /// Check if any data source has changed
     pub fn has_changed(&self, old_checksum: &DataChecksum) -> bool {
         self.miljo_checksum != old_checksum.miljo_checksum
@@ -83,6 +302,11 @@ impl DataChecksum {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
 
     #[test]
     fn test_checksum_creation() {
@@ -95,4 +319,138 @@ mod tests {
         assert!(!cs.miljo_url.is_empty());
         assert!(!cs.last_checked.is_empty());
     }
+
+    /// Minimal HTTP server answering GET requests from a path → (etag, body) table
+    /// Returns its base URL
+    fn spawn_mock_server(routes: Arc<Mutex<HashMap<String, (String, String)>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or("/")
+                    .to_string();
+
+                let mut if_none_match = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("if-none-match")
+                    {
+                        if_none_match = Some(value.trim().to_string());
+                    }
+                }
+
+                let response = match routes.lock().unwrap().get(&path) {
+                    Some((etag, _)) if if_none_match.as_ref() == Some(etag) => {
+                        "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                    }
+                    Some((etag, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        etag,
+                        body.len(),
+                        body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    fn collection(names: &[&str]) -> String {
+        let features: Vec<String> = names
+            .iter()
+            .map(|name| {
+                format!(
+                    r#"{{"type":"Feature","geometry":null,"properties":{{"BELADRESS":"{}","POSTNR":"21142"}}}}"#,
+                    name
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+            features.join(",")
+        )
+    }
+
+    #[tokio::test]
+    async fn test_check_remote_conditional_and_per_source_status() {
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+        for path in ["/miljo", "/parkering", "/adresser"] {
+            routes.lock().unwrap().insert(
+                path.to_string(),
+                ("\"v1\"".to_string(), collection(&["Storgatan 1"])),
+            );
+        }
+        let base = spawn_mock_server(routes.clone());
+
+        let mut cs = DataChecksum::new(
+            format!("{}/miljo", base),
+            format!("{}/parkering", base),
+            format!("{}/adresser", base),
+        );
+
+        let reports = cs.check_remote(None).await;
+        assert!(reports.iter().all(|r| r.status == SourceStatus::Changed));
+        assert_eq!(
+            cs.validators[&DataSource::Miljo].etag.as_deref(),
+            Some("\"v1\"")
+        );
+
+        // Second check is answered with 304 for every source
+        let reports = cs.check_remote(None).await;
+        assert!(reports.iter().all(|r| r.status == SourceStatus::Unchanged));
+
+        // Publish a new address dataset and drop parkering
+        let local_dir = std::env::temp_dir().join(format!("amp_checksum_{}", std::process::id()));
+        fs::create_dir_all(&local_dir).unwrap();
+        fs::write(
+            local_dir.join(DataSource::Adresser.file_name()),
+            collection(&["Storgatan 1"]),
+        )
+        .unwrap();
+        {
+            let mut routes = routes.lock().unwrap();
+            routes.insert(
+                "/adresser".to_string(),
+                (
+                    "\"v2\"".to_string(),
+                    collection(&["Storgatan 1", "Storgatan 2"]),
+                ),
+            );
+            routes.remove("/parkering");
+        }
+
+        let reports = cs.check_remote(Some(&local_dir)).await;
+        let status = |source| reports.iter().find(|r| r.source == source).unwrap().clone();
+        assert_eq!(status(DataSource::Miljo).status, SourceStatus::Unchanged);
+        assert!(matches!(
+            status(DataSource::Parkering).status,
+            SourceStatus::Error(_)
+        ));
+        let adresser = status(DataSource::Adresser);
+        assert_eq!(adresser.status, SourceStatus::Changed);
+        let diff = adresser.diff.unwrap();
+        assert_eq!((diff.added, diff.removed, diff.modified), (1, 0, 0));
+
+        fs::remove_dir_all(&local_dir).unwrap();
+    }
 }
@@ -0,0 +1,197 @@
+//! Cross-algorithm comparison
+//! Runs several correlation algorithms over the same addresses and reports the addresses
+//! where their picks disagree
+
+use crate::correlation_algorithms::CorrelationAlgo;
+use crate::structs::{AdressClean, MiljoeDataClean};
+use rayon::prelude::*;
+
+/// Zone index and distance chosen by one algorithm, `None` if nothing within the cutoff
+pub type Pick = Option<(usize, f64)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DisagreementKind {
+    /// Some algorithms match the address, others do not
+    Match,
+    /// Algorithms pick different zones
+    Zone,
+    /// Algorithms pick the same zone at different distances
+    Distance,
+}
+
+impl DisagreementKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisagreementKind::Match => "match",
+            DisagreementKind::Zone => "zone",
+            DisagreementKind::Distance => "distance",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    /// Index of the address in the compared addresses
+    pub address: usize,
+    pub kind: DisagreementKind,
+    /// One pick per algorithm, in the order the algorithms were given
+    pub picks: Vec<Pick>,
+    /// Largest difference in meters between the distances of the matching algorithms
+    pub spread: f64,
+}
+
+/// Compare the picks for one address. Different zones count as the same pick when they
+/// are at the same distance (within `tolerance` meters) and carry the same info, which
+/// happens for duplicated segments
+pub fn classify(
+    picks: &[Pick],
+    zones: &[MiljoeDataClean],
+    tolerance: f64,
+) -> Option<DisagreementKind> {
+    let matched: Vec<(usize, f64)> = picks.iter().flatten().copied().collect();
+    if matched.is_empty() {
+        return None;
+    }
+    if matched.len() < picks.len() {
+        return Some(DisagreementKind::Match);
+    }
+
+    let (first_zone, first_distance) = matched[0];
+    let info = |zone: usize| zones.get(zone).map(|z| z.info.as_str());
+    let mut kind = None;
+    for &(zone, distance) in &matched[1..] {
+        let far = (distance - first_distance).abs() > tolerance;
+        if zone != first_zone && (far || info(zone) != info(first_zone)) {
+            return Some(DisagreementKind::Zone);
+        }
+        if far {
+            kind = Some(DisagreementKind::Distance);
+        }
+    }
+    kind
+}
+
+/// Run every algorithm over the addresses and return the disagreements, in address order
+pub fn compare_algorithms(
+    algorithms: &[Box<dyn CorrelationAlgo + Sync>],
+    addresses: &[AdressClean],
+    zones: &[MiljoeDataClean],
+    cutoff: f64,
+    tolerance: f64,
+) -> Vec<Disagreement> {
+    addresses
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, address)| {
+            let picks: Vec<Pick> = algorithms
+                .iter()
+                .map(|algo| {
+                    algo.correlate(address, zones)
+                        .filter(|(_, distance)| *distance <= cutoff)
+                })
+                .collect();
+            let kind = classify(&picks, zones, tolerance)?;
+            let distances: Vec<f64> = picks.iter().flatten().map(|(_, d)| *d).collect();
+            let spread = distances.iter().copied().fold(f64::MIN, f64::max)
+                - distances.iter().copied().fold(f64::MAX, f64::min);
+            Some(Disagreement {
+                address: index,
+                kind,
+                picks,
+                spread,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_algorithms::DistanceBasedAlgo;
+    use rust_decimal::Decimal;
+
+    fn zone(y: f64, info: &str) -> MiljoeDataClean {
+        let d = |v: f64| Decimal::try_from(v).unwrap();
+        MiljoeDataClean {
+            coordinates: [[d(13.0000), d(y)], [d(13.0010), d(y)]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    /// Always picks the last zone, to disagree with the brute-force search
+    struct LastZone;
+
+    impl CorrelationAlgo for LastZone {
+        fn correlate(&self, _: &AdressClean, zones: &[MiljoeDataClean]) -> Option<(usize, f64)> {
+            Some((zones.len() - 1, 40.0))
+        }
+
+        fn name(&self) -> &'static str {
+            "Last zone"
+        }
+    }
+
+    #[test]
+    fn test_classify_picks() {
+        let zones = vec![
+            zone(55.6001, "Tisdag"),
+            zone(55.6001, "Tisdag"),
+            zone(55.6002, "Onsdag"),
+        ];
+        assert_eq!(classify(&[None, None], &zones, 0.5), None);
+        assert_eq!(
+            classify(&[Some((0, 10.0)), Some((0, 10.2))], &zones, 0.5),
+            None
+        );
+        // Duplicate segments at the same distance are the same pick
+        assert_eq!(
+            classify(&[Some((0, 10.0)), Some((1, 10.0))], &zones, 0.5),
+            None
+        );
+        assert_eq!(
+            classify(&[Some((0, 10.0)), Some((0, 12.0))], &zones, 0.5),
+            Some(DisagreementKind::Distance)
+        );
+        assert_eq!(
+            classify(&[Some((0, 10.0)), Some((2, 10.0))], &zones, 0.5),
+            Some(DisagreementKind::Zone)
+        );
+        assert_eq!(
+            classify(&[Some((0, 10.0)), None], &zones, 0.5),
+            Some(DisagreementKind::Match)
+        );
+    }
+
+    #[test]
+    fn test_compare_algorithms() {
+        let d = |v: f64| Decimal::try_from(v).unwrap();
+        let address = AdressClean {
+            coordinates: [d(13.0005), d(55.6000)],
+            postnummer: "21142".to_string(),
+            adress: "Storgatan 1".to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        };
+        let zones = vec![zone(55.6001, "Tisdag"), zone(55.6003, "Onsdag")];
+        let algorithms: Vec<Box<dyn CorrelationAlgo + Sync>> =
+            vec![Box::new(DistanceBasedAlgo), Box::new(LastZone)];
+
+        let found = compare_algorithms(
+            &algorithms,
+            std::slice::from_ref(&address),
+            &zones,
+            50.0,
+            0.5,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, DisagreementKind::Zone);
+        assert_eq!(found[0].picks[1], Some((1, 40.0)));
+        assert!((found[0].spread - (40.0 - 11.1)).abs() < 0.1);
+
+        // With a cutoff below the second pick only the brute-force search matches
+        let found = compare_algorithms(&algorithms, &[address], &zones, 20.0, 0.5);
+        assert_eq!(found[0].kind, DisagreementKind::Match);
+    }
+}
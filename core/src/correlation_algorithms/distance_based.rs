@@ -3,38 +3,13 @@
 
 use crate::correlation_algorithms::CorrelationAlgo;
 use crate::structs::{AdressClean, MiljoeDataClean};
+use crate::zone_index::distance_point_to_segment;
 use rust_decimal::prelude::ToPrimitive;
 
 const MAX_DISTANCE_METERS: f64 = 50.0;
-const EARTH_RADIUS_M: f64 = 6371000.0;
 
 pub struct DistanceBasedAlgo;
 
-impl DistanceBasedAlgo {
-    fn distance_to_line(&self, point: [f64; 2], line_start: [f64; 2], line_end: [f64; 2]) -> f64 {
-        let line_vec = [line_end[0] - line_start[0], line_end[1] - line_start[1]];
-        let point_vec = [point[0] - line_start[0], point[1] - line_start[1]];
-
-        let line_len_sq = line_vec[0] * line_vec[0] + line_vec[1] * line_vec[1];
-
-        if line_len_sq == 0.0 {
-            // Degenerate line (point)
-            return haversine_distance(point, line_start);
-        }
-
-        // Project point onto line with clippy-compliant clamp
-        let t = ((point_vec[0] * line_vec[0] + point_vec[1] * line_vec[1]) / line_len_sq)
-            .clamp(0.0, 1.0);
-
-        let closest = [
-            line_start[0] + t * line_vec[0],
-            line_start[1] + t * line_vec[1],
-        ];
-
-        haversine_distance(point, closest)
-    }
-}
-
 impl CorrelationAlgo for DistanceBasedAlgo {
     fn correlate(
         &self,
@@ -59,7 +34,7 @@ impl CorrelationAlgo for DistanceBasedAlgo {
                     line.coordinates[1][1].to_f64()?,
                 ];
 
-                let dist = self.distance_to_line(point, line_start, line_end);
+                let dist = distance_point_to_segment(point, line_start, line_end);
 
                 // Only include if within threshold
                 (dist <= MAX_DISTANCE_METERS).then_some((idx, dist))
@@ -72,25 +47,9 @@ impl CorrelationAlgo for DistanceBasedAlgo {
     }
 }
 
-/// Calculate distance between two points using Haversine formula
-/// Returns distance in meters
-fn haversine_distance(point1: [f64; 2], point2: [f64; 2]) -> f64 {
-    let lat1 = point1[1].to_radians();
-    let lat2 = point2[1].to_radians();
-    let delta_lat = (point2[1] - point1[1]).to_radians();
-    let delta_lon = (point2[0] - point1[0]).to_radians();
-
-    let a =
-        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
-
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-
-    EARTH_RADIUS_M * c
-}
-
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use crate::zone_index::haversine_distance;
 
     #[test]
     fn test_haversine_distance() {
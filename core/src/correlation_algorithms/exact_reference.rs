@@ -0,0 +1,96 @@
+//! Exact reference algorithm
+//! Brute-force search over every segment in a local metric projection around the
+//! address. Slow, but without the approximations of the indexed algorithms, so it is
+//! the ground truth they are tested against
+
+use crate::correlation_algorithms::CorrelationAlgo;
+use crate::structs::{AdressClean, MiljoeDataClean};
+use rust_decimal::prelude::ToPrimitive;
+
+const EARTH_RADIUS_M: f64 = 6371000.0;
+
+pub struct ExactReferenceAlgo;
+
+impl ExactReferenceAlgo {
+    /// Distance in meters from a `[lon, lat]` point to a segment, measured in an
+    /// equirectangular projection centred on the point
+    pub fn distance(point: [f64; 2], start: [f64; 2], end: [f64; 2]) -> f64 {
+        let meters_per_lat = EARTH_RADIUS_M.to_radians();
+        let meters_per_lon = meters_per_lat * point[1].to_radians().cos();
+        let project = |p: [f64; 2]| {
+            [
+                (p[0] - point[0]) * meters_per_lon,
+                (p[1] - point[1]) * meters_per_lat,
+            ]
+        };
+        let [sx, sy] = project(start);
+        let [ex, ey] = project(end);
+
+        let (dx, dy) = (ex - sx, ey - sy);
+        let length_sq = dx * dx + dy * dy;
+        let t = if length_sq == 0.0 {
+            0.0
+        } else {
+            (-(sx * dx + sy * dy) / length_sq).clamp(0.0, 1.0)
+        };
+        (sx + t * dx).hypot(sy + t * dy)
+    }
+}
+
+impl CorrelationAlgo for ExactReferenceAlgo {
+    /// Nearest segment at any distance; ties go to the lowest index
+    fn correlate(
+        &self,
+        address: &AdressClean,
+        parking_lines: &[MiljoeDataClean],
+    ) -> Option<(usize, f64)> {
+        let point = [
+            address.coordinates[0].to_f64()?,
+            address.coordinates[1].to_f64()?,
+        ];
+
+        parking_lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let start = [
+                    line.coordinates[0][0].to_f64()?,
+                    line.coordinates[0][1].to_f64()?,
+                ];
+                let end = [
+                    line.coordinates[1][0].to_f64()?,
+                    line.coordinates[1][1].to_f64()?,
+                ];
+                Some((idx, Self::distance(point, start, end)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn name(&self) -> &'static str {
+        "Exact Reference"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_is_perpendicular_in_meters() {
+        // 0.001° of latitude is ~111.2 m
+        let d = ExactReferenceAlgo::distance([13.0005, 55.601], [13.0, 55.6], [13.001, 55.6]);
+        assert!((d - 111.19).abs() < 0.01, "{}", d);
+
+        // Diagonal segment: a foot of the perpendicular taken in degrees would be ~4.6 m off
+        let point = [13.0, 55.6005];
+        let (start, end) = ([13.0, 55.6], [13.001, 55.601]);
+        let exact = ExactReferenceAlgo::distance(point, start, end);
+        let haversine = crate::zone_index::distance_point_to_segment(point, start, end);
+        assert!((exact - 27.35).abs() < 0.01, "{}", exact);
+        assert!((exact - haversine).abs() < 0.01, "{} {}", exact, haversine);
+
+        // Beyond the end of the segment the distance is to the end point
+        let d = ExactReferenceAlgo::distance([13.002, 55.6], [13.0, 55.6], [13.001, 55.6]);
+        assert!((d - 62.9).abs() < 0.1, "{}", d);
+    }
+}
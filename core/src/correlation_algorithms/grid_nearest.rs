@@ -4,10 +4,11 @@
 
 use crate::correlation_algorithms::CorrelationAlgo;
 use crate::structs::{AdressClean, MiljoeDataClean};
+use crate::zone_index::distance_point_to_segment;
 use rust_decimal::prelude::ToPrimitive;
 use std::collections::HashMap;
 
-const CELL_SIZE: f64 = 0.0005; // ~50m in degrees at Malmö latitude
+const CELL_SIZE: f64 = 0.0005; // ~56m north-south, ~28m east-west at Malmö latitude
 const MAX_DISTANCE_METERS: f64 = 50.0;
 const EARTH_RADIUS_M: f64 = 6371000.0;
 
@@ -42,42 +43,20 @@ impl GridNearestAlgo {
         }
     }
 
-    /// Get all grid cells a line segment passes through using DDA algorithm
+    /// Get all grid cells in a line segment's bounding box, which covers every cell
+    /// the segment passes through
     fn line_cells(x1: f64, y1: f64, x2: f64, y2: f64, cell_size: f64) -> Vec<(i32, i32)> {
-        let mut cells = Vec::new();
+        let min_x = (x1.min(x2) / cell_size).floor() as i32;
+        let max_x = (x1.max(x2) / cell_size).floor() as i32;
+        let min_y = (y1.min(y2) / cell_size).floor() as i32;
+        let max_y = (y1.max(y2) / cell_size).floor() as i32;
 
-        let cell_x1 = (x1 / cell_size).floor() as i32;
-        let cell_y1 = (y1 / cell_size).floor() as i32;
-        let cell_x2 = (x2 / cell_size).floor() as i32;
-        let cell_y2 = (y2 / cell_size).floor() as i32;
-
-        // Simple approach: add start, end, and midpoint cells
-        cells.push((cell_x1, cell_y1));
-        cells.push((cell_x2, cell_y2));
-
-        // Add cells along the line
-        let mid_x = (x1 + x2) / 2.0;
-        let mid_y = (y1 + y2) / 2.0;
-        let mid_cell_x = (mid_x / cell_size).floor() as i32;
-        let mid_cell_y = (mid_y / cell_size).floor() as i32;
-        cells.push((mid_cell_x, mid_cell_y));
-
-        // For longer lines, add more intermediate points
-        let dx = (cell_x2 - cell_x1).abs();
-        let dy = (cell_y2 - cell_y1).abs();
-        let steps = dx.max(dy).max(1);
-
-        for i in 1..steps {
-            let t = i as f64 / steps as f64;
-            let x = x1 + t * (x2 - x1);
-            let y = y1 + t * (y2 - y1);
-            let cx = (x / cell_size).floor() as i32;
-            let cy = (y / cell_size).floor() as i32;
-            cells.push((cx, cy));
+        let mut cells = Vec::new();
+        for cx in min_x..=max_x {
+            for cy in min_y..=max_y {
+                cells.push((cx, cy));
+            }
         }
-
-        cells.sort_unstable();
-        cells.dedup();
         cells
     }
 
@@ -88,10 +67,17 @@ impl GridNearestAlgo {
         )
     }
 
-    fn get_nearby_cells(cell: (i32, i32)) -> Vec<(i32, i32)> {
-        let mut cells = Vec::with_capacity(9);
-        for dx in -1..=1 {
-            for dy in -1..=1 {
+    /// Cells within MAX_DISTANCE_METERS of a cell at the given latitude. A cell is
+    /// narrower east-west than north-south, so more columns than rows are searched
+    fn get_nearby_cells(cell: (i32, i32), lat: f64, cell_size: f64) -> Vec<(i32, i32)> {
+        let cell_height = cell_size * EARTH_RADIUS_M.to_radians();
+        let cell_width = cell_height * lat.to_radians().cos();
+        let reach_x = (MAX_DISTANCE_METERS / cell_width).ceil() as i32;
+        let reach_y = (MAX_DISTANCE_METERS / cell_height).ceil() as i32;
+
+        let mut cells = Vec::with_capacity(((2 * reach_x + 1) * (2 * reach_y + 1)) as usize);
+        for dx in -reach_x..=reach_x {
+            for dy in -reach_y..=reach_y {
                 cells.push((cell.0 + dx, cell.1 + dy));
             }
         }
@@ -111,7 +97,7 @@ impl CorrelationAlgo for GridNearestAlgo {
         ];
 
         let cell = Self::get_cell(point, self.cell_size);
-        let nearby_cells = Self::get_nearby_cells(cell);
+        let nearby_cells = Self::get_nearby_cells(cell, point[1], self.cell_size);
 
         let mut best: Option<(usize, f64)> = None;
 
@@ -129,7 +115,7 @@ impl CorrelationAlgo for GridNearestAlgo {
                         line.coordinates[1][1].to_f64()?,
                     ];
 
-                    let dist = distance_point_to_line(point, start, end);
+                    let dist = distance_point_to_segment(point, start, end);
 
                     // Only consider if within threshold
                     if dist <= MAX_DISTANCE_METERS && (best.is_none() || dist < best.unwrap().1) {
@@ -147,41 +133,6 @@ impl CorrelationAlgo for GridNearestAlgo {
     }
 }
 
-fn distance_point_to_line(point: [f64; 2], line_start: [f64; 2], line_end: [f64; 2]) -> f64 {
-    let line_vec = [line_end[0] - line_start[0], line_end[1] - line_start[1]];
-    let point_vec = [point[0] - line_start[0], point[1] - line_start[1]];
-
-    let line_len_sq = line_vec[0] * line_vec[0] + line_vec[1] * line_vec[1];
-
-    if line_len_sq == 0.0 {
-        return haversine_distance(point, line_start);
-    }
-
-    let t =
-        ((point_vec[0] * line_vec[0] + point_vec[1] * line_vec[1]) / line_len_sq).clamp(0.0, 1.0);
-
-    let closest = [
-        line_start[0] + t * line_vec[0],
-        line_start[1] + t * line_vec[1],
-    ];
-
-    haversine_distance(point, closest)
-}
-
-fn haversine_distance(point1: [f64; 2], point2: [f64; 2]) -> f64 {
-    let lat1 = point1[1].to_radians();
-    let lat2 = point2[1].to_radians();
-    let delta_lat = (point2[1] - point1[1]).to_radians();
-    let delta_lon = (point2[0] - point1[0]).to_radians();
-
-    let a =
-        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
-
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-
-    EARTH_RADIUS_M * c
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,7 @@
 //! Provides multiple algorithms for correlating addresses with parking data
 
 pub mod distance_based;
+pub mod exact_reference;
 pub mod grid_nearest;
 pub mod kdtree_spatial;
 pub mod overlapping_chunks;
@@ -26,6 +27,7 @@ pub trait CorrelationAlgo {
 }
 
 pub use distance_based::DistanceBasedAlgo;
+pub use exact_reference::ExactReferenceAlgo;
 pub use grid_nearest::GridNearestAlgo;
 pub use kdtree_spatial::KDTreeSpatialAlgo;
 pub use overlapping_chunks::OverlappingChunksAlgo;
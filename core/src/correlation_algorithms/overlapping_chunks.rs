@@ -3,13 +3,13 @@
 
 use crate::correlation_algorithms::CorrelationAlgo;
 use crate::structs::{AdressClean, MiljoeDataClean};
+use crate::zone_index::distance_point_to_segment;
 use rust_decimal::prelude::ToPrimitive;
 use std::collections::HashMap;
 
 const CHUNK_SIZE: f64 = 0.001; // ~100m in degrees at Malmö latitude
 const OVERLAP: f64 = 0.0005; // ~50m overlap
 const MAX_DISTANCE_METERS: f64 = 50.0;
-const EARTH_RADIUS_M: f64 = 6371000.0;
 
 pub struct OverlappingChunksAlgo {
     grid: SpatialGrid,
@@ -107,7 +107,7 @@ impl CorrelationAlgo for OverlappingChunksAlgo {
                     line.coordinates[1][1].to_f64()?,
                 ];
 
-                let dist = distance_point_to_line(point, line_start, line_end);
+                let dist = distance_point_to_segment(point, line_start, line_end);
 
                 // Only include if within threshold
                 (dist <= MAX_DISTANCE_METERS).then_some((idx, dist))
@@ -120,42 +120,6 @@ impl CorrelationAlgo for OverlappingChunksAlgo {
     }
 }
 
-/// Calculate perpendicular distance from point to line segment using Haversine
-fn distance_point_to_line(point: [f64; 2], line_start: [f64; 2], line_end: [f64; 2]) -> f64 {
-    let line_vec = [line_end[0] - line_start[0], line_end[1] - line_start[1]];
-    let point_vec = [point[0] - line_start[0], point[1] - line_start[1]];
-
-    let line_len_sq = line_vec[0] * line_vec[0] + line_vec[1] * line_vec[1];
-
-    if line_len_sq == 0.0 {
-        return haversine_distance(point, line_start);
-    }
-
-    let t =
-        ((point_vec[0] * line_vec[0] + point_vec[1] * line_vec[1]) / line_len_sq).clamp(0.0, 1.0);
-
-    let closest = [
-        line_start[0] + t * line_vec[0],
-        line_start[1] + t * line_vec[1],
-    ];
-
-    haversine_distance(point, closest)
-}
-
-fn haversine_distance(point1: [f64; 2], point2: [f64; 2]) -> f64 {
-    let lat1 = point1[1].to_radians();
-    let lat2 = point2[1].to_radians();
-    let delta_lat = (point2[1] - point1[1]).to_radians();
-    let delta_lon = (point2[0] - point1[0]).to_radians();
-
-    let a =
-        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
-
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-
-    EARTH_RADIUS_M * c
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
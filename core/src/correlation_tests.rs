@@ -204,6 +204,7 @@ mod tests {
             postnummer: "200 00".to_string(),
             miljo_match: Some((15.5, "Miljö Zone A".to_string())),
             parkering_match: None,
+            source: MatchSource::Algorithm,
         };
 
         assert!(result1.has_match(), "Should have match");
@@ -215,6 +216,7 @@ mod tests {
             postnummer: "200 00".to_string(),
             miljo_match: Some((20.0, "Miljö Zone B".to_string())),
             parkering_match: Some((35.0, "Parkering Zone A".to_string())),
+            source: MatchSource::Algorithm,
         };
 
         assert!(result2.has_match(), "Should have match");
@@ -230,6 +232,7 @@ mod tests {
             postnummer: "200 00".to_string(),
             miljo_match: None,
             parkering_match: None,
+            source: MatchSource::Algorithm,
         };
 
         assert!(!result3.has_match(), "Should have no match");
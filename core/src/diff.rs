@@ -0,0 +1,159 @@
+//! Feature-level diff between two versions of a GeoJSON dataset
+//! Features are matched by a stable key and compared by a hash of their content
+
+use crate::geojson_stream::FeatureStream;
+use geojson::Feature;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+/// Properties used as feature identity, in order of preference
+const ID_PROPERTIES: [&str; 6] = ["id", "ID", "OBJECTID", "objectid", "fid", "FID"];
+
+/// Summary of changes between two versions of a dataset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureDiff {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+}
+
+impl FeatureDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.modified == 0
+    }
+}
+
+/// Stable identity of a feature across dataset versions
+///
+/// Uses the feature id or a known id property, then the address key
+/// (BELADRESS + POSTNR), and finally the geometry itself.
+pub fn feature_key(feature: &Feature) -> String {
+    if let Some(id) = &feature.id {
+        return format!("id:{}", serde_json::to_string(id).unwrap_or_default());
+    }
+
+    if let Some(props) = &feature.properties {
+        for name in ID_PROPERTIES {
+            if let Some(value) = props.get(name).filter(|v| !v.is_null()) {
+                return format!("{}:{}", name, value);
+            }
+        }
+
+        if let (Some(adress), Some(postnr)) = (props.get("BELADRESS"), props.get("POSTNR")) {
+            return format!("adress:{}|{}", adress, postnr);
+        }
+    }
+
+    let geometry = feature
+        .geometry
+        .as_ref()
+        .map(|g| serde_json::to_string(g).unwrap_or_default())
+        .unwrap_or_default();
+    format!("geom:{}", hash_str(&geometry))
+}
+
+/// Hash of geometry and properties of a feature
+pub fn feature_hash(feature: &Feature) -> String {
+    let geometry = serde_json::to_string(&feature.geometry).unwrap_or_default();
+    let properties = serde_json::to_string(&feature.properties).unwrap_or_default();
+    hash_str(&format!("{}\n{}", geometry, properties))
+}
+
+fn hash_str(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Key → content hash for every feature in a stream
+/// Duplicate keys are disambiguated by their order of appearance
+pub fn feature_hashes<R: BufRead>(reader: R) -> io::Result<HashMap<String, String>> {
+    let mut hashes = HashMap::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for feature in FeatureStream::new(reader) {
+        let feature = feature?;
+        let key = feature_key(&feature);
+        let occurrence = seen.entry(key.clone()).or_default();
+        let unique_key = if *occurrence == 0 {
+            key
+        } else {
+            format!("{}#{}", key, occurrence)
+        };
+        *occurrence += 1;
+
+        hashes.insert(unique_key, feature_hash(&feature));
+    }
+
+    Ok(hashes)
+}
+
+/// Compare two versions of a FeatureCollection
+pub fn diff_features<A: BufRead, B: BufRead>(old: A, new: B) -> io::Result<FeatureDiff> {
+    let old_hashes = feature_hashes(old)?;
+    let new_hashes = feature_hashes(new)?;
+    Ok(diff_hashes(&old_hashes, &new_hashes))
+}
+
+pub fn diff_hashes(old: &HashMap<String, String>, new: &HashMap<String, String>) -> FeatureDiff {
+    let mut diff = FeatureDiff::default();
+
+    for (key, hash) in new {
+        match old.get(key) {
+            None => diff.added += 1,
+            Some(old_hash) if old_hash != hash => diff.modified += 1,
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.removed = old.keys().filter(|k| !new.contains_key(*k)).count();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn address(name: &str, x: f64) -> String {
+        format!(
+            r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{},55.6]}},"properties":{{"BELADRESS":"{}","POSTNR":"21142"}}}}"#,
+            x, name
+        )
+    }
+
+    fn collection(features: &[String]) -> String {
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+            features.join(",")
+        )
+    }
+
+    #[test]
+    fn test_diff_counts_added_removed_modified() {
+        let old = collection(&[
+            address("Storgatan 1", 13.0),
+            address("Storgatan 2", 13.0),
+            address("Storgatan 3", 13.0),
+        ]);
+        let new = collection(&[
+            address("Storgatan 1", 13.0),
+            address("Storgatan 2", 13.1), // moved
+            address("Storgatan 4", 13.0), // added, 3 removed
+        ]);
+
+        let diff = diff_features(Cursor::new(old), Cursor::new(new)).unwrap();
+        assert_eq!(
+            diff,
+            FeatureDiff {
+                added: 1,
+                removed: 1,
+                modified: 1,
+                unchanged: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use crate::correlation_algorithms::{
+        CorrelationAlgo, DistanceBasedAlgo, ExactReferenceAlgo, GridNearestAlgo, KDTreeSpatialAlgo,
+        OverlappingChunksAlgo, RTreeSpatialAlgo,
+    };
+    use crate::structs::*;
+    use crate::synthetic::{CityOptions, generate_city};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rust_decimal::Decimal;
+    use rust_decimal::prelude::ToPrimitive;
+
+    /// Number of random cities generated, one per seed
+    const SEEDS: u64 = 20;
+    const ADDRESSES_PER_CITY: usize = 300;
+    /// Distance cutoff the algorithms apply
+    const CUTOFF_M: f64 = 50.0;
+    /// Meters two picks may differ by and still count as the same nearest zone;
+    /// covers ties between segments and coordinates rounded to 7 decimals (~1 cm)
+    const TOLERANCE_M: f64 = 0.05;
+
+    /// Round to the precision of the source data
+    fn d(v: f64) -> Decimal {
+        Decimal::from_f64_retain(v).unwrap().round_dp(7)
+    }
+
+    fn point(coordinates: &[Decimal; 2]) -> [f64; 2] {
+        [
+            coordinates[0].to_f64().unwrap(),
+            coordinates[1].to_f64().unwrap(),
+        ]
+    }
+
+    fn exact_distance(address: &AdressClean, zone: &MiljoeDataClean) -> f64 {
+        ExactReferenceAlgo::distance(
+            point(&address.coordinates),
+            point(&zone.coordinates[0]),
+            point(&zone.coordinates[1]),
+        )
+    }
+
+    /// Random street network somewhere around Malmö: a jittered grid of streets split
+    /// into segments of varying length, plus a few diagonal streets across it
+    fn random_city(rng: &mut StdRng) -> (Vec<MiljoeDataClean>, Vec<AdressClean>) {
+        let origin = [
+            13.0 + rng.gen_range(-0.05..0.05),
+            55.6 + rng.gen_range(-0.03..0.03),
+        ];
+        let blocks = rng.gen_range(4..8);
+        let block = [rng.gen_range(0.0008..0.0020), rng.gen_range(0.0005..0.0012)];
+        let size = [blocks as f64 * block[0], blocks as f64 * block[1]];
+
+        let mut streets: Vec<([f64; 2], [f64; 2])> = Vec::new();
+        for i in 0..=blocks {
+            let x = origin[0] + i as f64 * block[0] + rng.gen_range(-0.0001..0.0001);
+            let y = origin[1] + i as f64 * block[1] + rng.gen_range(-0.0001..0.0001);
+            streets.push(([x, origin[1]], [x, origin[1] + size[1]]));
+            streets.push(([origin[0], y], [origin[0] + size[0], y]));
+        }
+        for _ in 0..rng.gen_range(1..4) {
+            let start = [origin[0], origin[1] + rng.gen_range(0.0..size[1])];
+            let end = [origin[0] + size[0], origin[1] + rng.gen_range(0.0..size[1])];
+            streets.push((start, end));
+        }
+
+        let mut zones = Vec::new();
+        for (start, end) in streets {
+            let mut t: f64 = 0.0;
+            while t < 1.0 {
+                let next = (t + rng.gen_range(0.05..0.3)).min(1.0);
+                let at = |t: f64| {
+                    [
+                        d(start[0] + t * (end[0] - start[0])),
+                        d(start[1] + t * (end[1] - start[1])),
+                    ]
+                };
+                zones.push(MiljoeDataClean {
+                    coordinates: [at(t), at(next)],
+                    info: format!("Zone {}", zones.len()),
+                    tid: "0800 - 1200".to_string(),
+                    dag: rng.gen_range(1..29),
+                });
+                t = next;
+            }
+        }
+
+        // Addresses spill over the edges so some are beyond the cutoff of every zone
+        let addresses = (0..ADDRESSES_PER_CITY)
+            .map(|i| AdressClean {
+                coordinates: [
+                    d(origin[0] + rng.gen_range(-0.001..size[0] + 0.001)),
+                    d(origin[1] + rng.gen_range(-0.0006..size[1] + 0.0006)),
+                ],
+                postnummer: "21142".to_string(),
+                adress: format!("Testgatan {}", i),
+                gata: "Testgatan".to_string(),
+                gatunummer: i.to_string(),
+            })
+            .collect();
+
+        (zones, addresses)
+    }
+
+    fn describe(zones: &[MiljoeDataClean], pick: Option<(usize, f64)>) -> String {
+        match pick {
+            Some((index, distance)) => format!(
+                "zone {} {:?} -> {:?} at {:.3} m",
+                index,
+                point(&zones[index].coordinates[0]),
+                point(&zones[index].coordinates[1]),
+                distance
+            ),
+            None => "no match".to_string(),
+        }
+    }
+
+    /// Check every indexed algorithm against the exact reference on one city
+    fn assert_match_exact_reference(
+        seed: u64,
+        zones: &[MiljoeDataClean],
+        addresses: &[AdressClean],
+    ) {
+        let algorithms: Vec<Box<dyn CorrelationAlgo>> = vec![
+            Box::new(DistanceBasedAlgo),
+            Box::new(OverlappingChunksAlgo::new(zones)),
+            Box::new(RTreeSpatialAlgo::new(zones)),
+            Box::new(KDTreeSpatialAlgo::new(zones)),
+            Box::new(GridNearestAlgo::new(zones)),
+        ];
+
+        for address in addresses {
+            let expected = ExactReferenceAlgo.correlate(address, zones);
+            let expected_distance = expected.map_or(f64::INFINITY, |(_, d)| d);
+
+            for algo in &algorithms {
+                let got = algo
+                    .correlate(address, zones)
+                    .filter(|(_, distance)| *distance <= CUTOFF_M);
+                let agrees = match got {
+                    // Any zone as close as the nearest one is a correct pick
+                    Some((index, distance)) => {
+                        let exact = exact_distance(address, &zones[index]);
+                        exact - expected_distance <= TOLERANCE_M
+                            && (distance - exact).abs() <= TOLERANCE_M
+                    }
+                    // Near the cutoff either answer is fine
+                    None => expected_distance > CUTOFF_M - TOLERANCE_M,
+                };
+                assert!(
+                    agrees,
+                    "{} disagrees with the exact reference (seed {})\n  \
+                     address: {} {:?}\n  expected: {}\n  got: {}",
+                    algo.name(),
+                    seed,
+                    address.adress,
+                    point(&address.coordinates),
+                    describe(zones, expected),
+                    describe(zones, got),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_indexed_algorithms_match_exact_reference() {
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (zones, addresses) = random_city(&mut rng);
+            assert_match_exact_reference(seed, &zones, &addresses);
+        }
+    }
+
+    #[test]
+    fn test_synthetic_city_matches_exact_reference() {
+        for seed in 0..2 {
+            let city = generate_city(&CityOptions {
+                addresses: 1000,
+                seed,
+                ..CityOptions::default()
+            });
+            assert_match_exact_reference(seed, &city.miljodata, &city.addresses);
+        }
+    }
+}
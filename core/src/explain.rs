@@ -0,0 +1,144 @@
+//! Candidate zones around an address, for explaining why a match was chosen
+//! Lists every segment within a radius with its distance, the closest point on the
+//! segment and the side of the segment the address lies on
+
+use crate::structs::MiljoeDataClean;
+use crate::zone_index::{Projection, ZoneIndex, closest_point_on_segment};
+
+/// Offset in meters below which an address counts as in line with the segment
+const IN_LINE_M: f64 = 0.01;
+
+/// Side of a segment, looking from its start towards its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    /// On the segment or its extension
+    InLine,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+            Side::InLine => "in line",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    /// Index of the zone in the dataset, as returned by the correlation algorithms
+    pub index: usize,
+    pub zone: &'a MiljoeDataClean,
+    /// Haversine distance in meters
+    pub distance: f64,
+    /// Closest `[lon, lat]` point of the segment
+    pub projection: [f64; 2],
+    pub side: Side,
+    /// Compass direction from the projection point to the address, e.g. "NE"
+    pub direction: &'static str,
+    /// The closest point is an end of the segment rather than a point along it
+    pub at_end: bool,
+}
+
+/// Every zone within `radius` meters of a `[lon, lat]` point, closest first
+pub fn candidates(index: &ZoneIndex, point: [f64; 2], radius: f64) -> Vec<Candidate<'_>> {
+    let projection = Projection::around(point[1]);
+    index
+        .within(point, radius)
+        .into_iter()
+        .filter_map(|m| {
+            let [start, end] = index.segment(m.index)?;
+            let closest = closest_point_on_segment(point, start, end);
+            let [sx, sy] = projection.project(start);
+            let [ex, ey] = projection.project(end);
+            let [px, py] = projection.project(point);
+            let [cx, cy] = projection.project(closest);
+
+            let length = ((ex - sx).powi(2) + (ey - sy).powi(2)).sqrt();
+            let offset = if length > 0.0 {
+                ((ex - sx) * (py - sy) - (ey - sy) * (px - sx)) / length
+            } else {
+                0.0
+            };
+            let side = if length == 0.0 || offset.abs() < IN_LINE_M {
+                Side::InLine
+            } else if offset > 0.0 {
+                Side::Left
+            } else {
+                Side::Right
+            };
+
+            Some(Candidate {
+                index: m.index,
+                zone: m.zone,
+                distance: m.distance,
+                projection: closest,
+                side,
+                direction: compass(px - cx, py - cy),
+                at_end: length > 0.0 && (closest == start || closest == end),
+            })
+        })
+        .collect()
+}
+
+/// Eight-point compass direction of an east/north offset in meters
+fn compass(east: f64, north: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    if east == 0.0 && north == 0.0 {
+        return "-";
+    }
+    let bearing = east.atan2(north).to_degrees().rem_euclid(360.0);
+    POINTS[((bearing + 22.5) / 45.0) as usize % 8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn zone(x1: f64, y1: f64, x2: f64, y2: f64, info: &str) -> MiljoeDataClean {
+        let d = |v: f64| Decimal::try_from(v).unwrap();
+        MiljoeDataClean {
+            coordinates: [[d(x1), d(y1)], [d(x2), d(y2)]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    #[test]
+    fn test_candidates_side_and_projection() {
+        let index = ZoneIndex::new(vec![
+            zone(13.0000, 55.6001, 13.0010, 55.6001, "North, eastbound"),
+            zone(13.0010, 55.5999, 13.0000, 55.5999, "South, westbound"),
+            zone(13.0010, 55.6000, 13.0020, 55.6000, "East, in line"),
+            zone(13.0100, 55.6100, 13.0110, 55.6100, "Far"),
+        ]);
+        let point = [13.0005, 55.6000];
+        let found = candidates(&index, point, 100.0);
+        assert_eq!(found.len(), 3);
+
+        let by_info = |info: &str| found.iter().find(|c| c.zone.info == info).unwrap();
+
+        // Address south of an eastbound street lies to its right
+        let north = by_info("North, eastbound");
+        assert_eq!(north.side, Side::Right);
+        assert_eq!(north.direction, "S");
+        assert!(!north.at_end);
+        assert!((north.projection[0] - 13.0005).abs() < 1e-9);
+        assert!((north.distance - 11.1).abs() < 0.1);
+
+        // Address north of a westbound street lies to its right as well
+        let south = by_info("South, westbound");
+        assert_eq!(south.side, Side::Right);
+        assert_eq!(south.direction, "N");
+
+        let east = by_info("East, in line");
+        assert_eq!(east.side, Side::InLine);
+        assert_eq!(east.direction, "W");
+        assert!(east.at_end);
+    }
+}
@@ -0,0 +1,420 @@
+//! Download of source datasets into the local data directory
+//! Files are written to a temp file, verified and renamed into place; the previous
+//! version is kept next to it as `<file>.prev` for rollback
+
+use crate::checksum::{DataChecksum, DataSource};
+use crate::geojson_stream::{FeatureStream, decompress};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Outcome of fetching a single dataset
+#[derive(Debug, Clone)]
+pub struct FetchReport {
+    pub source: DataSource,
+    pub path: PathBuf,
+    pub checksum: String,
+    pub bytes: u64,
+    pub features: usize,
+    /// False if the downloaded content matched the local copy
+    pub updated: bool,
+}
+
+pub struct Fetcher {
+    data_dir: PathBuf,
+    base_url: Option<String>,
+}
+
+impl Fetcher {
+    /// `<base_url>/<file_name>` is fetched per dataset; both `http(s)://` and `file://`
+    /// URLs are supported. The portal URLs are landing pages rather than downloads, so
+    /// fetching requires a base URL; `None` is enough for [`Fetcher::rollback`].
+    pub fn new(data_dir: impl Into<PathBuf>, base_url: Option<String>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            base_url,
+        }
+    }
+
+    pub fn dataset_path(&self, source: DataSource) -> PathBuf {
+        self.data_dir.join(source.file_name())
+    }
+
+    pub fn previous_path(&self, source: DataSource) -> PathBuf {
+        self.data_dir.join(format!("{}.prev", source.file_name()))
+    }
+
+    fn temp_path(&self, source: DataSource) -> PathBuf {
+        self.data_dir.join(format!("{}.tmp", source.file_name()))
+    }
+
+    /// URL a dataset is downloaded from
+    pub fn source_url(&self, source: DataSource) -> Result<String, Box<dyn std::error::Error>> {
+        let base = self.base_url.as_ref().ok_or(
+            "No base URL configured; the portal does not serve the GeoJSON files directly",
+        )?;
+        Ok(format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            source.file_name()
+        ))
+    }
+
+    /// Fetch all datasets and record their URLs and checksums in `checksums`
+    /// `check-updates` then checks the same URLs the recorded checksums came from
+    pub async fn fetch_all(
+        &self,
+        checksums: &mut DataChecksum,
+    ) -> Result<Vec<FetchReport>, Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.data_dir)?;
+
+        let mut reports = Vec::new();
+        for source in DataSource::ALL {
+            let url = self.source_url(source)?;
+            let report = self
+                .fetch_source(source, &url)
+                .await
+                .map_err(|e| format!("Failed to fetch {} from {}: {}", source.name(), url, e))?;
+            checksums.set_url(source, url);
+            checksums.set_checksum(source, report.checksum.clone());
+            reports.push(report);
+        }
+        checksums.last_checked = Utc::now().to_rfc3339();
+
+        Ok(reports)
+    }
+
+    /// Download one dataset, verify it and atomically replace the local copy
+    /// The local file is left untouched if it already matches the download; it is
+    /// compared on disk since `check-updates` records remote checksums without fetching.
+    /// The replaced version is copied to `<file>.prev` first, so the dataset is never
+    /// missing; retrying after a partly failed fetch keeps the real previous version.
+    pub async fn fetch_source(
+        &self,
+        source: DataSource,
+        url: &str,
+    ) -> Result<FetchReport, Box<dyn std::error::Error>> {
+        let temp = self.temp_path(source);
+        let (checksum, bytes) = match download_to(url, &temp).await {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+        };
+
+        self.replace_with_temp(source, checksum, bytes)
+    }
+
+    /// Same as [`Fetcher::fetch_source`] for content that was already downloaded,
+    /// e.g. the body of a conditional request
+    pub fn install(
+        &self,
+        source: DataSource,
+        body: &[u8],
+    ) -> Result<FetchReport, Box<dyn std::error::Error>> {
+        let temp = self.temp_path(source);
+        let written = File::create(&temp).and_then(|mut file| {
+            file.write_all(body)?;
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp);
+            return Err(e.into());
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(body);
+        self.replace_with_temp(
+            source,
+            format!("{:x}", hasher.finalize()),
+            body.len() as u64,
+        )
+    }
+
+    /// Verify the temp file of `source` and move it over the dataset if it differs
+    fn replace_with_temp(
+        &self,
+        source: DataSource,
+        checksum: String,
+        bytes: u64,
+    ) -> Result<FetchReport, Box<dyn std::error::Error>> {
+        let temp = self.temp_path(source);
+        let target = self.dataset_path(source);
+
+        // Verify the written file against the streamed digest and that it parses
+        let verified = DataChecksum::calculate_file_checksum(&temp.to_string_lossy())?;
+        if verified != checksum {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Checksum mismatch after writing {}", temp.display()).into());
+        }
+        let features = match count_features(&temp) {
+            Ok(count) => count,
+            Err(e) => {
+                let _ = fs::remove_file(&temp);
+                return Err(format!("Downloaded file is not valid GeoJSON: {}", e).into());
+            }
+        };
+
+        let existing = target.exists();
+        let updated = !existing
+            || DataChecksum::calculate_file_checksum(&target.to_string_lossy())? != checksum;
+        if updated {
+            if existing {
+                fs::copy(&target, self.previous_path(source))?;
+            }
+            fs::rename(&temp, &target)?;
+        } else {
+            fs::remove_file(&temp)?;
+        }
+
+        Ok(FetchReport {
+            source,
+            path: target,
+            checksum,
+            bytes,
+            features,
+            updated,
+        })
+    }
+
+    /// Swap a dataset with its previous version; returns false if no previous version exists
+    /// Rolling back twice restores the newer version again
+    pub fn rollback(&self, source: DataSource) -> io::Result<bool> {
+        let previous = self.previous_path(source);
+        if !previous.exists() {
+            return Ok(false);
+        }
+
+        // Copy rather than move the current version aside so the dataset stays in place
+        let current = self.dataset_path(source);
+        let swap = self.temp_path(source);
+        let had_current = current.exists();
+        if had_current {
+            fs::copy(&current, &swap)?;
+        }
+        fs::rename(&previous, &current)?;
+        if had_current {
+            fs::rename(&swap, &previous)?;
+        }
+
+        Ok(true)
+    }
+}
+
+/// Stream `url` into `path`, returning the SHA256 checksum and size of the content
+async fn download_to(url: &str, path: &Path) -> Result<(String, u64), Box<dyn std::error::Error>> {
+    let mut file = File::create(path)?;
+    let mut hasher = Sha256::new();
+    let mut bytes = 0u64;
+
+    if let Some(local) = url.strip_prefix("file://") {
+        let mut reader = BufReader::new(File::open(local)?);
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])?;
+            bytes += read as u64;
+        }
+    } else {
+        let mut response = reqwest::get(url).await?.error_for_status()?;
+        while let Some(chunk) = response.chunk().await? {
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+            bytes += chunk.len() as u64;
+        }
+    }
+
+    file.sync_all()?;
+    Ok((format!("{:x}", hasher.finalize()), bytes))
+}
+
+fn count_features(path: &Path) -> io::Result<usize> {
+    let reader = decompress(Box::new(BufReader::new(File::open(path)?)))?;
+    let mut count = 0;
+    for feature in FeatureStream::new(reader) {
+        feature?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::SourceStatus;
+
+    fn collection(name: &str) -> String {
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{{"type":"Feature","geometry":{{"type":"Point","coordinates":[13.0,55.6]}},"properties":{{"BELADRESS":"{}"}}}}]}}"#,
+            name
+        )
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("amp_fetch_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn publish(remote: &Path, name: &str) {
+        for source in DataSource::ALL {
+            fs::write(remote.join(source.file_name()), collection(name)).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_replace_and_rollback() {
+        let root = test_dir("rollback");
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish(&remote, "Version 1");
+
+        let fetcher = Fetcher::new(
+            root.join("data"),
+            Some(format!("file://{}", remote.display())),
+        );
+        let mut checksums = DataChecksum::malmo();
+
+        let reports = fetcher.fetch_all(&mut checksums).await.unwrap();
+        assert!(reports.iter().all(|r| r.updated && r.features == 1));
+        let first = checksums.checksum(DataSource::Adresser).to_string();
+        assert!(!first.is_empty());
+
+        // Same content again: nothing is replaced
+        let reports = fetcher.fetch_all(&mut checksums).await.unwrap();
+        assert!(reports.iter().all(|r| !r.updated));
+        assert!(!fetcher.previous_path(DataSource::Adresser).exists());
+
+        publish(&remote, "Version 2");
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+        assert_ne!(checksums.checksum(DataSource::Adresser), first);
+        let current = fetcher.dataset_path(DataSource::Adresser);
+        assert!(fs::read_to_string(&current).unwrap().contains("Version 2"));
+
+        assert!(fetcher.rollback(DataSource::Adresser).unwrap());
+        assert!(fs::read_to_string(&current).unwrap().contains("Version 1"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_failed_fetch_keeps_previous_version() {
+        let root = test_dir("retry");
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish(&remote, "Version 1");
+
+        let fetcher = Fetcher::new(
+            root.join("data"),
+            Some(format!("file://{}", remote.display())),
+        );
+        let mut checksums = DataChecksum::malmo();
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+
+        // Miljo is replaced, then parkering fails and the checksums are not saved
+        publish(&remote, "Version 2");
+        fs::write(
+            remote.join(DataSource::Parkering.file_name()),
+            "<html>502</html>",
+        )
+        .unwrap();
+        let mut unsaved = checksums.clone();
+        assert!(fetcher.fetch_all(&mut unsaved).await.is_err());
+
+        publish(&remote, "Version 2");
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+        let previous = fetcher.previous_path(DataSource::Miljo);
+        assert!(fs::read_to_string(previous).unwrap().contains("Version 1"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_after_fetch_is_unchanged() {
+        let root = test_dir("fetch_then_check");
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish(&remote, "Version 1");
+
+        let fetcher = Fetcher::new(
+            root.join("data"),
+            Some(format!("file://{}", remote.display())),
+        );
+        let mut checksums = DataChecksum::malmo();
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+
+        let reports = checksums.check_remote(None).await;
+        assert!(
+            reports.iter().all(|r| r.status == SourceStatus::Unchanged),
+            "{:?}",
+            reports
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_after_check_updates_replaces_file() {
+        let root = test_dir("check_then_fetch");
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish(&remote, "Version 1");
+
+        let base = format!("file://{}", remote.display());
+        let fetcher = Fetcher::new(root.join("data"), Some(base.clone()));
+        let url = |source: DataSource| format!("{}/{}", base, source.file_name());
+        let mut checksums = DataChecksum::new(
+            url(DataSource::Miljo),
+            url(DataSource::Parkering),
+            url(DataSource::Adresser),
+        );
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+
+        // check-updates records the new remote checksum before anything is downloaded
+        publish(&remote, "Version 2");
+        checksums.check_remote(None).await;
+
+        let reports = fetcher.fetch_all(&mut checksums).await.unwrap();
+        assert!(reports.iter().all(|r| r.updated));
+        let current = fetcher.dataset_path(DataSource::Adresser);
+        assert!(fs::read_to_string(current).unwrap().contains("Version 2"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalid_download_keeps_existing_file() {
+        let root = test_dir("invalid");
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish(&remote, "Good");
+
+        let fetcher = Fetcher::new(
+            root.join("data"),
+            Some(format!("file://{}", remote.display())),
+        );
+        let mut checksums = DataChecksum::malmo();
+        fetcher.fetch_all(&mut checksums).await.unwrap();
+
+        fs::write(
+            remote.join(DataSource::Miljo.file_name()),
+            "<html>502</html>",
+        )
+        .unwrap();
+        assert!(fetcher.fetch_all(&mut checksums).await.is_err());
+
+        let current = fetcher.dataset_path(DataSource::Miljo);
+        assert!(fs::read_to_string(current).unwrap().contains("Good"));
+        assert!(!fetcher.temp_path(DataSource::Miljo).exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Reverse geocoding
+//! Turns a device position into the nearest address and the zones restricting parking there
+
+use crate::schedule::{Window, next_window};
+use crate::structs::{AdressClean, MiljoeDataClean};
+use crate::zone_index::{Projection, ZoneIndex, ZoneMatch, haversine_distance};
+use chrono::NaiveDateTime;
+use rstar::RTree;
+use rstar::primitives::GeomWithData;
+use rust_decimal::prelude::ToPrimitive;
+
+/// Default distance in meters within which a zone applies to a position
+pub const DEFAULT_CUTOFF: f64 = 50.0;
+
+/// Address closest to a position
+#[derive(Debug, Clone, Copy)]
+pub struct NearestAddress<'a> {
+    /// Index into [`Geocoder::addresses`]
+    pub index: usize,
+    pub address: &'a AdressClean,
+    /// Haversine distance in meters
+    pub distance: f64,
+}
+
+/// Closest zone of each dataset within the cutoff of a position
+#[derive(Debug, Clone, Copy)]
+pub struct Restrictions<'a> {
+    pub miljo: Option<ZoneMatch<'a>>,
+    pub parkering: Option<ZoneMatch<'a>>,
+}
+
+impl Restrictions<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.miljo.is_none() && self.parkering.is_none()
+    }
+
+    /// Current or next window of each matched zone at `at`
+    pub fn windows(&self, at: NaiveDateTime) -> (Option<Window>, Option<Window>) {
+        let window = |m: &Option<ZoneMatch>| {
+            m.as_ref()
+                .and_then(|m| next_window(m.zone.dag, &m.zone.tid, at))
+        };
+        (window(&self.miljo), window(&self.parkering))
+    }
+}
+
+pub struct Geocoder {
+    addresses: Vec<AdressClean>,
+    projection: Projection,
+    points: RTree<GeomWithData<[f64; 2], usize>>,
+    miljo: ZoneIndex,
+    parkering: ZoneIndex,
+    cutoff: f64,
+}
+
+impl Geocoder {
+    pub fn new(
+        addresses: Vec<AdressClean>,
+        miljodata: Vec<MiljoeDataClean>,
+        parkering: Vec<MiljoeDataClean>,
+    ) -> Self {
+        let lonlat: Vec<Option<[f64; 2]>> = addresses
+            .iter()
+            .map(|a| Some([a.coordinates[0].to_f64()?, a.coordinates[1].to_f64()?]))
+            .collect();
+
+        let latitudes: Vec<f64> = lonlat.iter().flatten().map(|p| p[1]).collect();
+        let reference = if latitudes.is_empty() {
+            0.0
+        } else {
+            latitudes.iter().sum::<f64>() / latitudes.len() as f64
+        };
+        let projection = Projection::around(reference);
+
+        let points = lonlat
+            .iter()
+            .enumerate()
+            .filter_map(|(i, point)| Some(GeomWithData::new(projection.project((*point)?), i)))
+            .collect();
+
+        Self {
+            addresses,
+            projection,
+            points: RTree::bulk_load(points),
+            miljo: ZoneIndex::new(miljodata),
+            parkering: ZoneIndex::new(parkering),
+            cutoff: DEFAULT_CUTOFF,
+        }
+    }
+
+    /// Distance in meters within which a zone applies to a position
+    pub fn with_cutoff(mut self, cutoff: f64) -> Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    pub fn addresses(&self) -> &[AdressClean] {
+        &self.addresses
+    }
+
+    pub fn miljo(&self) -> &ZoneIndex {
+        &self.miljo
+    }
+
+    pub fn parkering(&self) -> &ZoneIndex {
+        &self.parkering
+    }
+
+    /// Address closest to a position, regardless of distance; None for non-finite input
+    pub fn nearest_address(&self, lat: f64, lon: f64) -> Option<NearestAddress<'_>> {
+        if !lat.is_finite() || !lon.is_finite() {
+            return None;
+        }
+        let point = [lon, lat];
+        let nearest = self
+            .points
+            .nearest_neighbor(&self.projection.project(point))?;
+        let address = &self.addresses[nearest.data];
+
+        Some(NearestAddress {
+            index: nearest.data,
+            address,
+            distance: haversine_distance(
+                point,
+                [
+                    address.coordinates[0].to_f64()?,
+                    address.coordinates[1].to_f64()?,
+                ],
+            ),
+        })
+    }
+
+    /// Zones within the cutoff of a position
+    pub fn restrictions_at(&self, lat: f64, lon: f64) -> Restrictions<'_> {
+        let point = [lon, lat];
+        Restrictions {
+            miljo: self.miljo.nearest(point, self.cutoff),
+            parkering: self.parkering.nearest(point, self.cutoff),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn d(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn address(name: &str, lon: &str, lat: &str) -> AdressClean {
+        AdressClean {
+            coordinates: [d(lon), d(lat)],
+            postnummer: "211 42".to_string(),
+            adress: name.to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        }
+    }
+
+    fn zone(lon: &str, lat: &str, info: &str, tid: &str, dag: u8) -> MiljoeDataClean {
+        MiljoeDataClean {
+            coordinates: [[d(lon), d(lat)], [d(lon) + d("0.001"), d(lat)]],
+            info: info.to_string(),
+            tid: tid.to_string(),
+            dag,
+        }
+    }
+
+    fn geocoder() -> Geocoder {
+        Geocoder::new(
+            vec![
+                address("Storgatan 1", "13.0000", "55.6000"),
+                address("Storgatan 3", "13.0010", "55.6000"),
+                address("Lilla Torg 1", "12.9900", "55.6050"),
+            ],
+            vec![zone("13.0005", "55.6001", "Städning", "0800 - 1200", 15)],
+            vec![zone("12.9895", "55.6050", "Taxa 3", "08:00–18:00", 0)],
+        )
+    }
+
+    #[test]
+    fn test_nearest_address() {
+        let geocoder = geocoder();
+
+        let nearest = geocoder.nearest_address(55.6000, 13.0008).unwrap();
+        assert_eq!(nearest.address.adress, "Storgatan 3");
+        assert!((nearest.distance - 12.6).abs() < 0.5);
+
+        let nearest = geocoder.nearest_address(55.6100, 12.9800).unwrap();
+        assert_eq!(nearest.address.adress, "Lilla Torg 1");
+
+        assert!(geocoder.nearest_address(f64::NAN, 13.0).is_none());
+        assert!(geocoder.nearest_address(55.6, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_restrictions_at() {
+        let geocoder = geocoder();
+        let at = NaiveDateTime::parse_from_str("2026-03-15 09:00", "%Y-%m-%d %H:%M").unwrap();
+
+        let restrictions = geocoder.restrictions_at(55.6000, 13.0008);
+        assert_eq!(restrictions.miljo.unwrap().zone.info, "Städning");
+        assert!(restrictions.parkering.is_none());
+        let (miljo, _) = restrictions.windows(at);
+        assert!(miljo.unwrap().is_active(at));
+
+        let restrictions = geocoder.restrictions_at(55.6050, 12.9900);
+        assert_eq!(restrictions.parkering.unwrap().zone.info, "Taxa 3");
+
+        assert!(geocoder.restrictions_at(55.7, 13.2).is_empty());
+    }
+}
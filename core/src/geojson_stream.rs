@@ -0,0 +1,334 @@
+//! Streaming GeoJSON reader
+//! Yields one Feature at a time from a FeatureCollection without holding the whole tree in memory
+
+use flate2::bufread::MultiGzDecoder;
+use geojson::Feature;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Open a GeoJSON source for streaming
+/// `-` reads from stdin, gzip-compressed input is detected from its magic bytes
+pub fn open_source(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin().lock()))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+
+    decompress(reader)
+}
+
+/// Wrap reader in a gzip decoder if the stream starts with the gzip magic bytes
+pub fn decompress(mut reader: Box<dyn BufRead>) -> io::Result<Box<dyn BufRead>> {
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(reader)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    BeforeFeatures,
+    InFeatures,
+    Done,
+}
+
+/// Iterator over the features of a GeoJSON FeatureCollection read from `reader`
+///
+/// Top-level members other than `features` (crs, bbox, name, ...) are skipped,
+/// and only one feature is deserialized at a time.
+pub struct FeatureStream<R> {
+    reader: R,
+    state: State,
+}
+
+impl<R: BufRead> FeatureStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            state: State::BeforeFeatures,
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = self
+            .peek()?
+            .ok_or_else(|| invalid("unexpected end of GeoJSON input"))?;
+        self.reader.consume(1);
+        Ok(byte)
+    }
+
+    /// Next byte that is not JSON whitespace
+    fn next_token(&mut self) -> io::Result<u8> {
+        loop {
+            let byte = self.next_byte()?;
+            if !byte.is_ascii_whitespace() {
+                return Ok(byte);
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> io::Result<()> {
+        let byte = self.next_token()?;
+        if byte == expected {
+            Ok(())
+        } else {
+            Err(invalid(&format!(
+                "expected '{}' but found '{}'",
+                expected as char, byte as char
+            )))
+        }
+    }
+
+    /// Read the remainder of a JSON string whose opening quote is already consumed
+    fn read_string(&mut self) -> io::Result<String> {
+        let mut raw = vec![b'"'];
+        loop {
+            let byte = self.next_byte()?;
+            raw.push(byte);
+            match byte {
+                b'\\' => raw.push(self.next_byte()?),
+                b'"' => break,
+                _ => {}
+            }
+        }
+        serde_json::from_slice(&raw).map_err(|e| invalid(&e.to_string()))
+    }
+
+    /// Skip a JSON value whose first byte is `first`
+    fn skip_value(&mut self, first: u8) -> io::Result<()> {
+        match first {
+            b'"' => {
+                self.read_string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 1usize;
+                while depth > 0 {
+                    match self.next_byte()? {
+                        b'"' => {
+                            self.read_string()?;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {
+                // Number, true, false or null: runs until the next delimiter
+                while let Some(byte) = self.peek()? {
+                    if byte == b',' || byte == b'}' || byte == b']' || byte.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.reader.consume(1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Scan top-level members until the `features` array is opened or the object ends
+    /// Returns false if the collection ended without (further) features
+    fn scan_members(&mut self, mut first_member: bool) -> io::Result<bool> {
+        loop {
+            let token = self.next_token()?;
+            match token {
+                b'}' => return Ok(false),
+                b',' if !first_member => {
+                    self.expect(b'"')?;
+                }
+                b'"' if first_member => {}
+                other => {
+                    return Err(invalid(&format!(
+                        "unexpected '{}' in FeatureCollection",
+                        other as char
+                    )));
+                }
+            }
+            first_member = false;
+
+            let key = self.read_string()?;
+            self.expect(b':')?;
+            let value_start = self.next_token()?;
+
+            match key.as_str() {
+                "features" => {
+                    if value_start != b'[' {
+                        return Err(invalid("'features' is not an array"));
+                    }
+                    return Ok(true);
+                }
+                "type" => {
+                    if value_start != b'"' {
+                        return Err(invalid("'type' is not a string"));
+                    }
+                    let kind = self.read_string()?;
+                    if kind != "FeatureCollection" {
+                        return Err(invalid(&format!(
+                            "expected a FeatureCollection, found {}",
+                            kind
+                        )));
+                    }
+                }
+                _ => self.skip_value(value_start)?,
+            }
+        }
+    }
+
+    /// Advance to the next feature; returns false once the array is exhausted
+    fn advance(&mut self, first_in_array: bool) -> io::Result<bool> {
+        let token = self.next_token()?;
+        match token {
+            b']' => {
+                // Validate the members after `features` (e.g. a trailing "type")
+                self.scan_after_features()?;
+                Ok(false)
+            }
+            b',' if !first_in_array => Ok(true),
+            b'{' if first_in_array => {
+                self.state = State::InFeatures;
+                Ok(true)
+            }
+            other => Err(invalid(&format!(
+                "unexpected '{}' in features array",
+                other as char
+            ))),
+        }
+    }
+
+    fn scan_after_features(&mut self) -> io::Result<()> {
+        if self.scan_members(false)? {
+            // A second `features` member is not valid GeoJSON
+            return Err(invalid("duplicate 'features' member"));
+        }
+        Ok(())
+    }
+
+    fn read_feature(&mut self, brace_consumed: bool) -> io::Result<Feature> {
+        if !brace_consumed {
+            self.expect(b'{')?;
+        }
+        // The opening brace has been consumed from the reader, so supply it again
+        let chained = io::Read::chain(&b"{"[..], &mut self.reader);
+        let mut de = serde_json::Deserializer::from_reader(chained);
+        Feature::deserialize(&mut de).map_err(|e| invalid(&e.to_string()))
+    }
+
+    fn next_feature(&mut self) -> io::Result<Option<Feature>> {
+        match self.state {
+            State::Done => Ok(None),
+            State::BeforeFeatures => {
+                self.expect(b'{')?;
+                if !self.scan_members(true)? {
+                    return Err(invalid("FeatureCollection has no 'features' array"));
+                }
+                if self.advance(true)? {
+                    self.read_feature(true).map(Some)
+                } else {
+                    self.state = State::Done;
+                    Ok(None)
+                }
+            }
+            State::InFeatures => {
+                if self.advance(false)? {
+                    self.read_feature(false).map(Some)
+                } else {
+                    self.state = State::Done;
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FeatureStream<R> {
+    type Item = io::Result<Feature>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_feature() {
+            Ok(Some(feature)) => Some(Ok(feature)),
+            Ok(None) => None,
+            Err(e) => {
+                // Stop after the first error; the stream position is unknown
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::{Cursor, Write};
+
+    const COLLECTION: &str = r#"{
+        "type": "FeatureCollection",
+        "name": "adresser",
+        "crs": { "type": "name", "properties": { "name": "urn:ogc:def:crs:OGC:1.3:CRS84" } },
+        "bbox": [12.9, 55.5, 13.1, 55.7],
+        "features": [
+            { "type": "Feature", "geometry": { "type": "Point", "coordinates": [13.0, 55.6] },
+              "properties": { "BELADRESS": "Storgatan 1", "note": "has ] and } \" inside" } },
+            { "type": "Feature", "geometry": { "type": "Point", "coordinates": [13.1, 55.6] },
+              "properties": { "BELADRESS": "Storgatan 2" } }
+        ]
+    }"#;
+
+    fn names(reader: Box<dyn BufRead>) -> Vec<String> {
+        FeatureStream::new(reader)
+            .map(|f| {
+                f.expect("valid feature")
+                    .property("BELADRESS")
+                    .and_then(|v| v.as_str())
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streams_features_and_skips_other_members() {
+        let reader: Box<dyn BufRead> = Box::new(Cursor::new(COLLECTION.as_bytes()));
+        assert_eq!(names(reader), vec!["Storgatan 1", "Storgatan 2"]);
+    }
+
+    #[test]
+    fn test_gzip_input_is_detected() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(COLLECTION.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let reader = decompress(Box::new(Cursor::new(compressed))).unwrap();
+        assert_eq!(names(reader), vec!["Storgatan 1", "Storgatan 2"]);
+    }
+
+    #[test]
+    fn test_rejects_non_feature_collection() {
+        let input = r#"{ "type": "Feature", "geometry": null, "properties": {} }"#;
+        let mut stream = FeatureStream::new(Cursor::new(input.as_bytes()));
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_empty_features_array() {
+        let input = r#"{ "features": [], "type": "FeatureCollection" }"#;
+        let mut stream = FeatureStream::new(Cursor::new(input.as_bytes()));
+        assert!(stream.next().is_none());
+    }
+}
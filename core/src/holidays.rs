@@ -0,0 +1,144 @@
+//! Swedish public holidays ("röda dagar")
+//! Street cleaning is not performed on public holidays, and paid parking is free on
+//! Sundays and holidays with reduced hours on the days before
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+/// Easter Sunday in the Gregorian calendar (anonymous Gregorian computus)
+pub fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// First `weekday` on or after a date
+fn weekday_from(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let offset = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Days::new(u64::from(offset))
+}
+
+/// Public holidays of a year with their names, in date order
+pub fn public_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day);
+    let Some(easter) = easter_sunday(year) else {
+        return Vec::new();
+    };
+    let from_easter = |days: i64| easter + chrono::Duration::days(days);
+
+    let mut holidays: Vec<(NaiveDate, &'static str)> = [
+        (date(1, 1), "Nyårsdagen"),
+        (date(1, 6), "Trettondedag jul"),
+        (Some(from_easter(-2)), "Långfredagen"),
+        (Some(easter), "Påskdagen"),
+        (Some(from_easter(1)), "Annandag påsk"),
+        (date(5, 1), "Första maj"),
+        (Some(from_easter(39)), "Kristi himmelsfärdsdag"),
+        (Some(from_easter(49)), "Pingstdagen"),
+        (date(6, 6), "Sveriges nationaldag"),
+        (
+            date(6, 20).map(|d| weekday_from(d, Weekday::Sat)),
+            "Midsommardagen",
+        ),
+        (
+            date(10, 31).map(|d| weekday_from(d, Weekday::Sat)),
+            "Alla helgons dag",
+        ),
+        (date(12, 25), "Juldagen"),
+        (date(12, 26), "Annandag jul"),
+    ]
+    .into_iter()
+    .filter_map(|(date, name)| Some((date?, name)))
+    .collect();
+
+    holidays.sort_by_key(|(date, _)| *date);
+    holidays
+}
+
+/// Name of the public holiday on a date
+pub fn holiday_name(date: NaiveDate) -> Option<&'static str> {
+    public_holidays(date.year())
+        .into_iter()
+        .find(|(holiday, _)| *holiday == date)
+        .map(|(_, name)| name)
+}
+
+pub fn is_public_holiday(date: NaiveDate) -> bool {
+    holiday_name(date).is_some()
+}
+
+/// Sunday or public holiday ("sön- och helgdag")
+pub fn is_red_day(date: NaiveDate) -> bool {
+    date.weekday() == Weekday::Sun || is_public_holiday(date)
+}
+
+/// Saturday or the day before a public holiday, when parenthesised parking hours apply
+/// ("lördag och dag före sön- och helgdag"); red days themselves are not included
+pub fn is_day_before_red_day(date: NaiveDate) -> bool {
+    if is_red_day(date) {
+        return false;
+    }
+    date.weekday() == Weekday::Sat || date.succ_opt().is_some_and(is_public_holiday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_easter_and_movable_feasts() {
+        let easters = [
+            (2019, "2019-04-21"),
+            (2024, "2024-03-31"),
+            (2025, "2025-04-20"),
+            (2026, "2026-04-05"),
+            (2027, "2027-03-28"),
+            (2038, "2038-04-25"),
+        ];
+        for (year, easter) in easters {
+            assert_eq!(easter_sunday(year), Some(d(easter)), "{}", year);
+        }
+
+        assert_eq!(holiday_name(d("2026-04-03")), Some("Långfredagen"));
+        assert_eq!(holiday_name(d("2026-04-06")), Some("Annandag påsk"));
+        assert_eq!(
+            holiday_name(d("2026-05-14")),
+            Some("Kristi himmelsfärdsdag")
+        );
+        assert_eq!(holiday_name(d("2026-05-24")), Some("Pingstdagen"));
+        assert_eq!(holiday_name(d("2025-06-21")), Some("Midsommardagen"));
+        assert_eq!(holiday_name(d("2026-06-20")), Some("Midsommardagen"));
+        assert_eq!(holiday_name(d("2024-11-02")), Some("Alla helgons dag"));
+        assert_eq!(holiday_name(d("2026-10-31")), Some("Alla helgons dag"));
+        assert_eq!(public_holidays(2026).len(), 13);
+        assert!(!is_public_holiday(d("2026-06-19"))); // Midsommarafton
+    }
+
+    #[test]
+    fn test_red_days_and_eves() {
+        assert!(is_red_day(d("2026-03-15"))); // Sunday
+        assert!(is_red_day(d("2026-12-25")));
+        assert!(!is_red_day(d("2026-03-16")));
+
+        assert!(is_day_before_red_day(d("2026-03-14"))); // Saturday
+        assert!(is_day_before_red_day(d("2026-12-24"))); // Julafton, a Thursday
+        assert!(is_day_before_red_day(d("2026-05-13"))); // Before Kristi himmelsfärd
+        assert!(!is_day_before_red_day(d("2026-12-25")));
+        assert!(!is_day_before_red_day(d("2026-03-17")));
+    }
+}
@@ -0,0 +1,409 @@
+//! iCalendar export of restriction schedules
+//! Each recurring restriction of an address becomes a VEVENT with an RRULE, in Swedish local time
+
+use crate::holidays::{is_day_before_red_day, is_public_holiday, is_red_day};
+use crate::schedule::{DailyWindow, Hours, Window, next_window, parse_hours};
+use crate::structs::{AdressInfo, MiljoeDataClean};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use sha2::{Digest, Sha256};
+
+const PRODID: &str = "-//AMP//Parkeringsrestriktioner//SV";
+const TZID: &str = "Europe/Stockholm";
+
+/// Maximum line length in octets before folding (RFC 5545 §3.1)
+const LINE_LIMIT: usize = 75;
+
+/// How far ahead holiday occurrences are excluded (and eve occurrences listed)
+const EXCEPTION_MONTHS: u32 = 36;
+
+/// Central European time with the EU daylight saving rules
+const VTIMEZONE: [&str; 17] = [
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/Stockholm",
+    "BEGIN:STANDARD",
+    "DTSTART:19701025T030000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "TZOFFSETFROM:+0200",
+    "TZOFFSETTO:+0100",
+    "TZNAME:CET",
+    "END:STANDARD",
+    "BEGIN:DAYLIGHT",
+    "DTSTART:19700329T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "TZOFFSETFROM:+0100",
+    "TZOFFSETTO:+0200",
+    "TZNAME:CEST",
+    "END:DAYLIGHT",
+    "END:VTIMEZONE",
+];
+
+/// A restriction repeating on a day of month (`dag`), or every day when `dag` is 0
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecurringRestriction {
+    pub dag: u8,
+    pub tid: String,
+    pub info: String,
+}
+
+impl From<&MiljoeDataClean> for RecurringRestriction {
+    fn from(zone: &MiljoeDataClean) -> Self {
+        Self {
+            dag: zone.dag,
+            tid: zone.tid.clone(),
+            info: zone.info.clone(),
+        }
+    }
+}
+
+impl From<&AdressInfo> for RecurringRestriction {
+    fn from(row: &AdressInfo) -> Self {
+        Self {
+            dag: row.dag,
+            tid: row.tid.clone(),
+            info: row.info.clone(),
+        }
+    }
+}
+
+/// One VEVENT of a restriction
+struct Event {
+    /// Tells the events of one restriction apart in their UIDs; empty for a single event
+    part: &'static str,
+    first: Window,
+    rrule: Option<String>,
+    /// Extra occurrences with the duration of the first
+    rdates: Vec<String>,
+    exdates: Vec<String>,
+}
+
+/// Calendar with one recurring event per distinct restriction of an address
+///
+/// The first occurrence is the current or next window at `from` (local time);
+/// restrictions whose `tid` cannot be parsed are left out. Every-day restrictions with
+/// parenthesised hours are split into the events of [`sign_events`]. `stamp` is the
+/// creation time.
+pub fn address_calendar(
+    address: &str,
+    restrictions: &[RecurringRestriction],
+    from: NaiveDateTime,
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!(
+            "X-WR-CALNAME:{}",
+            escape(&format!("Gatustädning {}", address))
+        ),
+    ];
+    lines.extend(VTIMEZONE.iter().map(|line| line.to_string()));
+
+    let mut seen = Vec::new();
+    for restriction in restrictions {
+        if seen.contains(&restriction) {
+            continue;
+        }
+        seen.push(restriction);
+
+        let events = match parse_hours(&restriction.tid) {
+            Some(Hours {
+                regular,
+                eve: Some(eve),
+            }) if restriction.dag == 0 => sign_events(regular, eve, from),
+            _ => {
+                let Some(first) = next_window(restriction.dag, &restriction.tid, from) else {
+                    continue;
+                };
+                let rrule = match restriction.dag {
+                    0 => "FREQ=DAILY".to_string(),
+                    dag => format!("FREQ=MONTHLY;BYMONTHDAY={}", dag),
+                };
+                vec![Event {
+                    part: "",
+                    first,
+                    rrule: Some(rrule),
+                    rdates: Vec::new(),
+                    exdates: holiday_exceptions(restriction.dag, first.start),
+                }]
+            }
+        };
+
+        for event in events {
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}", event_uid(address, restriction, event.part)),
+                format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+                format!("DTSTART;TZID={}:{}", TZID, local_time(event.first.start)),
+                format!("DTEND;TZID={}:{}", TZID, local_time(event.first.end)),
+            ]);
+            if let Some(rrule) = &event.rrule {
+                lines.push(format!("RRULE:{}", rrule));
+            }
+            if !event.rdates.is_empty() {
+                lines.push(format!("RDATE;TZID={}:{}", TZID, event.rdates.join(",")));
+            }
+            if !event.exdates.is_empty() {
+                lines.push(format!("EXDATE;TZID={}:{}", TZID, event.exdates.join(",")));
+            }
+            lines.extend([
+                format!("SUMMARY:{}", escape(&format!("Gatustädning {}", address))),
+                format!("LOCATION:{}", escape(&format!("{}, Malmö", address))),
+                format!("DESCRIPTION:{}", escape(restriction.info.trim())),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Events of an every-day restriction with parenthesised hours, following the sign:
+/// `regular` hours on weekdays, `eve` hours on Saturdays and on weekdays before a red
+/// day, and nothing on Sundays and holidays
+///
+/// Weekdays and Saturdays are weekly rules with the red days and eves excluded; the
+/// weekday eves are listed as single dates up to [`EXCEPTION_MONTHS`] ahead.
+fn sign_events(regular: DailyWindow, eve: DailyWindow, from: NaiveDateTime) -> Vec<Event> {
+    let mut weekdays: Vec<Window> = Vec::new();
+    let mut saturdays: Vec<Window> = Vec::new();
+    let mut eves: Vec<Window> = Vec::new();
+    let mut weekdays_off: Vec<NaiveDate> = Vec::new();
+    let mut saturdays_off: Vec<NaiveDate> = Vec::new();
+
+    // Start a day early so a window running past midnight is still found
+    let Some(start) = from.date().checked_sub_days(Days::new(1)) else {
+        return Vec::new();
+    };
+    let end = from
+        .date()
+        .checked_add_months(Months::new(EXCEPTION_MONTHS))
+        .unwrap_or(start);
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let saturday = date.weekday() == Weekday::Sat;
+        let weekday = !saturday && date.weekday() != Weekday::Sun;
+        let upcoming = |hours: DailyWindow| Some(hours.on_date(date)).filter(|w| w.end > from);
+
+        if is_red_day(date) {
+            if saturday {
+                saturdays_off.push(date);
+            } else if weekday {
+                weekdays_off.push(date);
+            }
+        } else if is_day_before_red_day(date) {
+            if saturday {
+                saturdays.extend(upcoming(eve));
+            } else {
+                weekdays_off.push(date);
+                eves.extend(upcoming(eve));
+            }
+        } else if weekday {
+            weekdays.extend(upcoming(regular));
+        }
+    }
+
+    // Excluded dates after the first occurrence, at the rule's start time
+    let exdates = |first: &Window, hours: DailyWindow, off: &[NaiveDate]| -> Vec<String> {
+        off.iter()
+            .map(|date| hours.on_date(*date).start)
+            .filter(|at| *at > first.start)
+            .map(local_time)
+            .collect()
+    };
+
+    let mut events = Vec::new();
+    if let Some(first) = weekdays.first() {
+        events.push(Event {
+            part: "weekdays",
+            first: *first,
+            rrule: Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string()),
+            rdates: Vec::new(),
+            exdates: exdates(first, regular, &weekdays_off),
+        });
+    }
+    if let Some(first) = saturdays.first() {
+        events.push(Event {
+            part: "saturdays",
+            first: *first,
+            rrule: Some("FREQ=WEEKLY;BYDAY=SA".to_string()),
+            rdates: Vec::new(),
+            exdates: exdates(first, eve, &saturdays_off),
+        });
+    }
+    if let Some((first, rest)) = eves.split_first() {
+        events.push(Event {
+            part: "eves",
+            first: *first,
+            rrule: None,
+            rdates: rest.iter().map(|window| local_time(window.start)).collect(),
+            exdates: Vec::new(),
+        });
+    }
+    events
+}
+
+/// Occurrences of a monthly event that fall on public holidays, when no cleaning is done
+/// (every-day events without parenthesised hours apply on holidays too)
+fn holiday_exceptions(dag: u8, first: NaiveDateTime) -> Vec<String> {
+    if dag == 0 {
+        return Vec::new();
+    }
+    (0..EXCEPTION_MONTHS)
+        .filter_map(|months| first.checked_add_months(Months::new(months)))
+        .filter(|at| at.day() == u32::from(dag) && is_public_holiday(at.date()))
+        .map(local_time)
+        .collect()
+}
+
+fn local_time(at: NaiveDateTime) -> String {
+    at.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Stable across exports, so re-importing updates events instead of duplicating them
+fn event_uid(address: &str, restriction: &RecurringRestriction, part: &str) -> String {
+    let mut key = format!(
+        "{}|{}|{}|{}",
+        address, restriction.dag, restriction.tid, restriction.info
+    );
+    if !part.is_empty() {
+        key = format!("{}|{}", key, part);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    format!("{}@amp", &format!("{:x}", hasher.finalize())[..16])
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line into CRLF-terminated lines of at most 75 octets
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn restriction(dag: u8, tid: &str, info: &str) -> RecurringRestriction {
+        RecurringRestriction {
+            dag,
+            tid: tid.to_string(),
+            info: info.to_string(),
+        }
+    }
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_address_calendar_events() {
+        let stamp = at("2026-03-10 12:00").and_utc();
+        let calendar = address_calendar(
+            "Lokgatan 11B",
+            &[
+                restriction(15, "0800 - 1200", "Städning; den 15:e"),
+                restriction(15, "0800 - 1200", "Städning; den 15:e"),
+                restriction(0, "", ""),
+            ],
+            at("2026-03-10 12:00"),
+            stamp,
+        );
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(calendar.contains("DTSTART;TZID=Europe/Stockholm:20260315T080000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Stockholm:20260315T120000\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=MONTHLY;BYMONTHDAY=15\r\n"));
+        assert!(calendar.contains("DTSTAMP:20260310T120000Z\r\n"));
+        assert!(calendar.contains("DESCRIPTION:Städning\\; den 15:e\r\n"));
+        assert!(calendar.contains("LOCATION:Lokgatan 11B\\, Malmö\r\n"));
+        assert!(!calendar.contains("EXDATE"));
+
+        // Juldagen, Nationaldagen and Trettondedag jul are excluded
+        let calendar = address_calendar(
+            "Lokgatan 11B",
+            &[
+                restriction(25, "0800 - 1200", ""),
+                restriction(6, "0800 - 1200", ""),
+            ],
+            at("2026-03-10 12:00"),
+            stamp,
+        )
+        .replace("\r\n ", "");
+        assert!(calendar.contains("EXDATE;TZID=Europe/Stockholm:20261225T080000,20271225T080000"));
+        assert!(calendar.contains("EXDATE;TZID=Europe/Stockholm:20260606T080000,20270106T080000"));
+    }
+
+    #[test]
+    fn test_sign_hours_split_into_weekday_saturday_and_eve_events() {
+        let calendar = address_calendar(
+            "Lokgatan 11B",
+            &[restriction(0, "08:00–20:00 (08:00–16:00)", "Taxa 3")],
+            at("2026-05-11 12:00"),
+            at("2026-05-11 12:00").and_utc(),
+        )
+        .replace("\r\n ", "");
+
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 3);
+        assert!(!calendar.contains("FREQ=DAILY"));
+
+        // Weekdays: the Wednesday eve and Kristi himmelsfärd are excluded
+        assert!(calendar.contains("DTSTART;TZID=Europe/Stockholm:20260511T080000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Stockholm:20260511T200000\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n"));
+        assert!(calendar.contains("EXDATE;TZID=Europe/Stockholm:20260513T080000,20260514T080000,"));
+
+        // Saturdays with the reduced hours, except Nationaldagen
+        assert!(calendar.contains("DTSTART;TZID=Europe/Stockholm:20260516T080000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Stockholm:20260516T160000\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=SA\r\n"));
+        assert!(calendar.contains("EXDATE;TZID=Europe/Stockholm:20260606T080000,"));
+
+        // Weekday eves as single dates with the reduced hours
+        assert!(calendar.contains("DTSTART;TZID=Europe/Stockholm:20260513T080000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Stockholm:20260513T160000\r\n"));
+        assert!(calendar.contains("RDATE;TZID=Europe/Stockholm:20260605T080000,"));
+    }
+
+    #[test]
+    fn test_fold_long_lines() {
+        let line = format!("DESCRIPTION:{}", "å".repeat(50));
+        let folded = fold(&line);
+
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= LINE_LIMIT);
+        }
+        let unfolded = folded.replace("\r\n ", "");
+        assert_eq!(unfolded, format!("{}\r\n", line));
+    }
+}
@@ -0,0 +1,356 @@
+//! Incremental re-correlation support
+//! Records fingerprints of the inputs of a correlation run so the next run can
+//! re-correlate only the addresses affected by changed source data
+
+use crate::correlation_algorithms::{CorrelationAlgo, RTreeSpatialAlgo};
+use crate::structs::{AdressClean, CorrelationResult, MiljoeDataClean};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+
+pub const DEFAULT_METADATA_PATH: &str = "correlation_run.json";
+
+/// Key identifying an address across runs
+pub fn address_key(address: &AdressClean) -> String {
+    format!("{}|{}", address.adress, address.postnummer)
+}
+
+/// Key of a correlation result, matching [`address_key`]
+pub fn result_key(result: &CorrelationResult) -> String {
+    format!("{}|{}", result.address, result.postnummer)
+}
+
+/// Fingerprint of the address fields that influence correlation
+pub fn address_fingerprint(address: &AdressClean) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(
+        format!(
+            "{},{}|{}|{}",
+            address.coordinates[0], address.coordinates[1], address.gata, address.gatunummer
+        )
+        .as_bytes(),
+    );
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// Metadata written next to the Parquet output of a correlation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub created: String,
+    pub algorithm: String,
+    pub cutoff: f64,
+    /// address key → fingerprint
+    pub addresses: BTreeMap<String, String>,
+    /// zone id → segment [[lon, lat], [lon, lat]]
+    pub miljo_zones: BTreeMap<String, [[f64; 2]; 2]>,
+    pub parkering_zones: BTreeMap<String, [[f64; 2]; 2]>,
+    /// Changes relative to the previous run, if this run was incremental
+    #[serde(default)]
+    pub changes: Option<ChangeSet>,
+}
+
+/// Inputs that differ between two runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub added_addresses: Vec<String>,
+    pub removed_addresses: Vec<String>,
+    pub changed_addresses: Vec<String>,
+    pub added_zones: Vec<String>,
+    pub removed_zones: Vec<String>,
+    /// Unchanged addresses within cutoff of an added or removed zone
+    #[serde(default)]
+    pub nearby_addresses: usize,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.added_addresses.is_empty()
+            && self.removed_addresses.is_empty()
+            && self.changed_addresses.is_empty()
+            && self.added_zones.is_empty()
+            && self.removed_zones.is_empty()
+    }
+}
+
+fn zone_segments(zones: &[MiljoeDataClean]) -> BTreeMap<String, [[f64; 2]; 2]> {
+    zones
+        .iter()
+        .filter_map(|zone| {
+            let c = &zone.coordinates;
+            Some((
+                zone.zone_id(),
+                [
+                    [c[0][0].to_f64()?, c[0][1].to_f64()?],
+                    [c[1][0].to_f64()?, c[1][1].to_f64()?],
+                ],
+            ))
+        })
+        .collect()
+}
+
+fn key_diff(
+    old: &BTreeMap<String, [[f64; 2]; 2]>,
+    new: &BTreeMap<String, [[f64; 2]; 2]>,
+) -> (Vec<String>, Vec<String>) {
+    let added = new.keys().filter(|k| !old.contains_key(*k)).cloned();
+    let removed = old.keys().filter(|k| !new.contains_key(*k)).cloned();
+    (added.collect(), removed.collect())
+}
+
+impl RunMetadata {
+    pub fn new(
+        algorithm: &str,
+        cutoff: f64,
+        addresses: &[AdressClean],
+        miljodata: &[MiljoeDataClean],
+        parkering: &[MiljoeDataClean],
+    ) -> Self {
+        Self {
+            created: Utc::now().to_rfc3339(),
+            algorithm: algorithm.to_string(),
+            cutoff,
+            addresses: addresses
+                .iter()
+                .map(|a| (address_key(a), address_fingerprint(a)))
+                .collect(),
+            miljo_zones: zone_segments(miljodata),
+            parkering_zones: zone_segments(parkering),
+            changes: None,
+        }
+    }
+
+    /// Whether results of `previous` can be patched instead of recomputed
+    pub fn compatible_with(&self, previous: &RunMetadata) -> bool {
+        self.algorithm == previous.algorithm && self.cutoff == previous.cutoff
+    }
+
+    /// Addresses and zones that were added, removed or changed since `previous`
+    pub fn changes_since(&self, previous: &RunMetadata) -> ChangeSet {
+        let mut changes = ChangeSet::default();
+
+        for (key, fingerprint) in &self.addresses {
+            match previous.addresses.get(key) {
+                None => changes.added_addresses.push(key.clone()),
+                Some(old) if old != fingerprint => changes.changed_addresses.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed_addresses = previous
+            .addresses
+            .keys()
+            .filter(|k| !self.addresses.contains_key(*k))
+            .cloned()
+            .collect();
+
+        for (old, new) in [
+            (&previous.miljo_zones, &self.miljo_zones),
+            (&previous.parkering_zones, &self.parkering_zones),
+        ] {
+            let (added, removed) = key_diff(old, new);
+            changes.added_zones.extend(added);
+            changes.removed_zones.extend(removed);
+        }
+
+        changes
+    }
+
+    /// Segments of all added or removed zones, taken from whichever run contains them
+    fn changed_segments(
+        &self,
+        previous: &RunMetadata,
+        changes: &ChangeSet,
+    ) -> Vec<MiljoeDataClean> {
+        let lookup = |id: &String| {
+            self.miljo_zones
+                .get(id)
+                .or_else(|| self.parkering_zones.get(id))
+                .or_else(|| previous.miljo_zones.get(id))
+                .or_else(|| previous.parkering_zones.get(id))
+        };
+
+        changes
+            .added_zones
+            .iter()
+            .chain(changes.removed_zones.iter())
+            .filter_map(lookup)
+            .filter_map(|segment| {
+                let coordinates = [
+                    [
+                        Decimal::try_from(segment[0][0]).ok()?,
+                        Decimal::try_from(segment[0][1]).ok()?,
+                    ],
+                    [
+                        Decimal::try_from(segment[1][0]).ok()?,
+                        Decimal::try_from(segment[1][1]).ok()?,
+                    ],
+                ];
+                Some(MiljoeDataClean {
+                    coordinates,
+                    info: String::new(),
+                    tid: String::new(),
+                    dag: 0,
+                })
+            })
+            .collect()
+    }
+
+    /// Addresses that must be re-correlated: added or changed addresses and
+    /// addresses within `cutoff` of an added or removed zone.
+    /// Updates `changes.nearby_addresses`.
+    pub fn affected_addresses<'a>(
+        &self,
+        previous: &RunMetadata,
+        changes: &mut ChangeSet,
+        addresses: &'a [AdressClean],
+    ) -> Vec<&'a AdressClean> {
+        let direct: HashSet<&String> = changes
+            .added_addresses
+            .iter()
+            .chain(changes.changed_addresses.iter())
+            .collect();
+
+        let segments = self.changed_segments(previous, changes);
+        let index = (!segments.is_empty()).then(|| RTreeSpatialAlgo::new(&segments));
+
+        let mut nearby = 0;
+        let affected = addresses
+            .iter()
+            .filter(|address| {
+                if direct.contains(&address_key(address)) {
+                    return true;
+                }
+                let close = index.as_ref().is_some_and(|index| {
+                    index
+                        .correlate(address, &segments)
+                        .is_some_and(|(_, dist)| dist <= self.cutoff)
+                });
+                if close {
+                    nearby += 1;
+                }
+                close
+            })
+            .collect();
+
+        changes.nearby_addresses = nearby;
+        affected
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Replace the rows of re-correlated addresses and drop removed addresses
+pub fn patch_results(
+    previous: Vec<CorrelationResult>,
+    updated: Vec<CorrelationResult>,
+    removed: &[String],
+) -> Vec<CorrelationResult> {
+    let removed: HashSet<&String> = removed.iter().collect();
+    let mut updated: HashMap<String, CorrelationResult> =
+        updated.into_iter().map(|r| (result_key(&r), r)).collect();
+
+    let mut patched: Vec<CorrelationResult> = previous
+        .into_iter()
+        .filter(|r| !removed.contains(&result_key(r)))
+        .map(|r| updated.remove(&result_key(&r)).unwrap_or(r))
+        .collect();
+
+    // Addresses that did not exist in the previous run
+    let mut added: Vec<CorrelationResult> = updated.into_values().collect();
+    added.sort_by(|a, b| a.address.cmp(&b.address));
+    patched.extend(added);
+
+    patched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::MatchSource;
+    use std::str::FromStr;
+
+    fn address(name: &str, lon: &str, lat: &str) -> AdressClean {
+        AdressClean {
+            coordinates: [
+                Decimal::from_str(lon).unwrap(),
+                Decimal::from_str(lat).unwrap(),
+            ],
+            postnummer: "211 42".to_string(),
+            adress: name.to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        }
+    }
+
+    fn zone(lon: &str, lat: &str, info: &str) -> MiljoeDataClean {
+        let x = Decimal::from_str(lon).unwrap();
+        let y = Decimal::from_str(lat).unwrap();
+        MiljoeDataClean {
+            coordinates: [[x, y], [x + Decimal::from_str("0.0003").unwrap(), y]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    #[test]
+    fn test_only_addresses_near_changed_zones_are_affected() {
+        let addresses = vec![
+            address("Near", "13.0001", "55.6001"),
+            address("Far", "13.0200", "55.6200"),
+        ];
+        let old_zones = vec![
+            zone("13.0000", "55.6000", "Old"),
+            zone("13.0200", "55.6201", "Kept"),
+        ];
+        let new_zones = vec![
+            zone("13.0000", "55.6000", "New"),
+            zone("13.0200", "55.6201", "Kept"),
+        ];
+
+        let previous = RunMetadata::new("KDTree", 50.0, &addresses, &old_zones, &[]);
+        let current = RunMetadata::new("KDTree", 50.0, &addresses, &new_zones, &[]);
+
+        let mut changes = current.changes_since(&previous);
+        assert_eq!(changes.added_zones.len(), 1);
+        assert_eq!(changes.removed_zones.len(), 1);
+        assert!(changes.added_addresses.is_empty());
+
+        let affected = current.affected_addresses(&previous, &mut changes, &addresses);
+        let names: Vec<_> = affected.iter().map(|a| a.adress.as_str()).collect();
+        assert_eq!(names, vec!["Near"]);
+        assert_eq!(changes.nearby_addresses, 1);
+    }
+
+    #[test]
+    fn test_patch_results_replaces_and_removes() {
+        let result = |name: &str, dist: f64| CorrelationResult {
+            address: name.to_string(),
+            postnummer: "211 42".to_string(),
+            miljo_match: Some((dist, "Zone".to_string())),
+            parkering_match: None,
+            source: MatchSource::Algorithm,
+        };
+
+        let previous = vec![result("A", 10.0), result("B", 20.0), result("C", 30.0)];
+        let updated = vec![result("B", 5.0), result("D", 1.0)];
+        let patched = patch_results(previous, updated, &["C|211 42".to_string()]);
+
+        let rows: Vec<_> = patched
+            .iter()
+            .map(|r| (r.address.as_str(), r.miljo_match.as_ref().unwrap().0))
+            .collect();
+        assert_eq!(rows, vec![("A", 10.0), ("B", 5.0), ("D", 1.0)]);
+    }
+}
@@ -1,11 +1,28 @@
 pub mod api;
 pub mod benchmark;
 pub mod checksum;
+pub mod compare;
 pub mod correlation;
 pub mod correlation_algorithms;
+pub mod diff;
 pub mod error;
+pub mod explain;
+pub mod fetch;
+pub mod geocode;
+pub mod geojson_stream;
+pub mod holidays;
+pub mod ics;
+pub mod incremental;
+pub mod overrides;
 pub mod parquet;
+pub mod report;
+pub mod schedule;
+pub mod search;
 pub mod structs;
+pub mod synthetic;
+pub mod zone_index;
 
 #[cfg(test)]
 mod correlation_tests;
+#[cfg(test)]
+mod differential_tests;
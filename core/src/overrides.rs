@@ -0,0 +1,317 @@
+//! Manual overrides applied on top of algorithmic correlation
+//! Reviewers' corrections live in a versioned JSON file keyed by address and postnummer;
+//! zones are referenced by [`MiljoeDataClean::zone_id`]
+
+use crate::structs::{AdressClean, CorrelationResult, MatchSource, MiljoeDataClean};
+use crate::zone_index::{distance_point_to_segment, segment_of};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+pub const DEFAULT_OVERRIDES_PATH: &str = "overrides.json";
+const OVERRIDES_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dataset {
+    Miljo,
+    Parkering,
+}
+
+impl Dataset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dataset::Miljo => "miljödata",
+            Dataset::Parkering => "parkering",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum OverrideAction {
+    /// Match this zone segment whatever its distance
+    Zone { zone_id: String },
+    /// No zone of the dataset applies to the address
+    NoMatch,
+    /// Keep the matched zone but replace its schedule text
+    Schedule { info: String },
+}
+
+/// Correction for one address in one dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Override {
+    pub address: String,
+    pub postnummer: String,
+    pub dataset: Dataset,
+    #[serde(flatten)]
+    pub action: OverrideAction,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+}
+
+impl Override {
+    fn label(&self) -> String {
+        format!(
+            "{} ({}) {}",
+            self.address,
+            self.postnummer,
+            self.dataset.name()
+        )
+    }
+}
+
+/// Overrides applied to a set of results
+#[derive(Debug, Default, PartialEq)]
+pub struct OverrideReport {
+    pub applied: usize,
+    /// Overrides that could not be applied, e.g. referencing zones no longer in the data
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideSet {
+    pub version: u32,
+    pub overrides: Vec<Override>,
+}
+
+impl Default for OverrideSet {
+    fn default() -> Self {
+        Self {
+            version: OVERRIDES_VERSION,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// Address and postnummer digits, case ignored
+fn override_key(address: &str, postnummer: &str) -> String {
+    let digits: String = postnummer.chars().filter(char::is_ascii_digit).collect();
+    format!("{}|{}", address.trim().to_lowercase(), digits)
+}
+
+fn zones_by_id(zones: &[MiljoeDataClean]) -> HashMap<String, &MiljoeDataClean> {
+    zones.iter().map(|z| (z.zone_id(), z)).collect()
+}
+
+impl OverrideSet {
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let overrides: Self = serde_json::from_str(&content)?;
+        if overrides.version > OVERRIDES_VERSION {
+            return Err(format!(
+                "{} has version {}, newer than supported {}",
+                path, overrides.version, OVERRIDES_VERSION
+            )
+            .into());
+        }
+        Ok(overrides)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Apply the overrides in order and mark the changed results as [`MatchSource::Override`]
+    ///
+    /// A forced zone's distance is measured from the address in `addresses`.
+    pub fn apply(
+        &self,
+        results: &mut [CorrelationResult],
+        addresses: &[AdressClean],
+        miljodata: &[MiljoeDataClean],
+        parkering: &[MiljoeDataClean],
+    ) -> OverrideReport {
+        let mut report = OverrideReport::default();
+        if self.overrides.is_empty() {
+            return report;
+        }
+
+        let rows: HashMap<String, usize> = results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (override_key(&r.address, &r.postnummer), i))
+            .collect();
+        let points: HashMap<String, [f64; 2]> = addresses
+            .iter()
+            .filter_map(|a| {
+                let point = [a.coordinates[0].to_f64()?, a.coordinates[1].to_f64()?];
+                Some((override_key(&a.adress, &a.postnummer), point))
+            })
+            .collect();
+        let miljo_zones = zones_by_id(miljodata);
+        let parkering_zones = zones_by_id(parkering);
+
+        for entry in &self.overrides {
+            let key = override_key(&entry.address, &entry.postnummer);
+            let Some(&row) = rows.get(&key) else {
+                report
+                    .warnings
+                    .push(format!("{}: address not in results", entry.label()));
+                continue;
+            };
+            let result = &mut results[row];
+            let (slot, zones) = match entry.dataset {
+                Dataset::Miljo => (&mut result.miljo_match, &miljo_zones),
+                Dataset::Parkering => (&mut result.parkering_match, &parkering_zones),
+            };
+
+            match &entry.action {
+                OverrideAction::Zone { zone_id } => {
+                    let Some(zone) = zones.get(zone_id) else {
+                        report.warnings.push(format!(
+                            "{}: zone {} is no longer in the data",
+                            entry.label(),
+                            zone_id
+                        ));
+                        continue;
+                    };
+                    let distance = points
+                        .get(&key)
+                        .zip(segment_of(zone))
+                        .map(|(point, [start, end])| distance_point_to_segment(*point, start, end))
+                        .unwrap_or_default();
+                    *slot = Some((distance, zone.info.clone()));
+                }
+                OverrideAction::NoMatch => *slot = None,
+                OverrideAction::Schedule { info } => {
+                    let Some((_, current)) = slot else {
+                        report
+                            .warnings
+                            .push(format!("{}: no match to correct", entry.label()));
+                        continue;
+                    };
+                    *current = info.clone();
+                }
+            }
+            result.source = MatchSource::Override;
+            report.applied += 1;
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn zone(lon: &str, info: &str) -> MiljoeDataClean {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        MiljoeDataClean {
+            coordinates: [[d(lon), d("55.6000")], [d(lon), d("55.6010")]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    fn result(address: &str, miljo: Option<(f64, &str)>) -> CorrelationResult {
+        CorrelationResult {
+            address: address.to_string(),
+            postnummer: "211 42".to_string(),
+            miljo_match: miljo.map(|(d, i)| (d, i.to_string())),
+            parkering_match: Some((5.0, "Taxa 3".to_string())),
+            source: MatchSource::Algorithm,
+        }
+    }
+
+    fn entry(address: &str, dataset: Dataset, action: OverrideAction) -> Override {
+        Override {
+            address: address.to_string(),
+            postnummer: "21142".to_string(),
+            dataset,
+            action,
+            reason: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        let addresses = vec![AdressClean {
+            coordinates: [d("13.0000"), d("55.6005")],
+            postnummer: "211 42".to_string(),
+            adress: "Storgatan 1".to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        }];
+        let miljodata = vec![zone("13.0001", "Städning A"), zone("13.0010", "Städning B")];
+        let mut results = vec![
+            result("Storgatan 1", Some((6.3, "Städning A"))),
+            result("Lokgatan 2", Some((12.0, "Städning A"))),
+            result("Hamngatan 3", None),
+        ];
+
+        let overrides = OverrideSet {
+            version: 1,
+            overrides: vec![
+                entry(
+                    "storgatan 1",
+                    Dataset::Miljo,
+                    OverrideAction::Zone {
+                        zone_id: miljodata[1].zone_id(),
+                    },
+                ),
+                entry("Lokgatan 2", Dataset::Parkering, OverrideAction::NoMatch),
+                entry(
+                    "Lokgatan 2",
+                    Dataset::Miljo,
+                    OverrideAction::Schedule {
+                        info: "Städning udda veckor".to_string(),
+                    },
+                ),
+                entry(
+                    "Hamngatan 3",
+                    Dataset::Miljo,
+                    OverrideAction::Zone {
+                        zone_id: "0000000000000000".to_string(),
+                    },
+                ),
+                entry("Okänd 9", Dataset::Miljo, OverrideAction::NoMatch),
+            ],
+        };
+        let report = overrides.apply(&mut results, &addresses, &miljodata, &[]);
+
+        assert_eq!(report.applied, 3);
+        assert_eq!(report.warnings.len(), 2);
+        assert!(report.warnings[0].contains("no longer in the data"));
+
+        let (distance, info) = results[0].miljo_match.clone().unwrap();
+        assert_eq!(info, "Städning B");
+        assert!((distance - 62.9).abs() < 1.0, "{}", distance);
+        assert_eq!(results[0].source, MatchSource::Override);
+
+        assert!(results[1].parkering_match.is_none());
+        assert_eq!(
+            results[1].miljo_match.as_ref().unwrap().1,
+            "Städning udda veckor"
+        );
+        assert_eq!(results[2].source, MatchSource::Algorithm);
+    }
+
+    #[test]
+    fn test_overrides_file_format() {
+        let json = r#"{
+            "version": 1,
+            "overrides": [
+                { "address": "Storgatan 1", "postnummer": "211 42", "dataset": "miljo",
+                  "action": "zone", "zone_id": "3f2a9c0d1b7e4a55", "reason": "Wrong side of street" },
+                { "address": "Lokgatan 2", "postnummer": "211 42", "dataset": "parkering",
+                  "action": "no_match" }
+            ]
+        }"#;
+        let overrides: OverrideSet = serde_json::from_str(json).unwrap();
+        assert_eq!(overrides.overrides[1].action, OverrideAction::NoMatch);
+        assert_eq!(
+            overrides.overrides[0].action,
+            OverrideAction::Zone {
+                zone_id: "3f2a9c0d1b7e4a55".to_string()
+            }
+        );
+    }
+}
@@ -1,21 +1,32 @@
 use crate::structs::*;
 use anyhow;
 use arrow::{
-    array::{Float64Array, Float64Builder, StringArray, StringBuilder},
+    array::{
+        Array, BooleanArray, Float64Array, Float64Builder, StringArray, StringBuilder, UInt8Array,
+        UInt16Array,
+    },
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
 };
+use bytes::Bytes;
 use parquet::{
     arrow::ArrowWriter,
     arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
     file::properties::{EnabledStatistics, WriterProperties},
+    file::reader::ChunkReader,
 };
 use std::{collections::BTreeMap, fs::File, sync::Arc};
 
+pub const DEFAULT_RESULTS_PATH: &str = "correlation_results.parquet";
+
 /// Read correlation results from parquet file
 pub fn read_correlation_parquet() -> anyhow::Result<Vec<CorrelationResult>> {
-    let file = File::open("correlation_results.parquet")
-        .map_err(|e| anyhow::anyhow!("Failed to open correlation_results.parquet: {}", e))?;
+    read_correlation_parquet_from(DEFAULT_RESULTS_PATH)
+}
+
+/// Read correlation results from the parquet file at `path`
+pub fn read_correlation_parquet_from(path: &str) -> anyhow::Result<Vec<CorrelationResult>> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path, e))?;
 
     let builder = ParquetRecordBatchReaderBuilder::try_new(file)
         .map_err(|e| anyhow::anyhow!("Failed to create Parquet reader builder: {}", e))?;
@@ -71,6 +82,18 @@ pub fn read_correlation_parquet() -> anyhow::Result<Vec<CorrelationResult>> {
             .ok_or_else(|| anyhow::anyhow!("parkering_info column missing or wrong type"))?
             .iter();
 
+        // Written since overrides were introduced; older files are all algorithmic
+        let source = match batch.schema().index_of("source") {
+            Ok(index) => Some(
+                batch
+                    .column(index)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .ok_or_else(|| anyhow::anyhow!("source column has wrong type"))?,
+            ),
+            Err(_) => None,
+        };
+
         // Convert rows to CorrelationResult
         for i in 0..batch.num_rows() {
             let miljo_match = if let Some(Some(dist)) = miljo_dist.clone().nth(i) {
@@ -116,6 +139,10 @@ pub fn read_correlation_parquet() -> anyhow::Result<Vec<CorrelationResult>> {
                     .unwrap_or_default(),
                 miljo_match,
                 parkering_match,
+                source: source
+                    .filter(|column| column.is_valid(i))
+                    .and_then(|column| MatchSource::parse(column.value(i)))
+                    .unwrap_or_default(),
             };
             result.push(entry);
         }
@@ -124,8 +151,114 @@ pub fn read_correlation_parquet() -> anyhow::Result<Vec<CorrelationResult>> {
     Ok(result)
 }
 
+/// Read the address dataset bundled with the app from the parquet file at `path`
+pub fn read_address_info_parquet_from(path: &str) -> anyhow::Result<Vec<AdressInfo>> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path, e))?;
+    Ok(read_address_info(file)?
+        .into_iter()
+        .map(|(row, _)| row)
+        .collect())
+}
+
+/// Read the address dataset bundled with the app from in-memory parquet data
+pub fn read_address_info_parquet_bytes(data: Vec<u8>) -> anyhow::Result<Vec<AdressInfo>> {
+    Ok(read_address_info(Bytes::from(data))?
+        .into_iter()
+        .map(|(row, _)| row)
+        .collect())
+}
+
+/// Read addresses saved by earlier app versions (`local.parquet`)
+/// Same columns as the bundled dataset plus an `active` flag per address
+pub fn read_saved_addresses_parquet_from(path: &str) -> anyhow::Result<Vec<(AdressInfo, bool)>> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path, e))?;
+    read_address_info(file)
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> anyhow::Result<&'a T> {
+    batch
+        .column(batch.schema().index_of(name)?)
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| anyhow::anyhow!("{} column missing or wrong type", name))
+}
+
+/// Rows and their `active` flag, true when the column is absent
+fn read_address_info<R: ChunkReader + 'static>(
+    reader: R,
+) -> anyhow::Result<Vec<(AdressInfo, bool)>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(reader)
+        .map_err(|e| anyhow::anyhow!("Failed to create Parquet reader builder: {}", e))?;
+
+    let reader = builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build Parquet record batch reader: {}", e))?;
+
+    let mut result = Vec::new();
+
+    for batch in reader {
+        let batch = batch?;
+
+        let postnummer = column::<UInt16Array>(&batch, "postnummer")?;
+        let adress = column::<StringArray>(&batch, "adress")?;
+        let gata = column::<StringArray>(&batch, "gata")?;
+        let gatunummer = column::<StringArray>(&batch, "gatunummer")?;
+        let dag = column::<UInt8Array>(&batch, "dag")?;
+        let tid = column::<StringArray>(&batch, "tid")?;
+        let info = column::<StringArray>(&batch, "info")?;
+        // The dataset stores longitude in `lat` and latitude in `lon`
+        let lon = column::<Float64Array>(&batch, "lat")?;
+        let lat = column::<Float64Array>(&batch, "lon")?;
+        let active = match batch.schema().index_of("active") {
+            Ok(_) => Some(column::<BooleanArray>(&batch, "active")?),
+            Err(_) => None,
+        };
+
+        let text = |array: &StringArray, i: usize| {
+            if array.is_null(i) {
+                String::new()
+            } else {
+                array.value(i).to_string()
+            }
+        };
+
+        for i in 0..batch.num_rows() {
+            let postnummer = match postnummer.is_null(i) {
+                false if postnummer.value(i) != 0 => {
+                    let digits = format!("{:05}", postnummer.value(i));
+                    format!("{} {}", &digits[..3], &digits[3..])
+                }
+                _ => String::new(),
+            };
+
+            let row = AdressInfo {
+                coordinates: [lon.value(i), lat.value(i)],
+                postnummer,
+                adress: text(adress, i),
+                gata: text(gata, i),
+                gatunummer: text(gatunummer, i),
+                dag: if dag.is_null(i) { 0 } else { dag.value(i) },
+                tid: text(tid, i),
+                info: text(info, i),
+            };
+            let active = active.is_none_or(|a| a.is_null(i) || a.value(i));
+            result.push((row, active));
+        }
+    }
+
+    Ok(result)
+}
+
 /// Write correlation results to parquet file
 pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result<()> {
+    write_correlation_parquet_to(DEFAULT_RESULTS_PATH, data)
+}
+
+/// Write correlation results to the parquet file at `path`
+pub fn write_correlation_parquet_to(
+    path: &str,
+    data: Vec<CorrelationResult>,
+) -> anyhow::Result<()> {
     if data.is_empty() {
         return Err(anyhow::anyhow!("Empty correlation results"));
     }
@@ -137,6 +270,7 @@ pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result
         Field::new("miljo_info", DataType::Utf8, true),
         Field::new("parkering_distance", DataType::Float64, true),
         Field::new("parkering_info", DataType::Utf8, true),
+        Field::new("source", DataType::Utf8, false),
     ]));
 
     let mut grouped: BTreeMap<String, Vec<CorrelationResult>> = BTreeMap::new();
@@ -146,7 +280,6 @@ pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result
         grouped.entry(key).or_default().push(result);
     }
 
-    let path = "correlation_results.parquet";
     let file = File::create(path).map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
 
     let props = WriterProperties::builder()
@@ -163,10 +296,12 @@ pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result
         let mut miljo_info_builder = StringBuilder::new();
         let mut parkering_dist_builder = Float64Builder::new();
         let mut parkering_info_builder = StringBuilder::new();
+        let mut source_builder = StringBuilder::new();
 
         for r in rows {
             address_builder.append_value(&r.address);
             postnummer_builder.append_value(&r.postnummer);
+            source_builder.append_value(r.source.as_str());
 
             match &r.miljo_match {
                 Some((dist, info)) => {
@@ -200,6 +335,7 @@ pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result
                 Arc::new(miljo_info_builder.finish()),
                 Arc::new(parkering_dist_builder.finish()),
                 Arc::new(parkering_info_builder.finish()),
+                Arc::new(source_builder.finish()),
             ],
         )
         .map_err(|e| anyhow::anyhow!("Failed to create record batch: {}", e))?;
@@ -215,3 +351,69 @@ pub fn write_correlation_parquet(data: Vec<CorrelationResult>) -> anyhow::Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_bundled_address_info() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ui/assets/data/adress_info.parquet"
+        );
+        let rows = read_address_info_parquet_bytes(std::fs::read(path).unwrap()).unwrap();
+        assert!(!rows.is_empty());
+
+        // Malmö, with longitude first
+        let first = &rows[0];
+        assert!((12.8..13.3).contains(&first.coordinates[0]));
+        assert!((55.4..55.8).contains(&first.coordinates[1]));
+        assert!(rows.iter().any(|r| r.has_restriction()));
+        assert!(
+            rows.iter()
+                .filter(|r| !r.postnummer.is_empty())
+                .all(|r| r.postnummer.len() == 6 && r.postnummer.as_bytes()[3] == b' ')
+        );
+    }
+
+    #[test]
+    fn test_read_saved_addresses() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ui/assets/data/local.parquet"
+        );
+        let rows = read_saved_addresses_parquet_from(path).unwrap();
+        assert_eq!(rows.len(), 7);
+        assert!(rows.iter().all(|(_, active)| *active));
+        assert!(rows.iter().any(|(row, _)| row.postnummer == "211 20"));
+    }
+
+    #[test]
+    fn test_correlation_roundtrip_keeps_source() {
+        let path = std::env::temp_dir().join(format!("amp_results_{}.parquet", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        let result = |address: &str, source| CorrelationResult {
+            address: address.to_string(),
+            postnummer: "211 42".to_string(),
+            miljo_match: Some((3.5, "Städning".to_string())),
+            parkering_match: None,
+            source,
+        };
+
+        write_correlation_parquet_to(
+            &path,
+            vec![
+                result("Storgatan 1", MatchSource::Algorithm),
+                result("Lokgatan 2", MatchSource::Override),
+            ],
+        )
+        .unwrap();
+        let read = read_correlation_parquet_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read[0].source, MatchSource::Algorithm);
+        assert_eq!(read[1].source, MatchSource::Override);
+        assert_eq!(read[1].miljo_match, Some((3.5, "Städning".to_string())));
+    }
+}
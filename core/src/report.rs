@@ -0,0 +1,243 @@
+//! GeoJSON report of correlation results
+//! Correlation output only stores the distance and info of each match, so the matched
+//! segment is looked up again in the zone data by its info and distance
+
+use crate::incremental::{address_key, result_key};
+use crate::structs::{AdressClean, CorrelationResult};
+use crate::zone_index::{ZoneIndex, ZoneMatch, closest_point_on_segment};
+use geojson::{Feature, FeatureCollection, Geometry, JsonObject, Value};
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+/// Largest difference in meters between a stored and a recomputed match distance
+const DISTANCE_TOLERANCE: f64 = 0.5;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReportSummary {
+    pub addresses: usize,
+    pub matched: usize,
+    pub zones: usize,
+    /// Matches whose segment could not be found in the zone data
+    pub unresolved: usize,
+    /// Results whose address is not in the address data
+    pub unlocated: usize,
+}
+
+/// Zone a stored match refers to: the segment at the stored distance, preferring one
+/// with the stored info (schedule overrides replace the info)
+pub fn matched_zone<'a>(
+    index: &'a ZoneIndex,
+    point: [f64; 2],
+    distance: f64,
+    info: &str,
+) -> Option<ZoneMatch<'a>> {
+    let candidates: Vec<ZoneMatch> = index
+        .within(point, distance + DISTANCE_TOLERANCE)
+        .into_iter()
+        .filter(|m| (m.distance - distance).abs() <= DISTANCE_TOLERANCE)
+        .collect();
+    let closest = |matches: Vec<ZoneMatch<'a>>| {
+        matches.into_iter().min_by(|a, b| {
+            (a.distance - distance)
+                .abs()
+                .total_cmp(&(b.distance - distance).abs())
+        })
+    };
+    let same_info: Vec<ZoneMatch> = candidates
+        .iter()
+        .filter(|m| m.zone.info == info)
+        .copied()
+        .collect();
+    closest(same_info).or_else(|| closest(candidates))
+}
+
+fn feature(geometry: Value, properties: serde_json::Value) -> Feature {
+    let properties: Option<JsonObject> = match properties {
+        serde_json::Value::Object(map) => Some(map),
+        _ => None,
+    };
+    Feature {
+        bbox: None,
+        geometry: Some(Geometry::new(geometry)),
+        id: None,
+        properties,
+        foreign_members: None,
+    }
+}
+
+fn position(point: [f64; 2]) -> Vec<f64> {
+    vec![point[0], point[1]]
+}
+
+/// Features for every located result: an `address` point, the matched `zone` segments
+/// (once per segment) and `connector` lines from the address to its segments
+pub fn build_report(
+    results: &[CorrelationResult],
+    addresses: &[AdressClean],
+    miljo: &ZoneIndex,
+    parkering: &ZoneIndex,
+) -> (FeatureCollection, ReportSummary) {
+    let located: HashMap<String, [f64; 2]> = addresses
+        .iter()
+        .filter_map(|a| {
+            let point = [a.coordinates[0].to_f64()?, a.coordinates[1].to_f64()?];
+            Some((address_key(a), point))
+        })
+        .collect();
+
+    let mut summary = ReportSummary::default();
+    let mut features = Vec::new();
+    let mut zone_features = Vec::new();
+    let mut seen_zones: HashSet<(&str, usize)> = HashSet::new();
+
+    for result in results {
+        let Some(&point) = located.get(&result_key(result)) else {
+            summary.unlocated += 1;
+            continue;
+        };
+        summary.addresses += 1;
+        if result.has_match() {
+            summary.matched += 1;
+        }
+
+        let datasets = [
+            ("miljo", miljo, &result.miljo_match),
+            ("parkering", parkering, &result.parkering_match),
+        ];
+        for (dataset, index, found) in datasets {
+            let Some((distance, info)) = found else {
+                continue;
+            };
+            let Some(zone) = matched_zone(index, point, *distance, info) else {
+                summary.unresolved += 1;
+                continue;
+            };
+            let Some([start, end]) = index.segment(zone.index) else {
+                continue;
+            };
+
+            if seen_zones.insert((dataset, zone.index)) {
+                zone_features.push(feature(
+                    Value::LineString(vec![position(start), position(end)]),
+                    json!({
+                        "kind": "zone",
+                        "dataset": dataset,
+                        "info": zone.zone.info,
+                        "tid": zone.zone.tid,
+                        "dag": zone.zone.dag,
+                    }),
+                ));
+            }
+            features.push(feature(
+                Value::LineString(vec![
+                    position(point),
+                    position(closest_point_on_segment(point, start, end)),
+                ]),
+                json!({
+                    "kind": "connector",
+                    "dataset": dataset,
+                    "address": result.address,
+                    "distance": distance,
+                }),
+            ));
+        }
+
+        features.push(feature(
+            Value::Point(position(point)),
+            json!({
+                "kind": "address",
+                "address": result.address,
+                "postnummer": result.postnummer,
+                "source": result.source.as_str(),
+                "miljo_distance": result.miljo_match.as_ref().map(|(d, _)| d),
+                "miljo_info": result.miljo_match.as_ref().map(|(_, info)| info),
+                "parkering_distance": result.parkering_match.as_ref().map(|(d, _)| d),
+                "parkering_info": result.parkering_match.as_ref().map(|(_, info)| info),
+            }),
+        ));
+    }
+
+    summary.zones = zone_features.len();
+    // Zones first so connectors and addresses are drawn on top
+    zone_features.extend(features);
+    let collection = FeatureCollection {
+        bbox: None,
+        features: zone_features,
+        foreign_members: None,
+    };
+    (collection, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{MatchSource, MiljoeDataClean};
+    use rust_decimal::Decimal;
+
+    fn d(v: f64) -> Decimal {
+        Decimal::try_from(v).unwrap()
+    }
+
+    fn zone(y: f64, info: &str) -> MiljoeDataClean {
+        MiljoeDataClean {
+            coordinates: [[d(13.0000), d(y)], [d(13.0010), d(y)]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    #[test]
+    fn test_report_resolves_matched_segments() {
+        let miljo = ZoneIndex::new(vec![zone(55.6001, "Tisdag"), zone(55.6002, "Onsdag")]);
+        let parkering = ZoneIndex::new(Vec::new());
+        let address = AdressClean {
+            coordinates: [d(13.0005), d(55.6000)],
+            postnummer: "21142".to_string(),
+            adress: "Storgatan 1".to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        };
+        let onsdag = miljo.within([13.0005, 55.6000], 50.0)[1].distance;
+        let results = vec![
+            CorrelationResult {
+                address: "Storgatan 1".to_string(),
+                postnummer: "21142".to_string(),
+                miljo_match: Some((onsdag, "Onsdag".to_string())),
+                parkering_match: Some((5.0, "Taxa C".to_string())),
+                source: MatchSource::Algorithm,
+            },
+            CorrelationResult {
+                address: "Okänd väg 2".to_string(),
+                postnummer: "21142".to_string(),
+                miljo_match: None,
+                parkering_match: None,
+                source: MatchSource::Algorithm,
+            },
+        ];
+
+        let (collection, summary) = build_report(&results, &[address], &miljo, &parkering);
+        assert_eq!(
+            summary,
+            ReportSummary {
+                addresses: 1,
+                matched: 1,
+                zones: 1,
+                unresolved: 1,
+                unlocated: 1,
+            }
+        );
+
+        let kinds: Vec<_> = collection
+            .features
+            .iter()
+            .map(|f| f.property("kind").unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, ["zone", "connector", "address"]);
+        assert_eq!(
+            collection.features[0].property("info").unwrap(),
+            &json!("Onsdag")
+        );
+    }
+}
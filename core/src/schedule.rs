@@ -0,0 +1,202 @@
+//! Restriction schedules of parking zones
+//! Parses the `tid` strings of the source data ("0800 - 1200", "08:00–20:00", "8–18")
+//! and finds the next restriction window for a zone's `dag`, skipping public holidays
+
+use crate::holidays::{is_day_before_red_day, is_public_holiday, is_red_day};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// How far ahead to look for a matching day; covers every day of month at least once
+const SEARCH_DAYS: u64 = 400;
+
+/// Daily time range in minutes since midnight
+/// `end <= start` means the range continues past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyWindow {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl DailyWindow {
+    /// The window of these hours starting on `date`
+    pub fn on_date(&self, date: NaiveDate) -> Window {
+        let end = if self.end <= self.start {
+            self.end + 24 * 60
+        } else {
+            self.end
+        };
+        Window {
+            start: at_minutes(date, self.start),
+            end: at_minutes(date, end),
+        }
+    }
+}
+
+/// Hours of a `tid`, with the parenthesised range of a parking sign as `eve`
+/// ("08:00–20:00 (08:00–16:00)"): the regular hours apply on weekdays, the parenthesised
+/// ones on Saturdays and days before public holidays, and Sundays and holidays are free
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hours {
+    pub regular: DailyWindow,
+    pub eve: Option<DailyWindow>,
+}
+
+impl Hours {
+    /// Window in effect on a date for a zone's `dag`, if any
+    ///
+    /// Street cleaning (`dag` 1–31) is not performed on public holidays. Every-day
+    /// restrictions only follow the sign conventions when they carry parenthesised hours.
+    fn on(&self, dag: u8, date: NaiveDate) -> Option<DailyWindow> {
+        match (dag, self.eve) {
+            (0, None) => Some(self.regular),
+            (0, Some(_)) if is_red_day(date) => None,
+            (0, Some(eve)) if is_day_before_red_day(date) => Some(eve),
+            (0, Some(_)) => Some(self.regular),
+            (dag, _) => {
+                (date.day() == u32::from(dag) && !is_public_holiday(date)).then_some(self.regular)
+            }
+        }
+    }
+}
+
+/// A concrete restriction period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl Window {
+    pub fn is_active(&self, at: NaiveDateTime) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+/// Parse a time of day such as "0800", "08:00", "8.30" or "8" into minutes since midnight
+fn parse_time(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let (hours, minutes) = match value.find([':', '.']) {
+        Some(pos) => (&value[..pos], &value[pos + 1..]),
+        None if value.len() > 2 => value.split_at(value.len() - 2),
+        None => (value, "0"),
+    };
+
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    let total = hours * 60 + minutes;
+    (minutes < 60 && total <= 24 * 60).then_some(total)
+}
+
+/// Parse a `tid` string into a daily window, ignoring any parenthesised hours
+pub fn parse_tid(tid: &str) -> Option<DailyWindow> {
+    let regular = tid.split('(').next().unwrap_or_default();
+    let normalized = regular.replace(['–', '—'], "-");
+    let (start, end) = normalized.split_once('-')?;
+    Some(DailyWindow {
+        start: parse_time(start)?,
+        end: parse_time(end)?,
+    })
+}
+
+/// Parse a `tid` string including the parenthesised hours of a parking sign
+pub fn parse_hours(tid: &str) -> Option<Hours> {
+    let eve = tid
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split(')').next())
+        .and_then(parse_tid);
+    Some(Hours {
+        regular: parse_tid(tid)?,
+        eve,
+    })
+}
+
+fn at_minutes(date: NaiveDate, minutes: u32) -> NaiveDateTime {
+    let days = u64::from(minutes / (24 * 60));
+    let time = NaiveTime::from_hms_opt((minutes / 60) % 24, minutes % 60, 0).unwrap_or_default();
+    (date + Days::new(days)).and_time(time)
+}
+
+/// Current or next restriction window for a zone at `now`
+///
+/// `dag` is the day of month the restriction applies to; 0 means every day.
+/// Public holidays are skipped as described on [`Hours`].
+/// Returns None if `tid` cannot be parsed.
+pub fn next_window(dag: u8, tid: &str, now: NaiveDateTime) -> Option<Window> {
+    let hours = parse_hours(tid)?;
+
+    // Start a day early so a window running past midnight is still found
+    let first = now.date().checked_sub_days(Days::new(1))?;
+    (0..=SEARCH_DAYS)
+        .filter_map(|offset| first.checked_add_days(Days::new(offset)))
+        .filter_map(|date| Some(hours.on(dag, date)?.on_date(date)))
+        .find(|window| window.end > now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_tid_formats() {
+        let window = |start, end| Some(DailyWindow { start, end });
+        assert_eq!(parse_tid("0800 - 1200"), window(480, 720));
+        assert_eq!(parse_tid("08:00–20:00"), window(480, 1200));
+        assert_eq!(parse_tid("8–18"), window(480, 1080));
+        assert_eq!(parse_tid("22.30-0600"), window(1350, 360));
+        assert_eq!(parse_tid(""), None);
+        assert_eq!(parse_tid("2500 - 2600"), None);
+    }
+
+    #[test]
+    fn test_next_window_for_day_of_month() {
+        // Before, during and after the window on the 15th
+        let window = next_window(15, "0800 - 1200", at("2026-03-15 07:00")).unwrap();
+        assert_eq!(window.start, at("2026-03-15 08:00"));
+        assert!(!window.is_active(at("2026-03-15 07:00")));
+
+        let window = next_window(15, "0800 - 1200", at("2026-03-15 09:30")).unwrap();
+        assert!(window.is_active(at("2026-03-15 09:30")));
+
+        let window = next_window(15, "0800 - 1200", at("2026-03-15 12:00")).unwrap();
+        assert_eq!(window.start, at("2026-04-15 08:00"));
+
+        // Day 31 skips months without one
+        let window = next_window(31, "1800 - 2200", at("2026-04-01 00:00")).unwrap();
+        assert_eq!(window.start, at("2026-05-31 18:00"));
+
+        // Every day, running past midnight
+        let window = next_window(0, "22:00–06:00", at("2026-03-15 02:00")).unwrap();
+        assert_eq!(window.start, at("2026-03-14 22:00"));
+        assert_eq!(window.end, at("2026-03-15 06:00"));
+    }
+
+    #[test]
+    fn test_next_window_around_holidays() {
+        // Cleaning on the 25th skips Juldagen
+        let window = next_window(25, "0800 - 1200", at("2026-12-01 00:00")).unwrap();
+        assert_eq!(window.start, at("2027-01-25 08:00"));
+
+        let hours = parse_hours("08:00–20:00 (08:00–16:00)").unwrap();
+        assert_eq!(
+            hours.eve,
+            Some(DailyWindow {
+                start: 480,
+                end: 960
+            })
+        );
+
+        // Wednesday before Kristi himmelsfärd has the reduced hours, the holiday itself is free
+        let window = next_window(0, "08:00–20:00 (08:00–16:00)", at("2026-05-13 17:00")).unwrap();
+        assert_eq!(window.start, at("2026-05-15 08:00"));
+        assert_eq!(window.end, at("2026-05-15 20:00"));
+
+        // Saturday reduced, Sunday free
+        let window = next_window(0, "08:00–20:00 (08:00–16:00)", at("2026-03-14 09:00")).unwrap();
+        assert_eq!(window.end, at("2026-03-14 16:00"));
+        let window = next_window(0, "08:00–20:00 (08:00–16:00)", at("2026-03-14 17:00")).unwrap();
+        assert_eq!(window.start, at("2026-03-16 08:00"));
+    }
+}
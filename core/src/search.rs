@@ -0,0 +1,445 @@
+//! Address search index
+//! Resolves free-text input such as "sodra forstadsg 12b" against `AdressClean`
+//! with diacritic folding, typo tolerance, house-number ranges and letter suffixes
+
+use crate::structs::AdressClean;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// A ranked search result; lower `score` is a better match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// Index into the address slice the index was built from
+    pub index: usize,
+    pub adress: String,
+    pub gata: String,
+    pub gatunummer: String,
+    pub postnummer: String,
+    pub score: u32,
+}
+
+/// House number of an address, e.g. "12", "12B" or "10-14"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HouseNumber {
+    pub from: u32,
+    pub to: u32,
+    pub suffix: Option<char>,
+}
+
+impl HouseNumber {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some((from, to)) = value.split_once('-') {
+            let from: u32 = from.trim().parse().ok()?;
+            let to: u32 = to.trim().parse().ok()?;
+            return (from <= to).then_some(Self {
+                from,
+                to,
+                suffix: None,
+            });
+        }
+
+        let digits = value.chars().take_while(|c| c.is_ascii_digit()).count();
+        let number: u32 = value[..digits].parse().ok()?;
+        let mut rest = value[digits..].trim().chars();
+        let suffix = match (rest.next(), rest.next()) {
+            (None, _) => None,
+            (Some(c), None) if c.is_alphabetic() => c.to_uppercase().next(),
+            _ => return None,
+        };
+
+        Some(Self {
+            from: number,
+            to: number,
+            suffix,
+        })
+    }
+}
+
+/// Lowercase and fold Swedish and other common diacritics: "Södra Förstadsgatan" → "sodra forstadsgatan"
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let c = match c {
+            'å' | 'ä' | 'à' | 'á' | 'â' => 'a',
+            'ö' | 'ø' | 'ò' | 'ó' | 'ô' => 'o',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'ü' | 'ú' | 'ù' => 'u',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        };
+        if c != ' ' || !folded.ends_with(' ') {
+            folded.push(c);
+        }
+    }
+    folded.trim().to_string()
+}
+
+/// Optimal string alignment distance (Levenshtein with adjacent transpositions)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Typos tolerated for a query term of `len` characters
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn digits_only(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// House number part of a query
+#[derive(Debug, Clone, PartialEq)]
+enum NumberQuery {
+    /// Number with optional suffix, as typed (for prefix completion)
+    Single {
+        number: HouseNumber,
+        typed: String,
+    },
+    Range(u32, u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ParsedQuery {
+    street: String,
+    number: Option<NumberQuery>,
+    postnummer: Option<String>,
+}
+
+fn parse_query(text: &str) -> ParsedQuery {
+    // Folding turns separators into spaces, so "10-14" arrives as "10 14"
+    let folded = fold(text);
+    let mut tokens: Vec<&str> = folded.split_whitespace().collect();
+    let mut query = ParsedQuery::default();
+    let is_number = |t: &str| t.chars().all(|c| c.is_ascii_digit());
+
+    if let Some(pos) = tokens
+        .iter()
+        .position(|t| t.len() == 5 && is_number(t))
+        .filter(|&pos| pos > 0)
+    {
+        query.postnummer = Some(tokens.remove(pos).to_string());
+    }
+
+    if tokens.len() >= 3 {
+        let last = tokens[tokens.len() - 1];
+        let before = tokens[tokens.len() - 2];
+        let merged = if is_number(before) && is_number(last) {
+            // Range: "10 14"
+            Some(format!("{}-{}", before, last))
+        } else if is_number(before) && last.chars().count() == 1 && !is_number(last) {
+            // Separated suffix: "12 b"
+            Some(format!("{}{}", before, last))
+        } else {
+            None
+        };
+        if let Some(merged) = merged {
+            tokens.truncate(tokens.len() - 2);
+            return finish_query(query, tokens, Some(merged));
+        }
+    }
+
+    let number = match tokens.last() {
+        Some(last) if tokens.len() > 1 && last.starts_with(|c: char| c.is_ascii_digit()) => {
+            tokens.pop().map(str::to_string)
+        }
+        _ => None,
+    };
+    finish_query(query, tokens, number)
+}
+
+fn finish_query(mut query: ParsedQuery, street: Vec<&str>, number: Option<String>) -> ParsedQuery {
+    query.street = street.join(" ");
+    query.number = number.and_then(|typed| {
+        let parsed = HouseNumber::parse(&typed)?;
+        Some(if parsed.from != parsed.to {
+            NumberQuery::Range(parsed.from, parsed.to)
+        } else {
+            NumberQuery::Single {
+                number: parsed,
+                typed: typed.to_uppercase(),
+            }
+        })
+    });
+    query
+}
+
+struct Entry {
+    index: usize,
+    number: Option<HouseNumber>,
+    number_text: String,
+    postnummer: String,
+}
+
+pub struct AddressIndex {
+    /// Folded street name → display name and entries
+    streets: BTreeMap<String, (String, Vec<Entry>)>,
+    addresses: Vec<AdressClean>,
+}
+
+impl AddressIndex {
+    pub fn new(addresses: &[AdressClean]) -> Self {
+        let mut streets: BTreeMap<String, (String, Vec<Entry>)> = BTreeMap::new();
+        let mut seen = HashSet::new();
+
+        for (index, address) in addresses.iter().enumerate() {
+            // Skip duplicate rows of the same address
+            if !seen.insert((address.adress.clone(), address.postnummer.clone())) {
+                continue;
+            }
+            let street = streets
+                .entry(fold(&address.gata))
+                .or_insert_with(|| (address.gata.clone(), Vec::new()));
+            street.1.push(Entry {
+                index,
+                number: HouseNumber::parse(&address.gatunummer),
+                number_text: address.gatunummer.trim().to_uppercase(),
+                postnummer: digits_only(&address.postnummer),
+            });
+        }
+
+        for (_, entries) in streets.values_mut() {
+            entries.sort_by_key(|entry| entry.number);
+        }
+
+        Self {
+            streets,
+            addresses: addresses.to_vec(),
+        }
+    }
+
+    /// Address a suggestion refers to
+    pub fn address(&self, index: usize) -> Option<&AdressClean> {
+        self.addresses.get(index)
+    }
+
+    /// Number of distinct streets
+    pub fn street_count(&self) -> usize {
+        self.streets.len()
+    }
+
+    /// Score of a street name for a folded query; None if it does not match
+    fn street_score(street: &str, query: &str) -> Option<u32> {
+        if street == query {
+            return Some(0);
+        }
+        if street.starts_with(query) {
+            return Some(10);
+        }
+
+        let typos = max_typos(query.chars().count());
+        if typos == 0 {
+            return None;
+        }
+        let full = edit_distance(street, query);
+        if full <= typos {
+            return Some(20 + 10 * full as u32);
+        }
+        // Typo in an incomplete name: compare with the street prefix of the same length
+        let prefix: String = street.chars().take(query.chars().count()).collect();
+        let partial = edit_distance(&prefix, query);
+        (partial <= typos).then_some(30 + 10 * partial as u32)
+    }
+
+    /// Score of an entry's house number; None if it does not match
+    fn number_score(entry: &Entry, query: &Option<NumberQuery>) -> Option<u32> {
+        let Some(query) = query else {
+            return Some(0);
+        };
+        let number = entry.number?;
+
+        match query {
+            NumberQuery::Range(from, to) => (number.from <= *to && *from <= number.to).then_some(0),
+            NumberQuery::Single {
+                number: wanted,
+                typed,
+            } => {
+                let covers = number.from <= wanted.from && wanted.from <= number.to;
+                if covers && number.suffix == wanted.suffix {
+                    Some(0)
+                } else if covers && wanted.suffix.is_none() {
+                    // "12" also suggests 12A, 12B
+                    Some(1)
+                } else if covers {
+                    Some(3)
+                } else if entry.number_text.starts_with(typed.as_str()) {
+                    // Completion: "1" → 10, 12B, 154
+                    Some(5)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Ranked suggestions for free-text input; `postnummer` may be partial ("211")
+    pub fn search(&self, text: &str, postnummer: Option<&str>, limit: usize) -> Vec<Suggestion> {
+        let query = parse_query(text);
+        if query.street.is_empty() {
+            return Vec::new();
+        }
+        let postnummer = postnummer
+            .map(digits_only)
+            .or(query.postnummer.clone())
+            .filter(|p| !p.is_empty());
+
+        let mut results: Vec<(u32, &String, Option<HouseNumber>, usize)> = Vec::new();
+        for (folded, (_, entries)) in &self.streets {
+            let Some(street_score) = Self::street_score(folded, &query.street) else {
+                continue;
+            };
+
+            for entry in entries {
+                if postnummer
+                    .as_ref()
+                    .is_some_and(|p| !entry.postnummer.starts_with(p.as_str()))
+                {
+                    continue;
+                }
+                if let Some(number_score) = Self::number_score(entry, &query.number) {
+                    results.push((
+                        street_score + number_score,
+                        folded,
+                        entry.number,
+                        entry.index,
+                    ));
+                }
+            }
+        }
+
+        results.sort_by(|a, b| (a.0, a.1, a.2).cmp(&(b.0, b.1, b.2)));
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(score, _, _, index)| {
+                let address = &self.addresses[index];
+                Suggestion {
+                    index,
+                    adress: address.adress.clone(),
+                    gata: address.gata.clone(),
+                    gatunummer: address.gatunummer.clone(),
+                    postnummer: address.postnummer.clone(),
+                    score,
+                }
+            })
+            .collect()
+    }
+
+    /// Street name completions for a partial street name
+    pub fn complete_street(&self, text: &str, limit: usize) -> Vec<String> {
+        let query = fold(text);
+        let mut matches: Vec<(u32, &String)> = self
+            .streets
+            .iter()
+            .filter_map(|(folded, (name, _))| Some((Self::street_score(folded, &query)?, name)))
+            .collect();
+        matches.sort();
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn address(gata: &str, nummer: &str, postnummer: &str) -> AdressClean {
+        AdressClean {
+            coordinates: [Decimal::ZERO, Decimal::ZERO],
+            postnummer: postnummer.to_string(),
+            adress: format!("{} {}", gata, nummer),
+            gata: gata.to_string(),
+            gatunummer: nummer.to_string(),
+        }
+    }
+
+    fn index() -> AddressIndex {
+        AddressIndex::new(&[
+            address("Södra Förstadsgatan", "12", "211 43"),
+            address("Södra Förstadsgatan", "12A", "211 43"),
+            address("Södra Förstadsgatan", "12B", "211 43"),
+            address("Södra Förstadsgatan", "14", "211 43"),
+            address("Södra Förstadsgatan", "120", "214 20"),
+            address("Storgatan", "12", "211 42"),
+            address("Storgatan", "12", "211 42"), // duplicate row
+            address("Stortorget", "1", "211 22"),
+        ])
+    }
+
+    fn found(results: &[Suggestion]) -> Vec<&str> {
+        results.iter().map(|s| s.adress.as_str()).collect()
+    }
+
+    #[test]
+    fn test_folding_typos_and_suffixes() {
+        let index = index();
+
+        // Diacritics folded, exact suffix first
+        let results = index.search("sodra forstadsgatan 12b", None, 3);
+        assert_eq!(found(&results)[0], "Södra Förstadsgatan 12B");
+
+        // Typo in the street name
+        let results = index.search("Sodra Forstadsgtan 12", None, 10);
+        assert_eq!(
+            found(&results)[..3],
+            [
+                "Södra Förstadsgatan 12",
+                "Södra Förstadsgatan 12A",
+                "Södra Förstadsgatan 12B"
+            ]
+        );
+        // "12" completes to 120 after the 12x variants
+        assert_eq!(found(&results)[3], "Södra Förstadsgatan 120");
+
+        // Transposition and postnummer filter; duplicate rows collapsed
+        let results = index.search("Stroagtan 12", Some("21142"), 10);
+        assert_eq!(found(&results), vec!["Storgatan 12"]);
+    }
+
+    #[test]
+    fn test_ranges_and_street_completion() {
+        let index = index();
+
+        let results = index.search("södra förstadsgatan 13-14", None, 10);
+        assert_eq!(found(&results), vec!["Södra Förstadsgatan 14"]);
+
+        let results = index.search("södra förstadsgatan 12 b", None, 1);
+        assert_eq!(found(&results), vec!["Södra Förstadsgatan 12B"]);
+
+        assert_eq!(
+            index.complete_street("stor", 5),
+            vec!["Storgatan", "Stortorget"]
+        );
+        assert!(index.search("Okändgatan 1", None, 5).is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Clone)]
 pub struct AdressClean {
@@ -17,6 +19,53 @@ pub struct MiljoeDataClean {
     pub dag: u8,
 }
 
+impl MiljoeDataClean {
+    /// Stable identifier of a zone segment, derived from its geometry and schedule
+    pub fn zone_id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(
+            format!(
+                "{},{},{},{}|{}|{}|{}",
+                self.coordinates[0][0],
+                self.coordinates[0][1],
+                self.coordinates[1][0],
+                self.coordinates[1][1],
+                self.info,
+                self.tid,
+                self.dag
+            )
+            .as_bytes(),
+        );
+        format!("{:x}", hasher.finalize())[..16].to_string()
+    }
+}
+
+/// Where the matches of a correlation result come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchSource {
+    #[default]
+    Algorithm,
+    /// At least one match was set by a manual override
+    Override,
+}
+
+impl MatchSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchSource::Algorithm => "algorithm",
+            MatchSource::Override => "override",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "algorithm" => Some(MatchSource::Algorithm),
+            "override" => Some(MatchSource::Override),
+            _ => None,
+        }
+    }
+}
+
 /// Result of correlation for a single address
 #[derive(Debug, Clone)]
 pub struct CorrelationResult {
@@ -24,6 +73,7 @@ pub struct CorrelationResult {
     pub postnummer: String,
     pub miljo_match: Option<(f64, String)>, // (distance, info)
     pub parkering_match: Option<(f64, String)>, // (distance, info)
+    pub source: MatchSource,
 }
 
 impl CorrelationResult {
@@ -49,3 +99,37 @@ impl CorrelationResult {
         }
     }
 }
+
+/// Row of the address dataset bundled with the app (`adress_info.parquet`)
+/// One row per address and matched miljözon; `dag` 0 and an empty `tid` mean no restriction
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdressInfo {
+    /// `[lon, lat]`
+    pub coordinates: [f64; 2],
+    /// Formatted as "211 42", empty when unknown
+    pub postnummer: String,
+    pub adress: String,
+    pub gata: String,
+    pub gatunummer: String,
+    pub dag: u8,
+    pub tid: String,
+    pub info: String,
+}
+
+impl AdressInfo {
+    pub fn has_restriction(&self) -> bool {
+        self.dag != 0 && !self.tid.is_empty()
+    }
+
+    /// The address part of the row, e.g. for building an `AddressIndex`
+    pub fn to_adress_clean(&self) -> AdressClean {
+        let decimal = |value: f64| Decimal::from_f64(value).unwrap_or_default();
+        AdressClean {
+            coordinates: [decimal(self.coordinates[0]), decimal(self.coordinates[1])],
+            postnummer: self.postnummer.clone(),
+            adress: self.adress.clone(),
+            gata: self.gata.clone(),
+            gatunummer: self.gatunummer.clone(),
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Synthetic city generator
+//! Builds a street grid with curved streets, addresses along both sides and zones with
+//! schedules, and writes it as GeoJSON in the Malmö open data schema. Lets tests,
+//! benchmarks and CI run without the real datasets
+
+use crate::checksum::DataSource;
+use crate::structs::{AdressClean, MiljoeDataClean};
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::{Value, json};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const EARTH_RADIUS_M: f64 = 6371000.0;
+/// Distance between parallel streets in meters, before jitter
+const BLOCK_M: f64 = 100.0;
+/// Addresses on each side of a block before thinning to the requested count
+const ADDRESSES_PER_SIDE: usize = 6;
+/// Offset of the curb, where zones lie, from the street's center line
+const CURB_M: f64 = 4.0;
+/// Length of the straight pieces curved streets and zones are made of
+const PIECE_M: f64 = 20.0;
+
+const STREET_NAMES: [&str; 20] = [
+    "Stor",
+    "Kyrko",
+    "Skol",
+    "Kvarn",
+    "Tull",
+    "Hamn",
+    "Bruks",
+    "Järnvägs",
+    "Torg",
+    "Väster",
+    "Öster",
+    "Norr",
+    "Söder",
+    "Smeds",
+    "Möllevångs",
+    "Ros",
+    "Lin",
+    "Ek",
+    "Björk",
+    "Lönn",
+];
+const STREET_SUFFIXES: [&str; 4] = ["gatan", "vägen", "allén", "stigen"];
+const STREET_PREFIXES: [&str; 5] = ["", "Lilla ", "Stora ", "Västra ", "Östra "];
+
+const MILJO_INFO: [&str; 3] = [
+    "Städning, ingen parkering",
+    "Gatusopning",
+    "Städning udda veckor",
+];
+const MILJO_TID: [&str; 3] = ["0800 - 1200", "0000 - 0600", "1200 - 1600"];
+/// Fee zones as `taxa` property and the `tid` the loader derives from it
+const TAXOR: [(&str, &str); 3] = [
+    ("Taxa 1: 9–20 (9–17)", "09:00–20:00 (09:00–17:00)"),
+    ("Taxa 3: 8–20 (8–16)", "08:00–20:00 (08:00–16:00)"),
+    ("Taxa 5: 8–18", "08:00–18:00"),
+];
+
+#[derive(Debug, Clone)]
+pub struct CityOptions {
+    pub addresses: usize,
+    pub seed: u64,
+    /// South-west corner `[lon, lat]` of the city
+    pub origin: [f64; 2],
+    /// Share of block sides with a miljöparkering zone
+    pub miljo_share: f64,
+    /// Share of blocks with a parking fee zone on both sides
+    pub parkering_share: f64,
+}
+
+impl Default for CityOptions {
+    fn default() -> Self {
+        Self {
+            addresses: 1000,
+            seed: 0,
+            origin: [12.98, 55.58],
+            miljo_share: 0.75,
+            parkering_share: 0.35,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticCity {
+    pub addresses: Vec<AdressClean>,
+    pub miljodata: Vec<MiljoeDataClean>,
+    pub parkering: Vec<MiljoeDataClean>,
+}
+
+/// A street along one axis of the grid, bent by a sine wave when curved
+struct Street {
+    name: String,
+    /// Position across the axis in meters
+    offset: f64,
+    vertical: bool,
+    amplitude: f64,
+    wavelength: f64,
+}
+
+impl Street {
+    /// Local east/north point in meters at `along` meters down the street, `side`
+    /// meters to its right (negative for left)
+    fn point(&self, along: f64, side: f64) -> [f64; 2] {
+        let bend = self.amplitude * (2.0 * std::f64::consts::PI * along / self.wavelength).sin();
+        let across = self.offset + bend;
+        if self.vertical {
+            // Northbound, right is east
+            [across + side, along]
+        } else {
+            // Eastbound, right is south
+            [along, across - side]
+        }
+    }
+}
+
+/// Generate a city with `options.addresses` addresses, the same for the same options
+pub fn generate_city(options: &CityOptions) -> SyntheticCity {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    // Streets per axis so that the blocks hold at least the requested addresses
+    let per_block = 2 * ADDRESSES_PER_SIDE;
+    let mut count = 2;
+    while 2 * count * (count - 1) * per_block < options.addresses {
+        count += 1;
+    }
+
+    let mut streets = Vec::with_capacity(2 * count);
+    for vertical in [true, false] {
+        for i in 0..count {
+            let curved = rng.gen_bool(0.25);
+            streets.push(Street {
+                name: street_name(streets.len()),
+                offset: i as f64 * BLOCK_M + rng.gen_range(-10.0..10.0),
+                vertical,
+                amplitude: if curved {
+                    rng.gen_range(4.0..12.0)
+                } else {
+                    0.0
+                },
+                wavelength: rng.gen_range(150.0..400.0),
+            });
+        }
+    }
+
+    let to_lonlat = |p: [f64; 2]| {
+        let meters_per_lat = EARTH_RADIUS_M.to_radians();
+        let meters_per_lon = meters_per_lat * options.origin[1].to_radians().cos();
+        [
+            round(options.origin[0] + p[0] / meters_per_lon),
+            round(options.origin[1] + p[1] / meters_per_lat),
+        ]
+    };
+
+    let mut city = SyntheticCity::default();
+    for street in &streets {
+        // Odd numbers on the left, even on the right, increasing along the street
+        let mut numbers = [1, 2];
+        for block in 0..count - 1 {
+            let start = block as f64 * BLOCK_M;
+            let end = start + BLOCK_M;
+            let parkering = rng.gen_bool(options.parkering_share);
+            let (taxa, taxa_tid) = TAXOR[rng.gen_range(0..TAXOR.len())];
+
+            for (side_index, side) in [-1.0, 1.0].into_iter().enumerate() {
+                // Keep clear of the crossings so addresses belong to this street
+                for k in 0..ADDRESSES_PER_SIDE {
+                    let along = start
+                        + 20.0
+                        + (k as f64 + rng.gen_range(0.2..0.8)) * (BLOCK_M - 40.0)
+                            / ADDRESSES_PER_SIDE as f64;
+                    let point = street.point(along, side * rng.gen_range(12.0..18.0));
+                    let number = numbers[side_index];
+                    numbers[side_index] += 2;
+                    city.addresses.push(AdressClean {
+                        coordinates: to_lonlat(point),
+                        postnummer: postnummer(point),
+                        adress: format!("{} {}", street.name, number),
+                        gata: street.name.clone(),
+                        gatunummer: number.to_string(),
+                    });
+                }
+
+                let curb = |along: f64| to_lonlat(street.point(along, side * CURB_M));
+                if rng.gen_bool(options.miljo_share) {
+                    let info = MILJO_INFO[rng.gen_range(0..MILJO_INFO.len())];
+                    let tid = MILJO_TID[rng.gen_range(0..MILJO_TID.len())];
+                    let dag = rng.gen_range(1..=28);
+                    push_pieces(&mut city.miljodata, start, end, &curb, info, tid, dag);
+                }
+                if parkering {
+                    push_pieces(&mut city.parkering, start, end, &curb, taxa, taxa_tid, 0);
+                }
+            }
+        }
+    }
+
+    // Thin out evenly to the requested count, keeping street order
+    if city.addresses.len() > options.addresses {
+        let mut keep = vec![false; city.addresses.len()];
+        for i in index::sample(&mut rng, city.addresses.len(), options.addresses) {
+            keep[i] = true;
+        }
+        let mut keep = keep.into_iter();
+        city.addresses.retain(|_| keep.next().unwrap_or(false));
+    }
+
+    city
+}
+
+/// Split the curb between `start` and `end` meters into straight zone segments
+fn push_pieces(
+    zones: &mut Vec<MiljoeDataClean>,
+    start: f64,
+    end: f64,
+    curb: &impl Fn(f64) -> [Decimal; 2],
+    info: &str,
+    tid: &str,
+    dag: u8,
+) {
+    let pieces = ((end - start) / PIECE_M).ceil() as usize;
+    for i in 0..pieces {
+        let from = start + (end - start) * i as f64 / pieces as f64;
+        let to = start + (end - start) * (i + 1) as f64 / pieces as f64;
+        zones.push(MiljoeDataClean {
+            coordinates: [curb(from), curb(to)],
+            info: info.to_string(),
+            tid: tid.to_string(),
+            dag,
+        });
+    }
+}
+
+fn street_name(index: usize) -> String {
+    let base = STREET_NAMES.len() * STREET_SUFFIXES.len();
+    let prefix = index / base;
+    if prefix >= STREET_PREFIXES.len() {
+        return format!("Gata {}", index + 1);
+    }
+    let name = STREET_NAMES[index % STREET_NAMES.len()];
+    let suffix = STREET_SUFFIXES[(index / STREET_NAMES.len()) % STREET_SUFFIXES.len()];
+    format!("{}{}{}", STREET_PREFIXES[prefix], name, suffix)
+}
+
+/// Postal district of a local point, one per 500 m square
+fn postnummer(point: [f64; 2]) -> String {
+    let district =
+        (point[0] / 500.0).floor().max(0.0) as u32 * 7 + (point[1] / 500.0).floor().max(0.0) as u32;
+    (21100 + district % 900).to_string()
+}
+
+/// Round to the 7 decimals of the source data (~1 cm)
+fn round(value: f64) -> Decimal {
+    Decimal::from_f64_retain(value)
+        .unwrap_or_default()
+        .round_dp(7)
+}
+
+fn coordinate(point: &[Decimal; 2]) -> Value {
+    json!([
+        point[0].to_f64().unwrap_or_default(),
+        point[1].to_f64().unwrap_or_default()
+    ])
+}
+
+/// Write the city as `adresser.json`, `miljoparkeringar.json` and
+/// `parkeringsavgifter.json` in `dir`, readable by [`crate::api::api_from_dir`]
+pub fn write_city(city: &SyntheticCity, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+
+    write_features(
+        &dir.join(DataSource::Adresser.file_name()),
+        city.addresses.iter().map(|a| {
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": coordinate(&a.coordinates) },
+                "properties": {
+                    "BELADRESS": a.adress,
+                    "POSTNR": a.postnummer,
+                    "ADRESSOMR": a.gata,
+                    "ADRESSPLAT": a.gatunummer,
+                },
+            })
+        }),
+    )?;
+
+    let line = |zone: &MiljoeDataClean, properties: Value| {
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [coordinate(&zone.coordinates[0]), coordinate(&zone.coordinates[1])],
+            },
+            "properties": properties,
+        })
+    };
+    write_features(
+        &dir.join(DataSource::Miljo.file_name()),
+        city.miljodata
+            .iter()
+            .map(|z| line(z, json!({ "value": z.info, "tid": z.tid, "day": z.dag }))),
+    )?;
+    write_features(
+        &dir.join(DataSource::Parkering.file_name()),
+        city.parkering
+            .iter()
+            .map(|z| line(z, json!({ "taxa": z.info }))),
+    )?;
+    Ok(())
+}
+
+/// Stream features into a FeatureCollection file, one feature per line
+fn write_features(
+    path: &Path,
+    features: impl Iterator<Item = Value>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "{{\"type\": \"FeatureCollection\", \"features\": [")?;
+    for (i, feature) in features.enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        writeln!(out)?;
+        serde_json::to_writer(&mut out, &feature)?;
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::api_from_dir;
+
+    #[test]
+    fn test_generate_city_is_seeded_and_sized() {
+        let options = CityOptions {
+            addresses: 500,
+            seed: 7,
+            ..CityOptions::default()
+        };
+        let city = generate_city(&options);
+        assert_eq!(city.addresses.len(), 500);
+        assert!(!city.miljodata.is_empty());
+        assert!(!city.parkering.is_empty());
+
+        let again = generate_city(&options);
+        assert_eq!(again.addresses[123].adress, city.addresses[123].adress);
+        assert_eq!(
+            again.addresses[123].coordinates,
+            city.addresses[123].coordinates
+        );
+        assert_eq!(again.miljodata.len(), city.miljodata.len());
+
+        let other = generate_city(&CityOptions { seed: 8, ..options });
+        assert_ne!(
+            other.addresses[123].coordinates,
+            city.addresses[123].coordinates
+        );
+
+        assert!(
+            city.addresses
+                .iter()
+                .all(|a| a.adress == format!("{} {}", a.gata, a.gatunummer))
+        );
+        assert!(city.miljodata.iter().all(|z| (1..=28).contains(&z.dag)));
+    }
+
+    #[test]
+    fn test_write_city_round_trips_through_loader() {
+        let city = generate_city(&CityOptions {
+            addresses: 100,
+            ..CityOptions::default()
+        });
+        let dir = std::env::temp_dir().join(format!("amp-synthetic-{}", std::process::id()));
+        write_city(&city, &dir).unwrap();
+
+        let (addresses, miljodata, parkering) = api_from_dir(dir.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(addresses.len(), city.addresses.len());
+        assert_eq!(miljodata.len(), city.miljodata.len());
+        assert_eq!(parkering.len(), city.parkering.len());
+        for (loaded, generated) in addresses.iter().zip(&city.addresses) {
+            assert_eq!(loaded.adress, generated.adress);
+            assert_eq!(loaded.coordinates[0].round_dp(7), generated.coordinates[0]);
+            assert_eq!(loaded.coordinates[1].round_dp(7), generated.coordinates[1]);
+        }
+        for (loaded, generated) in parkering.iter().zip(&city.parkering) {
+            assert_eq!(
+                (&loaded.info, &loaded.tid, loaded.dag),
+                (&generated.info, &generated.tid, 0)
+            );
+        }
+        assert_eq!(miljodata[0].dag, city.miljodata[0].dag);
+    }
+}
@@ -0,0 +1,244 @@
+//! Spatial index over parking zone segments
+//! Segments are indexed in a local metric projection so radius and bounding box
+//! queries can be answered without scanning every zone
+
+use crate::structs::MiljoeDataClean;
+use rstar::primitives::{GeomWithData, Line};
+use rstar::{AABB, RTree};
+use rust_decimal::prelude::ToPrimitive;
+
+const EARTH_RADIUS_M: f64 = 6371000.0;
+
+type IndexedSegment = GeomWithData<Line<[f64; 2]>, usize>;
+
+/// A zone found by a spatial query
+#[derive(Debug, Clone, Copy)]
+pub struct ZoneMatch<'a> {
+    pub index: usize,
+    pub zone: &'a MiljoeDataClean,
+    /// Haversine distance in meters from the query point to the closest point of the segment
+    pub distance: f64,
+}
+
+pub struct ZoneIndex {
+    zones: Vec<MiljoeDataClean>,
+    /// `[lon, lat]` segments, `None` for zones with unrepresentable coordinates
+    segments: Vec<Option<[[f64; 2]; 2]>>,
+    projection: Projection,
+    rtree: RTree<IndexedSegment>,
+}
+
+/// Equirectangular projection around a reference latitude, in meters
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Projection {
+    meters_per_lon: f64,
+    meters_per_lat: f64,
+}
+
+impl Projection {
+    pub(crate) fn around(latitude: f64) -> Self {
+        let meters_per_lat = EARTH_RADIUS_M.to_radians();
+        Self {
+            meters_per_lon: meters_per_lat * latitude.to_radians().cos(),
+            meters_per_lat,
+        }
+    }
+
+    pub(crate) fn project(&self, point: [f64; 2]) -> [f64; 2] {
+        [
+            point[0] * self.meters_per_lon,
+            point[1] * self.meters_per_lat,
+        ]
+    }
+}
+
+impl ZoneIndex {
+    pub fn new(zones: Vec<MiljoeDataClean>) -> Self {
+        let segments: Vec<Option<[[f64; 2]; 2]>> = zones.iter().map(segment_of).collect();
+
+        let latitudes: Vec<f64> = segments.iter().flatten().map(|s| s[0][1]).collect();
+        let reference = if latitudes.is_empty() {
+            0.0
+        } else {
+            latitudes.iter().sum::<f64>() / latitudes.len() as f64
+        };
+        let projection = Projection::around(reference);
+
+        let indexed = segments
+            .iter()
+            .enumerate()
+            .filter_map(|(index, segment)| {
+                let [start, end] = (*segment)?;
+                let line = Line::new(projection.project(start), projection.project(end));
+                Some(GeomWithData::new(line, index))
+            })
+            .collect();
+
+        Self {
+            zones,
+            segments,
+            projection,
+            rtree: RTree::bulk_load(indexed),
+        }
+    }
+
+    pub fn zones(&self) -> &[MiljoeDataClean] {
+        &self.zones
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// `[lon, lat]` start and end of a zone
+    pub fn segment(&self, index: usize) -> Option<[[f64; 2]; 2]> {
+        self.segments.get(index).copied().flatten()
+    }
+
+    fn zone_match(&self, index: usize, point: [f64; 2]) -> Option<ZoneMatch<'_>> {
+        let [start, end] = self.segment(index)?;
+        Some(ZoneMatch {
+            index,
+            zone: &self.zones[index],
+            distance: distance_point_to_segment(point, start, end),
+        })
+    }
+
+    /// Closest zone to a `[lon, lat]` point within `max_distance` meters
+    pub fn nearest(&self, point: [f64; 2], max_distance: f64) -> Option<ZoneMatch<'_>> {
+        self.within(point, max_distance).into_iter().next()
+    }
+
+    /// All zones within `radius` meters of a `[lon, lat]` point, closest first
+    pub fn within(&self, point: [f64; 2], radius: f64) -> Vec<ZoneMatch<'_>> {
+        // Query slightly wider in projected space and filter on the haversine distance
+        let projected = self.projection.project(point);
+        let search = radius * 1.01 + 1.0;
+
+        let mut matches: Vec<ZoneMatch> = self
+            .rtree
+            .locate_within_distance(projected, search * search)
+            .filter_map(|segment| self.zone_match(segment.data, point))
+            .filter(|m| m.distance <= radius)
+            .collect();
+        matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        matches
+    }
+
+    /// Zones whose segment intersects the bounding box `[min_lon, min_lat]`–`[max_lon, max_lat]`
+    pub fn in_bbox(&self, min: [f64; 2], max: [f64; 2]) -> Vec<(usize, &MiljoeDataClean)> {
+        let envelope =
+            AABB::from_corners(self.projection.project(min), self.projection.project(max));
+
+        let mut indices: Vec<usize> = self
+            .rtree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|segment| segment.data)
+            .collect();
+        indices.sort_unstable();
+        indices.into_iter().map(|i| (i, &self.zones[i])).collect()
+    }
+}
+
+pub(crate) fn segment_of(zone: &MiljoeDataClean) -> Option<[[f64; 2]; 2]> {
+    let c = &zone.coordinates;
+    Some([
+        [c[0][0].to_f64()?, c[0][1].to_f64()?],
+        [c[1][0].to_f64()?, c[1][1].to_f64()?],
+    ])
+}
+
+/// Closest `[lon, lat]` point of a segment to a point
+pub fn closest_point_on_segment(point: [f64; 2], start: [f64; 2], end: [f64; 2]) -> [f64; 2] {
+    let line_vec = [end[0] - start[0], end[1] - start[1]];
+    let point_vec = [point[0] - start[0], point[1] - start[1]];
+
+    // Scale longitude to its length at this latitude so the projection is
+    // perpendicular in meters rather than in degrees
+    let lon_sq = point[1].to_radians().cos().powi(2);
+    let line_len_sq = line_vec[0] * line_vec[0] * lon_sq + line_vec[1] * line_vec[1];
+    if line_len_sq == 0.0 {
+        return start;
+    }
+
+    let t = ((point_vec[0] * line_vec[0] * lon_sq + point_vec[1] * line_vec[1]) / line_len_sq)
+        .clamp(0.0, 1.0);
+    [start[0] + t * line_vec[0], start[1] + t * line_vec[1]]
+}
+
+/// Distance in meters from a `[lon, lat]` point to the closest point of a segment
+pub fn distance_point_to_segment(point: [f64; 2], start: [f64; 2], end: [f64; 2]) -> f64 {
+    haversine_distance(point, closest_point_on_segment(point, start, end))
+}
+
+/// Great-circle distance in meters between two `[lon, lat]` points
+pub fn haversine_distance(point1: [f64; 2], point2: [f64; 2]) -> f64 {
+    let lat1 = point1[1].to_radians();
+    let lat2 = point2[1].to_radians();
+    let delta_lat = (point2[1] - point1[1]).to_radians();
+    let delta_lon = (point2[0] - point1[0]).to_radians();
+
+    let a =
+        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_M * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn zone(x1: f64, y1: f64, x2: f64, y2: f64, info: &str) -> MiljoeDataClean {
+        let d = |v: f64| Decimal::try_from(v).unwrap();
+        MiljoeDataClean {
+            coordinates: [[d(x1), d(y1)], [d(x2), d(y2)]],
+            info: info.to_string(),
+            tid: "0800 - 1200".to_string(),
+            dag: 1,
+        }
+    }
+
+    #[test]
+    fn test_within_and_nearest_use_meters() {
+        let index = ZoneIndex::new(vec![
+            zone(13.0000, 55.6000, 13.0010, 55.6000, "Near"),
+            zone(13.0000, 55.6003, 13.0010, 55.6003, "Middle"), // ~33 m north
+            zone(13.0100, 55.6100, 13.0110, 55.6100, "Far"),
+        ]);
+        let point = [13.0005, 55.6000];
+
+        let names: Vec<_> = index
+            .within(point, 50.0)
+            .iter()
+            .map(|m| m.zone.info.as_str())
+            .collect();
+        assert_eq!(names, vec!["Near", "Middle"]);
+
+        let nearest = index.nearest([13.0005, 55.6002], 50.0).unwrap();
+        assert_eq!(nearest.zone.info, "Middle");
+        assert!((nearest.distance - 11.1).abs() < 0.5);
+
+        assert!(index.nearest([13.05, 55.65], 50.0).is_none());
+    }
+
+    #[test]
+    fn test_bbox_query() {
+        let index = ZoneIndex::new(vec![
+            zone(13.0000, 55.6000, 13.0010, 55.6000, "Inside"),
+            zone(12.9990, 55.6005, 13.0020, 55.6005, "Crossing"),
+            zone(13.0100, 55.6100, 13.0110, 55.6100, "Outside"),
+        ]);
+
+        let found: Vec<_> = index
+            .in_bbox([13.0, 55.5995], [13.001, 55.601])
+            .iter()
+            .map(|(_, z)| z.info.as_str())
+            .collect();
+        assert_eq!(found, vec!["Inside", "Crossing"]);
+    }
+}
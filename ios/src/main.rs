@@ -1,10 +1,26 @@
-use dioxus::prelude::*;
+use amp_ui::Platform;
+use amp_ui::components::notification::{LogNotifier, Notifier};
+use amp_ui::platform::data_dir_override;
+use std::path::PathBuf;
 
-pub mod components;
-pub mod ui;
+/// iOS services; notifications and location are not bridged to the OS yet
+struct Ios;
 
-use ui::App;
+impl Platform for Ios {
+    fn data_dir(&self) -> PathBuf {
+        // Application Support inside the app container
+        data_dir_override().unwrap_or_else(|| {
+            dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("amp")
+        })
+    }
+
+    fn notifier(&self) -> Box<dyn Notifier> {
+        Box::new(LogNotifier::default())
+    }
+}
 
 fn main() {
-    launch(App);
+    amp_ui::launch(Ios);
 }
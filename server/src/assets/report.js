@@ -0,0 +1,327 @@
+// ===================================================================
+// AMP Correlation Report - offline map of correlation results
+// Draws the embedded GeoJSON on a canvas; no tiles or network access needed
+// ===================================================================
+
+const REPORT = {REPORT_DATA};
+const CUTOFF = parseFloat('{CUTOFF}');
+
+const COLORS = {
+    miljo: '#2e7d32',
+    parkering: '#1565c0',
+    unmatched: '#9e9e9e',
+    override: '#6a1b9a',
+};
+
+const zones = [];
+const connectors = [];
+const addresses = [];
+
+for (const feature of REPORT.features) {
+    const p = feature.properties;
+    const c = feature.geometry.coordinates;
+    if (p.kind === 'zone') {
+        zones.push({ ...p, start: c[0], end: c[1] });
+    } else if (p.kind === 'connector') {
+        connectors.push({ ...p, start: c[0], end: c[1] });
+    } else if (p.kind === 'address') {
+        addresses.push({ ...p, point: c, search: `${p.address} ${p.postnummer}`.toLowerCase() });
+    }
+}
+
+const addressByName = new Map(addresses.map(a => [a.address, a]));
+
+// Equirectangular projection around the mean latitude
+const bounds = addresses.reduce(
+    (b, a) => ({
+        minX: Math.min(b.minX, a.point[0]),
+        maxX: Math.max(b.maxX, a.point[0]),
+        minY: Math.min(b.minY, a.point[1]),
+        maxY: Math.max(b.maxY, a.point[1]),
+    }),
+    { minX: Infinity, maxX: -Infinity, minY: Infinity, maxY: -Infinity }
+);
+const lonScale = Math.cos((((bounds.minY + bounds.maxY) / 2) * Math.PI) / 180) || 1;
+
+const canvas = document.getElementById('canvas');
+const ctx = canvas.getContext('2d');
+const popup = document.getElementById('popup');
+const view = { scale: 1, x: 0, y: 0 };
+let selected = null;
+
+function project(point) {
+    return [
+        point[0] * lonScale * view.scale + view.x,
+        -point[1] * view.scale + view.y,
+    ];
+}
+
+function fitView() {
+    const width = canvas.clientWidth;
+    const height = canvas.clientHeight;
+    if (addresses.length === 0) {
+        return;
+    }
+    const spanX = Math.max((bounds.maxX - bounds.minX) * lonScale, 1e-4);
+    const spanY = Math.max(bounds.maxY - bounds.minY, 1e-4);
+    view.scale = 0.9 * Math.min(width / spanX, height / spanY);
+    const centerX = ((bounds.minX + bounds.maxX) / 2) * lonScale;
+    const centerY = (bounds.minY + bounds.maxY) / 2;
+    view.x = width / 2 - centerX * view.scale;
+    view.y = height / 2 + centerY * view.scale;
+}
+
+// Green at 0 m through yellow to red at the cutoff
+function distanceColor(distance) {
+    const ratio = Math.min(Math.max(distance / CUTOFF, 0), 1);
+    const hue = 120 * (1 - ratio);
+    return `hsl(${hue}, 75%, ${40 + 5 * Math.sin(ratio * Math.PI)}%)`;
+}
+
+function readFilters() {
+    const min = parseFloat(document.getElementById('min-distance').value);
+    const max = parseFloat(document.getElementById('max-distance').value);
+    return {
+        miljo: document.getElementById('show-miljo').checked,
+        parkering: document.getElementById('show-parkering').checked,
+        unmatched: document.getElementById('show-unmatched').checked,
+        zones: document.getElementById('show-zones').checked,
+        connectors: document.getElementById('show-connectors').checked,
+        overridesOnly: document.getElementById('overrides-only').checked,
+        min: isNaN(min) ? 0 : min,
+        max: isNaN(max) ? Infinity : max,
+        search: document.getElementById('search').value.trim().toLowerCase(),
+    };
+}
+
+// Distances of the datasets that are shown
+function shownDistances(a, filters) {
+    const distances = [];
+    if (filters.miljo && a.miljo_distance !== null) {
+        distances.push(a.miljo_distance);
+    }
+    if (filters.parkering && a.parkering_distance !== null) {
+        distances.push(a.parkering_distance);
+    }
+    return distances;
+}
+
+function isVisible(a, filters) {
+    if (filters.overridesOnly && a.source !== 'override') {
+        return false;
+    }
+    if (filters.search && !a.search.includes(filters.search)) {
+        return false;
+    }
+    const unmatched = a.miljo_distance === null && a.parkering_distance === null;
+    if (unmatched) {
+        return filters.unmatched;
+    }
+    return shownDistances(a, filters).some(d => d >= filters.min && d <= filters.max);
+}
+
+function drawLine(start, end, color, width) {
+    const [x1, y1] = project(start);
+    const [x2, y2] = project(end);
+    ctx.strokeStyle = color;
+    ctx.lineWidth = width;
+    ctx.beginPath();
+    ctx.moveTo(x1, y1);
+    ctx.lineTo(x2, y2);
+    ctx.stroke();
+}
+
+function draw() {
+    const ratio = window.devicePixelRatio || 1;
+    const width = canvas.clientWidth;
+    const height = canvas.clientHeight;
+    if (canvas.width !== width * ratio || canvas.height !== height * ratio) {
+        canvas.width = width * ratio;
+        canvas.height = height * ratio;
+    }
+    ctx.setTransform(ratio, 0, 0, ratio, 0, 0);
+    ctx.clearRect(0, 0, width, height);
+
+    const filters = readFilters();
+    const visible = addresses.filter(a => isVisible(a, filters));
+    const visibleNames = new Set(visible.map(a => a.address));
+
+    if (filters.zones) {
+        ctx.globalAlpha = 0.8;
+        for (const zone of zones) {
+            if (filters[zone.dataset]) {
+                drawLine(zone.start, zone.end, COLORS[zone.dataset], 3);
+            }
+        }
+    }
+
+    if (filters.connectors) {
+        ctx.globalAlpha = 0.9;
+        for (const connector of connectors) {
+            if (filters[connector.dataset] && visibleNames.has(connector.address)) {
+                drawLine(connector.start, connector.end, distanceColor(connector.distance), 1.5);
+            }
+        }
+    }
+
+    ctx.globalAlpha = 1;
+    const radius = Math.max(2, Math.min(6, view.scale / 40000));
+    for (const a of visible) {
+        const [x, y] = project(a.point);
+        if (x < -radius || y < -radius || x > width + radius || y > height + radius) {
+            continue;
+        }
+        const distances = shownDistances(a, filters);
+        ctx.fillStyle = distances.length
+            ? distanceColor(Math.min(...distances))
+            : COLORS.unmatched;
+        ctx.beginPath();
+        ctx.arc(x, y, a === selected ? radius + 2 : radius, 0, 2 * Math.PI);
+        ctx.fill();
+        if (a.source === 'override' || a === selected) {
+            ctx.strokeStyle = a === selected ? '#000' : COLORS.override;
+            ctx.lineWidth = 2;
+            ctx.stroke();
+        }
+    }
+
+    updateStats(visible, filters);
+    positionPopup();
+}
+
+function updateStats(visible, filters) {
+    const matched = visible.filter(a => shownDistances(a, filters).length > 0);
+    const overrides = visible.filter(a => a.source === 'override').length;
+    const distances = matched.map(a => Math.min(...shownDistances(a, filters)));
+    const mean = distances.length
+        ? distances.reduce((sum, d) => sum + d, 0) / distances.length
+        : 0;
+    document.getElementById('stats').innerHTML = `
+        <strong>${visible.length}</strong> of ${addresses.length} addresses shown<br>
+        ${matched.length} matched, ${visible.length - matched.length} unmatched<br>
+        ${overrides} overrides<br>
+        Mean distance: ${mean.toFixed(1)} m<br>
+        ${zones.length} matched segments
+    `;
+}
+
+function escapeHtml(text) {
+    const div = document.createElement('div');
+    div.textContent = text;
+    return div.innerHTML;
+}
+
+function matchLine(label, distance, info) {
+    if (distance === null) {
+        return `<div><strong>${label}:</strong> <span class="none">no match</span></div>`;
+    }
+    return `<div><strong>${label}:</strong> ${distance.toFixed(2)} m<br>${escapeHtml(info)}</div>`;
+}
+
+function showPopup(a) {
+    selected = a;
+    if (!a) {
+        popup.style.display = 'none';
+        return;
+    }
+    popup.innerHTML = `
+        <h3>${escapeHtml(a.address)}</h3>
+        <div>${escapeHtml(a.postnummer)}</div>
+        ${a.source === 'override' ? '<div class="override">Manual override</div>' : ''}
+        ${matchLine('Miljödata', a.miljo_distance, a.miljo_info)}
+        ${matchLine('Parkering', a.parkering_distance, a.parkering_info)}
+    `;
+    popup.style.display = 'block';
+}
+
+function positionPopup() {
+    if (!selected) {
+        return;
+    }
+    const [x, y] = project(selected.point);
+    popup.style.left = `${Math.min(x + 12, canvas.clientWidth - popup.offsetWidth - 4)}px`;
+    popup.style.top = `${Math.max(y - popup.offsetHeight - 12, 4)}px`;
+}
+
+function addressAt(x, y) {
+    const filters = readFilters();
+    let best = null;
+    let bestDistance = 8 * 8;
+    for (const a of addresses) {
+        const [ax, ay] = project(a.point);
+        const d = (ax - x) ** 2 + (ay - y) ** 2;
+        if (d < bestDistance && isVisible(a, filters)) {
+            best = a;
+            bestDistance = d;
+        }
+    }
+    return best;
+}
+
+// Pan and zoom
+let drag = null;
+
+canvas.addEventListener('mousedown', event => {
+    drag = { x: event.offsetX, y: event.offsetY, moved: false };
+    canvas.classList.add('dragging');
+});
+
+canvas.addEventListener('mousemove', event => {
+    if (!drag) {
+        return;
+    }
+    const dx = event.offsetX - drag.x;
+    const dy = event.offsetY - drag.y;
+    if (Math.abs(dx) + Math.abs(dy) > 2) {
+        drag.moved = true;
+    }
+    view.x += dx;
+    view.y += dy;
+    drag.x = event.offsetX;
+    drag.y = event.offsetY;
+    draw();
+});
+
+window.addEventListener('mouseup', event => {
+    if (drag && !drag.moved && event.target === canvas) {
+        showPopup(addressAt(event.offsetX, event.offsetY));
+        draw();
+    }
+    drag = null;
+    canvas.classList.remove('dragging');
+});
+
+canvas.addEventListener('wheel', event => {
+    event.preventDefault();
+    const factor = Math.exp(-event.deltaY * 0.0015);
+    view.x = event.offsetX - (event.offsetX - view.x) * factor;
+    view.y = event.offsetY - (event.offsetY - view.y) * factor;
+    view.scale *= factor;
+    draw();
+}, { passive: false });
+
+for (const id of ['show-miljo', 'show-parkering', 'show-unmatched', 'show-zones', 'show-connectors', 'overrides-only', 'min-distance', 'max-distance', 'search']) {
+    document.getElementById(id).addEventListener('input', draw);
+}
+
+// Enter in the search field zooms to the first visible match
+document.getElementById('search').addEventListener('keydown', event => {
+    if (event.key !== 'Enter') {
+        return;
+    }
+    const filters = readFilters();
+    const match = addressByName.get(event.target.value.trim())
+        || addresses.find(a => isVisible(a, filters));
+    if (match) {
+        view.scale = Math.max(view.scale, 500000);
+        view.x = canvas.clientWidth / 2 - match.point[0] * lonScale * view.scale;
+        view.y = canvas.clientHeight / 2 + match.point[1] * view.scale;
+        showPopup(match);
+        draw();
+    }
+});
+
+window.addEventListener('resize', draw);
+
+fitView();
+draw();
@@ -1,13 +1,19 @@
 // ===================================================================
 // AMP StadsAtlas Interface - JavaScript Controller
 // Handles address search, map loading, tab switching, and logging
-// Uses localStorage for persistent classification data (works with file:// URLs)
+// Uses localStorage for persistent classification data (works with file:// URLs),
+// or the review server's API when served by `amp-server review`
 // ===================================================================
 
 const BASE_URL = 'https://geo.malmo.se/api/search';
 let shouldAutoLoad = true; // Flag to auto-load on page load
 let hasAutoLoaded = false; // Track if auto-load has already happened
 
+// Review server API base and queue position; empty for file:// pages
+const REVIEW_API = '{REVIEW_API}';
+const REVIEW_INDEX = parseInt('{REVIEW_INDEX}', 10);
+const isReviewSession = REVIEW_API !== '';
+
 // Storage keys
 const STORAGE_KEY_NOT_MATCHING = 'amp_classification_notMatching';
 const STORAGE_KEY_INVALID = 'amp_classification_invalid';
@@ -127,7 +133,7 @@ function loadMapWithAddress(address, x, y) {
     
     logToConsole('MAP', `✓ iframe element found`);
     
-    // Use the embedded data URI for origo_map.html
+    // Use the embedded data URI (or served page) for origo_map.html
     const origoDataUri = '{ORIGO_DATA_URI}';
     
     // Append coordinates and zoom level to the data URI hash
@@ -262,6 +268,11 @@ function handleDataReviewAction(category) {
         return;
     }
 
+    if (isReviewSession) {
+        postReviewAction('classify', category);
+        return;
+    }
+
     const snapshot = buildCurrentDataSnapshot();
 
     // If there is no address, do nothing
@@ -306,6 +317,11 @@ function undoDataReviewAction(category) {
         return;
     }
 
+    if (isReviewSession) {
+        postReviewAction('undo', category);
+        return;
+    }
+
     const lastAdded = JSON.parse(localStorage.getItem(STORAGE_KEY_LAST_ADDED) || '{}');
     const lastId = lastAdded[category];
 
@@ -336,6 +352,47 @@ function undoDataReviewAction(category) {
     logToConsole('DATA', `Total ${category} entries: ${entries.length}`);
 }
 
+// Send a classification or undo for this address to the review server
+function postReviewAction(action, category) {
+    fetch(`${REVIEW_API}/${action}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ index: REVIEW_INDEX, category })
+    })
+        .then(response => response.json())
+        .then(result => {
+            logToConsole(result.success ? 'DATA' : 'ERROR', result.message);
+            updateReviewProgress();
+        })
+        .catch(error => logToConsole('ERROR', `Review server unreachable: ${error.message}`));
+}
+
+// Mark this address as reviewed and open the next one in the queue
+function nextReviewItem() {
+    fetch(`${REVIEW_API}/next`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ index: REVIEW_INDEX })
+    })
+        .then(response => response.json())
+        .then(result => {
+            window.location.href = result.next === null ? '/' : `/review/${result.next}`;
+        })
+        .catch(error => logToConsole('ERROR', `Review server unreachable: ${error.message}`));
+}
+
+function updateReviewProgress() {
+    fetch(`${REVIEW_API}/session`)
+        .then(response => response.json())
+        .then(session => {
+            const progress = document.getElementById('review-progress');
+            if (progress) {
+                progress.textContent = `Address ${REVIEW_INDEX + 1} of ${session.total} · ${session.reviewed} reviewed`;
+            }
+        })
+        .catch(error => logToConsole('ERROR', `Review server unreachable: ${error.message}`));
+}
+
 // Initialize on page load
 document.addEventListener('DOMContentLoaded', function() {
     logToConsole('READY', 'AMP Testing Interface initialized');
@@ -354,8 +411,14 @@ document.addEventListener('DOMContentLoaded', function() {
     logToConsole('INFO', '  2. Compare the line\'s data with what you see in the Data tab.');
     logToConsole('INFO', '  3. If it does NOT match, press "No - Not matching".');
     logToConsole('INFO', '  4. If the data is NOT relevant for the address, press "No - Invalid/Irrelevant".');
-    logToConsole('INFO', '  5. Then close this tab and move to the next one.');
-    logToConsole('INFO', '  6. When done with all tabs, click "Download JSON" to export classifications.');
+    if (isReviewSession) {
+        logToConsole('INFO', '  5. Then press "Next address" - verdicts are saved by the review server.');
+        document.body.classList.add('review-session');
+        updateReviewProgress();
+    } else {
+        logToConsole('INFO', '  5. Then close this tab and move to the next one.');
+        logToConsole('INFO', '  6. When done with all tabs, click "Download JSON" to export classifications.');
+    }
     logToConsole('INFO', '');
     
     // Set up search button
@@ -4,34 +4,37 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Get the classification data directory (Documents/amp_classifications)
-fn _get_classification_dir() -> Result<PathBuf, String> {
-    let _home = dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string())?;
+/// Categories a reviewer can file an address under
+pub const CATEGORIES: [&str; 2] = ["notMatching", "invalid"];
 
-    let _docs_dir = _home.join("Documents");
-
-    let _class_dir = _docs_dir.join("amp_classifications");
+/// Get the default classification data directory (Documents/amp_classifications)
+pub fn classification_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string())?;
+    Ok(home.join("Documents").join("amp_classifications"))
+}
 
-    // Create directory if it doesn't exist
-    if !_class_dir.exists() {
-        fs::create_dir_all(&_class_dir)
+/// Get path to classification JSON file for a category, creating the directory if needed
+fn classification_file(dir: &Path, category: &str) -> Result<PathBuf, String> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)
             .map_err(|e| format!("Failed to create classifications directory: {}", e))?;
     }
-
-    Ok(_class_dir)
+    Ok(dir.join(format!("amp_stadsatlas_{}.json", category)))
 }
 
-/// Get path to classification JSON file for a category
-fn _get_classification_file(_category: &str) -> Result<PathBuf, String> {
-    let _dir = _get_classification_dir()?;
-    Ok(_dir.join(format!("amp_stadsatlas_{}.json", _category)))
+fn validate_category(category: &str) -> Result<(), String> {
+    if CATEGORIES.contains(&category) {
+        Ok(())
+    } else {
+        Err(format!("Invalid category: {}", category))
+    }
 }
 
 /// Classification entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct _ClassificationEntry {
+pub struct ClassificationEntry {
     pub id: String,
     pub timestamp: String,
     pub address: String,
@@ -42,14 +45,14 @@ pub struct _ClassificationEntry {
 
 /// Request body for classification
 #[derive(Debug, Serialize, Deserialize)]
-pub struct _ClassificationRequest {
+pub struct ClassificationRequest {
     pub category: String,
-    pub data: _ClassificationData,
+    pub data: ClassificationData,
     pub timestamp: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct _ClassificationData {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationData {
     pub address: String,
     pub postal_code: String,
     pub source: String,
@@ -58,25 +61,21 @@ pub struct _ClassificationData {
 
 /// Response body for classification operations
 #[derive(Debug, Serialize, Deserialize)]
-pub struct _ClassificationResponse {
+pub struct ClassificationResponse {
     pub success: bool,
     pub message: String,
     pub id: Option<String>,
 }
 
-/// Add classification entry to JSON file
-pub fn _add_classification(_req: &_ClassificationRequest) -> Result<String, String> {
-    // Validate category
-    if _req.category != "notMatching" && _req.category != "invalid" {
-        return Err(format!("Invalid category: {}", _req.category));
-    }
-
-    let _file_path = _get_classification_file(&_req.category)?;
+/// Add classification entry to the category's JSON file in `dir`
+pub fn add_classification(dir: &Path, req: &ClassificationRequest) -> Result<String, String> {
+    validate_category(&req.category)?;
+    let file_path = classification_file(dir, &req.category)?;
 
     // Generate unique ID
-    let _id = format!(
+    let id = format!(
         "{}-{}-{}",
-        _req.category,
+        req.category,
         chrono::Local::now().timestamp_millis(),
         uuid::Uuid::new_v4()
             .to_string()
@@ -86,99 +85,97 @@ pub fn _add_classification(_req: &_ClassificationRequest) -> Result<String, Stri
     );
 
     // Create entry
-    let _entry = _ClassificationEntry {
-        id: _id.clone(),
-        timestamp: _req.timestamp.clone(),
-        address: _req.data.address.clone(),
-        postal_code: _req.data.postal_code.clone(),
-        source: _req.data.source.clone(),
-        matches_html: _req.data.matches_html.clone(),
+    let entry = ClassificationEntry {
+        id: id.clone(),
+        timestamp: req.timestamp.clone(),
+        address: req.data.address.clone(),
+        postal_code: req.data.postal_code.clone(),
+        source: req.data.source.clone(),
+        matches_html: req.data.matches_html.clone(),
     };
 
     // Load or create JSON structure
-    let mut _json: Value = if _file_path.exists() {
-        let _content = fs::read_to_string(&_file_path)
+    let mut json: Value = if file_path.exists() {
+        let content = fs::read_to_string(&file_path)
             .map_err(|e| format!("Failed to read classification file: {}", e))?;
-        serde_json::from_str(&_content).unwrap_or_else(|_| json!({ "entries": [] }))
+        serde_json::from_str(&content).unwrap_or_else(|_| json!({ "entries": [] }))
     } else {
         json!({ "entries": [] })
     };
 
     // Ensure entries array exists
-    if !_json["entries"].is_array() {
-        _json["entries"] = json!([])
+    if !json["entries"].is_array() {
+        json["entries"] = json!([])
     }
 
     // Add new entry
-    if let Some(_entries) = _json["entries"].as_array_mut() {
-        _entries.push(
-            serde_json::to_value(&_entry)
+    if let Some(entries) = json["entries"].as_array_mut() {
+        entries.push(
+            serde_json::to_value(&entry)
                 .map_err(|e| format!("Failed to serialize entry: {}", e))?,
         );
     }
 
     // Write back to file
-    let _json_str = serde_json::to_string_pretty(&_json)
+    let json_str = serde_json::to_string_pretty(&json)
         .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
 
-    fs::write(&_file_path, _json_str)
+    fs::write(&file_path, json_str)
         .map_err(|e| format!("Failed to write classification file: {}", e))?;
 
-    Ok(_id)
+    Ok(id)
 }
 
-/// Undo the last classification for a category and address
-pub fn _undo_classification(_category: &str, _address: &str) -> Result<String, String> {
-    // Validate category
-    if _category != "notMatching" && _category != "invalid" {
-        return Err(format!("Invalid category: {}", _category));
-    }
-
-    let _file_path = _get_classification_file(_category)?;
+/// Undo the classification with `id` in a category of `dir`
+/// Other entries for the same address, e.g. from another review session, are kept.
+pub fn undo_classification(dir: &Path, category: &str, id: &str) -> Result<String, String> {
+    validate_category(category)?;
+    let file_path = classification_file(dir, category)?;
 
     // If file doesn't exist, nothing to undo
-    if !_file_path.exists() {
+    if !file_path.exists() {
         return Err(format!(
             "No classifications found for category: {}",
-            _category
+            category
         ));
     }
 
     // Load JSON
-    let _content = fs::read_to_string(&_file_path)
+    let content = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read classification file: {}", e))?;
 
-    let mut _json: Value = serde_json::from_str(&_content)
+    let mut json: Value = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse classification file: {}", e))?;
 
     // Get entries array
-    if let Some(_entries) = _json["entries"].as_array_mut() {
-        // Find last entry matching this address
-        if let Some(_last_index) = _entries.iter().rposition(|e| {
-            e.get("address")
-                .and_then(|addr| addr.as_str())
-                .map(|addr| addr == _address)
-                .unwrap_or(false)
-        }) {
-            _entries.remove(_last_index);
-
-            // Write back to file
-            let _json_str = serde_json::to_string_pretty(&_json)
-                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-            fs::write(&_file_path, _json_str)
-                .map_err(|e| format!("Failed to write classification file: {}", e))?;
-
-            return Ok(format!(
-                "Undid last classification for '{}' in category '{}'",
-                _address, _category
-            ));
-        }
+    if let Some(entries) = json["entries"].as_array_mut()
+        && let Some(index) = entries
+            .iter()
+            .position(|e| e.get("id").and_then(|v| v.as_str()) == Some(id))
+    {
+        let removed = entries.remove(index);
+        let address = removed
+            .get("address")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // Write back to file
+        let json_str = serde_json::to_string_pretty(&json)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+
+        fs::write(&file_path, json_str)
+            .map_err(|e| format!("Failed to write classification file: {}", e))?;
+
+        return Ok(format!(
+            "Undid classification for '{}' in category '{}'",
+            address, category
+        ));
     }
 
     Err(format!(
-        "No classifications found for '{}' in category '{}'",
-        _address, _category
+        "No classification '{}' found in category '{}'",
+        id, category
     ))
 }
 
@@ -188,9 +185,40 @@ mod tests {
 
     #[test]
     fn test_get_classification_dir() {
-        let _result = _get_classification_dir();
-        assert!(_result.is_ok());
-        let _dir = _result.unwrap();
-        assert!(_dir.ends_with("amp_classifications"));
+        let result = classification_dir();
+        assert!(result.is_ok());
+        let dir = result.unwrap();
+        assert!(dir.ends_with("amp_classifications"));
+    }
+
+    #[test]
+    fn test_add_and_undo_classification() {
+        let dir = std::env::temp_dir().join(format!("amp_classify_{}", std::process::id()));
+        let request = |category: &str| ClassificationRequest {
+            category: category.to_string(),
+            data: ClassificationData {
+                address: "Storgatan 1".to_string(),
+                postal_code: "211 42".to_string(),
+                source: "Miljödata".to_string(),
+                matches_html: String::new(),
+            },
+            timestamp: "2026-03-15T09:00:00Z".to_string(),
+        };
+
+        let id = add_classification(&dir, &request("notMatching")).unwrap();
+        assert!(id.starts_with("notMatching-"));
+        assert!(add_classification(&dir, &request("correct")).is_err());
+        // Same address filed again later, e.g. from another session
+        let later = add_classification(&dir, &request("notMatching")).unwrap();
+
+        let path = dir.join("amp_stadsatlas_notMatching.json");
+        assert!(fs::read_to_string(&path).unwrap().contains(&id));
+
+        assert!(undo_classification(&dir, "notMatching", &id).is_ok());
+        assert!(undo_classification(&dir, "notMatching", &id).is_err());
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains(&id));
+        assert!(content.contains(&later));
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
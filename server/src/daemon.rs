@@ -0,0 +1,588 @@
+//! Daemon mode: periodically refresh source data and republish correlation results
+//! Each cycle checks the remote datasets, installs changed ones, re-runs the correlation
+//! and writes a new versioned Parquet file plus `manifest.json` to the output directory.
+//! Events are logged as JSON lines on stdout and the last run is recorded in `status.json`.
+
+use crate::{AlgorithmChoice, correlate_dataset, merge_results};
+use amp_core::api::api_from_dir_quiet;
+use amp_core::checksum::{DataChecksum, DataSource, SourceStatus};
+use amp_core::fetch::Fetcher;
+use amp_core::overrides::OverrideSet;
+use amp_core::parquet::write_correlation_parquet_to;
+use chrono::Utc;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const STATUS_FILE: &str = "status.json";
+
+pub struct DaemonConfig {
+    pub data_dir: String,
+    pub output_dir: PathBuf,
+    pub checksum_file: String,
+    /// Serves the dataset files, see [`Fetcher::new`]
+    pub base_url: String,
+    pub interval: Duration,
+    pub algorithm: AlgorithmChoice,
+    pub cutoff: f64,
+    /// Number of published versions to keep
+    pub keep: usize,
+    /// Run a single cycle and exit
+    pub once: bool,
+    /// Manual overrides applied to each published version, if the file exists
+    pub overrides: String,
+}
+
+/// A published set of correlation results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedVersion {
+    pub version: String,
+    pub created: String,
+    pub file: String,
+    pub algorithm: String,
+    pub cutoff: f64,
+    pub rows: usize,
+    pub matched: usize,
+    pub checksums: BTreeMap<DataSource, String>,
+}
+
+/// Index of the published versions, newest last
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub latest: Option<String>,
+    pub versions: Vec<PublishedVersion>,
+}
+
+impl Manifest {
+    pub fn load(output_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = output_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        write_atomic(
+            &output_dir.join(MANIFEST_FILE),
+            &serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    /// Drop all but the newest `keep` versions, returning the removed entries
+    pub fn prune(&mut self, keep: usize) -> Vec<PublishedVersion> {
+        let excess = self.versions.len().saturating_sub(keep.max(1));
+        self.versions.drain(..excess).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+pub enum CycleOutcome {
+    Published { version: String },
+    Unchanged,
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleRecord {
+    pub started: String,
+    pub finished: String,
+    pub duration_ms: u128,
+    #[serde(flatten)]
+    pub outcome: CycleOutcome,
+}
+
+/// Contents of the status file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    /// running, idle or stopped
+    pub state: String,
+    pub pid: u32,
+    pub started: String,
+    pub cycles: u64,
+    pub failures: u64,
+    pub last_run: Option<CycleRecord>,
+    pub latest_version: Option<String>,
+    pub next_run: Option<String>,
+}
+
+impl DaemonStatus {
+    fn new() -> Self {
+        Self {
+            state: "running".to_string(),
+            pid: std::process::id(),
+            started: Utc::now().to_rfc3339(),
+            cycles: 0,
+            failures: 0,
+            last_run: None,
+            latest_version: None,
+            next_run: None,
+        }
+    }
+
+    fn save(&self, output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        write_atomic(
+            &output_dir.join(STATUS_FILE),
+            &serde_json::to_string_pretty(self)?,
+        )
+    }
+}
+
+/// Write a JSON log line: `{"ts": ..., "level": ..., "event": ..., <fields>}`
+fn log_event(level: &str, event: &str, fields: Value) {
+    let mut entry = json!({
+        "ts": Utc::now().to_rfc3339(),
+        "level": level,
+        "event": event,
+    });
+    if let (Some(entry), Value::Object(fields)) = (entry.as_object_mut(), fields) {
+        entry.extend(fields);
+    }
+    println!("{}", entry);
+}
+
+fn write_atomic(path: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Run cycles on the configured interval until interrupted (or once with `config.once`)
+pub async fn run(config: DaemonConfig) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(&config.output_dir)?;
+
+    let mut status = DaemonStatus::new();
+    status.latest_version = Manifest::load(&config.output_dir)?.latest;
+    status.save(&config.output_dir)?;
+
+    let mut checksums = DataChecksum::load_from_file(&config.checksum_file)
+        .unwrap_or_else(|_| DataChecksum::malmo());
+
+    log_event(
+        "info",
+        "daemon_started",
+        json!({
+            "data_dir": config.data_dir,
+            "output_dir": config.output_dir,
+            "interval_secs": config.interval.as_secs(),
+            "algorithm": format!("{:?}", config.algorithm),
+            "cutoff": config.cutoff,
+        }),
+    );
+
+    loop {
+        status.state = "running".to_string();
+        status.next_run = None;
+        status.save(&config.output_dir)?;
+
+        let started = Utc::now();
+        let timer = Instant::now();
+        log_event(
+            "info",
+            "cycle_started",
+            json!({ "cycle": status.cycles + 1 }),
+        );
+
+        // Checksums are only kept if the cycle completes, so a failed cycle is retried
+        let snapshot = checksums.clone();
+        let outcome = match run_cycle(&config, &mut checksums).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                checksums = snapshot;
+                CycleOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+        };
+
+        let record = CycleRecord {
+            started: started.to_rfc3339(),
+            finished: Utc::now().to_rfc3339(),
+            duration_ms: timer.elapsed().as_millis(),
+            outcome: outcome.clone(),
+        };
+        let level = match outcome {
+            CycleOutcome::Failed { .. } => {
+                status.failures += 1;
+                "error"
+            }
+            CycleOutcome::Published { ref version } => {
+                status.latest_version = Some(version.clone());
+                "info"
+            }
+            CycleOutcome::Unchanged => "info",
+        };
+        log_event(level, "cycle_finished", serde_json::to_value(&record)?);
+        status.cycles += 1;
+        status.last_run = Some(record);
+
+        if config.once {
+            status.state = "stopped".to_string();
+            status.save(&config.output_dir)?;
+            return match outcome {
+                CycleOutcome::Failed { error } => Err(error.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let next_run = Utc::now() + config.interval;
+        status.state = "idle".to_string();
+        status.next_run = Some(next_run.to_rfc3339());
+        status.save(&config.output_dir)?;
+
+        tokio::select! {
+            _ = tokio::time::sleep(config.interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                log_event("info", "daemon_stopped", json!({ "cycles": status.cycles }));
+                status.state = "stopped".to_string();
+                status.next_run = None;
+                status.save(&config.output_dir)?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Check, fetch, correlate and publish once
+/// `checksums` is updated but only saved to disk when the cycle succeeds; datasets
+/// replaced by a failed cycle are restored so they match the unsaved checksums.
+pub async fn run_cycle(
+    config: &DaemonConfig,
+    checksums: &mut DataChecksum,
+) -> Result<CycleOutcome, Box<dyn std::error::Error>> {
+    fs::create_dir_all(&config.data_dir)?;
+    let fetcher = Fetcher::new(&config.data_dir, Some(config.base_url.clone()));
+
+    let mut replaced = Vec::new();
+    let result = cycle(config, checksums, &fetcher, &mut replaced).await;
+    if result.is_err() {
+        for (source, existed) in replaced.into_iter().rev() {
+            let restored = if existed {
+                fetcher.rollback(source).map(|_| ())
+            } else {
+                fs::remove_file(fetcher.dataset_path(source))
+            };
+            log_event(
+                if restored.is_ok() { "warn" } else { "error" },
+                "source_restored",
+                json!({
+                    "source": source,
+                    "error": restored.err().map(|e| e.to_string()),
+                }),
+            );
+        }
+    }
+    result
+}
+
+/// Body of [`run_cycle`]; `replaced` collects each replaced dataset and whether it
+/// existed before
+async fn cycle(
+    config: &DaemonConfig,
+    checksums: &mut DataChecksum,
+    fetcher: &Fetcher,
+    replaced: &mut Vec<(DataSource, bool)>,
+) -> Result<CycleOutcome, Box<dyn std::error::Error>> {
+    // Conditional requests against the base URL; the returned bodies are installed
+    // directly instead of being downloaded a second time
+    for source in DataSource::ALL {
+        checksums.set_url(source, fetcher.source_url(source)?);
+    }
+    let reports = checksums
+        .check_remote(Some(Path::new(&config.data_dir)))
+        .await;
+
+    let mut updated = false;
+    for report in reports {
+        let source = report.source;
+        let (status, error) = match &report.status {
+            SourceStatus::Changed => ("changed", None),
+            SourceStatus::Unchanged => ("unchanged", None),
+            SourceStatus::Error(e) => ("error", Some(e.clone())),
+        };
+        log_event(
+            if error.is_some() { "warn" } else { "info" },
+            "source_checked",
+            json!({
+                "source": source,
+                "status": status,
+                "error": error,
+                "diff": report.diff,
+            }),
+        );
+
+        let existed = fetcher.dataset_path(source).exists();
+        let url = checksums.url(source).to_string();
+        let fetched = match report.body {
+            Some(body) => fetcher.install(source, &body),
+            // Not modified or unreachable: keep the local copy if there is one
+            None if existed => continue,
+            None => fetcher.fetch_source(source, &url).await,
+        }
+        .map_err(|e| format!("Failed to fetch {} from {}: {}", source.name(), url, e))?;
+        checksums.set_checksum(source, fetched.checksum.clone());
+        if fetched.updated {
+            replaced.push((source, existed));
+            updated = true;
+        }
+
+        log_event(
+            "info",
+            "source_fetched",
+            json!({
+                "source": source,
+                "updated": fetched.updated,
+                "features": fetched.features,
+                "bytes": fetched.bytes,
+            }),
+        );
+    }
+    checksums.last_checked = Utc::now().to_rfc3339();
+
+    let mut manifest = Manifest::load(&config.output_dir)?;
+    if !updated && manifest.latest.is_some() {
+        checksums.save_to_file(&config.checksum_file)?;
+        return Ok(CycleOutcome::Unchanged);
+    }
+
+    let published = correlate_and_publish(config, checksums, &mut manifest)?;
+    checksums.save_to_file(&config.checksum_file)?;
+
+    Ok(CycleOutcome::Published {
+        version: published.version,
+    })
+}
+
+fn correlate_and_publish(
+    config: &DaemonConfig,
+    checksums: &DataChecksum,
+    manifest: &mut Manifest,
+) -> Result<PublishedVersion, Box<dyn std::error::Error>> {
+    let timer = Instant::now();
+    let (addresses, miljodata, parkering) = api_from_dir_quiet(&config.data_dir)?;
+
+    let pb = ProgressBar::hidden();
+    let miljo_results = correlate_dataset(
+        &config.algorithm,
+        &addresses,
+        &miljodata,
+        config.cutoff,
+        &pb,
+    )?;
+    let parkering_results = correlate_dataset(
+        &config.algorithm,
+        &addresses,
+        &parkering,
+        config.cutoff,
+        &pb,
+    )?;
+    let mut results = merge_results(&addresses, &miljo_results, &parkering_results);
+
+    if Path::new(&config.overrides).exists() {
+        let overrides = OverrideSet::load_from_file(&config.overrides)
+            .map_err(|e| format!("Failed to load overrides {}: {}", config.overrides, e))?;
+        let report = overrides.apply(&mut results, &addresses, &miljodata, &parkering);
+        for warning in &report.warnings {
+            log_event(
+                "warn",
+                "override_skipped",
+                json!({ "file": config.overrides, "reason": warning }),
+            );
+        }
+        log_event(
+            "info",
+            "overrides_applied",
+            json!({ "applied": report.applied, "total": overrides.overrides.len() }),
+        );
+    }
+
+    let rows = results.len();
+    let matched = results.iter().filter(|r| r.has_match()).count();
+    log_event(
+        "info",
+        "correlation_finished",
+        json!({
+            "addresses": addresses.len(),
+            "miljo_zones": miljodata.len(),
+            "parkering_zones": parkering.len(),
+            "matched": matched,
+            "duration_ms": timer.elapsed().as_millis(),
+        }),
+    );
+
+    // Versions are timestamps; a suffix keeps them unique within the same second
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut version = stamp.clone();
+    let mut n = 1;
+    while manifest.versions.iter().any(|v| v.version == version) {
+        n += 1;
+        version = format!("{}-{}", stamp, n);
+    }
+
+    let file = format!("correlation_{}.parquet", version);
+    let path = config.output_dir.join(&file);
+    write_correlation_parquet_to(&path.to_string_lossy(), results)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let published = PublishedVersion {
+        version: version.clone(),
+        created: Utc::now().to_rfc3339(),
+        file,
+        algorithm: format!("{:?}", config.algorithm),
+        cutoff: config.cutoff,
+        rows,
+        matched,
+        checksums: DataSource::ALL
+            .iter()
+            .map(|s| (*s, checksums.checksum(*s).to_string()))
+            .collect(),
+    };
+    manifest.versions.push(published.clone());
+    manifest.latest = Some(version);
+
+    for old in manifest.prune(config.keep) {
+        let _ = fs::remove_file(config.output_dir.join(&old.file));
+    }
+    manifest.save(&config.output_dir)?;
+
+    log_event(
+        "info",
+        "version_published",
+        json!({ "version": published.version, "file": published.file, "rows": rows }),
+    );
+
+    Ok(published)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(feature: &str) -> String {
+        format!(r#"{{"type":"FeatureCollection","features":[{}]}}"#, feature)
+    }
+
+    fn publish_sources(remote: &Path, zone_info: &str) {
+        let address = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[13.0001,55.6001]},"properties":{"BELADRESS":"Storgatan 1","POSTNR":"21142","ADRESSOMR":"Storgatan","ADRESSPLAT":"1"}}"#;
+        let zone = format!(
+            r#"{{"type":"Feature","geometry":{{"type":"LineString","coordinates":[[13.0,55.6],[13.0003,55.6]]}},"properties":{{"value":"{}","tid":"0800 - 1200","day":1}}}}"#,
+            zone_info
+        );
+        fs::write(
+            remote.join(DataSource::Adresser.file_name()),
+            collection(address),
+        )
+        .unwrap();
+        fs::write(
+            remote.join(DataSource::Miljo.file_name()),
+            collection(&zone),
+        )
+        .unwrap();
+        fs::write(
+            remote.join(DataSource::Parkering.file_name()),
+            collection(&zone),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cycle_publishes_only_when_data_changes() {
+        let root = std::env::temp_dir().join(format!("amp_daemon_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish_sources(&remote, "Städning");
+
+        let config = DaemonConfig {
+            data_dir: root.join("data").to_string_lossy().to_string(),
+            output_dir: root.join("published"),
+            checksum_file: root.join("checksums.json").to_string_lossy().to_string(),
+            base_url: format!("file://{}", remote.display()),
+            interval: Duration::from_secs(60),
+            algorithm: AlgorithmChoice::RTree,
+            cutoff: 50.,
+            keep: 1,
+            once: true,
+            overrides: root.join("overrides.json").to_string_lossy().to_string(),
+        };
+        fs::create_dir_all(&config.output_dir).unwrap();
+        let mut checksums = DataChecksum::malmo();
+
+        let first = run_cycle(&config, &mut checksums).await.unwrap();
+        assert!(matches!(first, CycleOutcome::Published { .. }));
+        let manifest = Manifest::load(&config.output_dir).unwrap();
+        assert_eq!(manifest.versions[0].rows, 1);
+        assert_eq!(manifest.versions[0].matched, 1);
+
+        let second = run_cycle(&config, &mut checksums).await.unwrap();
+        assert_eq!(second, CycleOutcome::Unchanged);
+
+        publish_sources(&remote, "Gatusopning");
+        let third = run_cycle(&config, &mut checksums).await.unwrap();
+        let manifest = Manifest::load(&config.output_dir).unwrap();
+        assert_eq!(
+            third,
+            CycleOutcome::Published {
+                version: manifest.latest.clone().unwrap()
+            }
+        );
+        // keep = 1: the first version was pruned
+        assert_eq!(manifest.versions.len(), 1);
+        let files = fs::read_dir(&config.output_dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension() == Some("parquet".as_ref()))
+            .count();
+        assert_eq!(files, 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_cycle_restores_replaced_datasets() {
+        let root = std::env::temp_dir().join(format!("amp_daemon_fail_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let remote = root.join("remote");
+        fs::create_dir_all(&remote).unwrap();
+        publish_sources(&remote, "Städning");
+
+        let config = DaemonConfig {
+            data_dir: root.join("data").to_string_lossy().to_string(),
+            output_dir: root.join("published"),
+            checksum_file: root.join("checksums.json").to_string_lossy().to_string(),
+            base_url: format!("file://{}", remote.display()),
+            interval: Duration::from_secs(60),
+            algorithm: AlgorithmChoice::RTree,
+            cutoff: 50.,
+            keep: 2,
+            once: true,
+            overrides: root.join("overrides.json").to_string_lossy().to_string(),
+        };
+        fs::create_dir_all(&config.output_dir).unwrap();
+        let mut checksums = DataChecksum::malmo();
+        run_cycle(&config, &mut checksums).await.unwrap();
+
+        // An unreadable override table fails the cycle after the data was replaced
+        publish_sources(&remote, "Gatusopning");
+        fs::write(&config.overrides, "not json").unwrap();
+        let mut unsaved = checksums.clone();
+        assert!(run_cycle(&config, &mut unsaved).await.is_err());
+        let miljo = Path::new(&config.data_dir).join(DataSource::Miljo.file_name());
+        assert!(fs::read_to_string(&miljo).unwrap().contains("Städning"));
+
+        fs::remove_file(&config.overrides).unwrap();
+        let retry = run_cycle(&config, &mut checksums).await.unwrap();
+        assert!(matches!(retry, CycleOutcome::Published { .. }));
+        assert!(fs::read_to_string(&miljo).unwrap().contains("Gatusopning"));
+        let previous = Fetcher::new(&config.data_dir, None).previous_path(DataSource::Miljo);
+        assert!(fs::read_to_string(previous).unwrap().contains("Städning"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
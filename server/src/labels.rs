@@ -0,0 +1,461 @@
+//! Labelled review verdicts
+//! Imports classifications from browser exports, the classification store and review
+//! sessions, and merges them into one canonical labels file used as ground truth
+
+use crate::classification::{CATEGORIES, ClassificationEntry};
+use crate::review::ReviewSession;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_LABELS_PATH: &str = "labels.json";
+const LABELS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Verdict {
+    /// Reviewed without objections: the matched zone is right
+    Correct,
+    NotMatching,
+    Invalid,
+}
+
+impl Verdict {
+    pub fn from_category(category: &str) -> Option<Self> {
+        match category {
+            "correct" => Some(Verdict::Correct),
+            "notMatching" => Some(Verdict::NotMatching),
+            "invalid" => Some(Verdict::Invalid),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::NotMatching => "notMatching",
+            Verdict::Invalid => "invalid",
+        }
+    }
+}
+
+/// One reviewer's verdict on an address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vote {
+    pub verdict: Verdict,
+    pub reviewer: String,
+    pub timestamp: String,
+    /// Classification id, empty for verdicts without one
+    #[serde(default)]
+    pub id: String,
+    /// File the vote was imported from
+    #[serde(default)]
+    pub origin: String,
+}
+
+impl Vote {
+    fn same_as(&self, other: &Vote) -> bool {
+        if !self.id.is_empty() || !other.id.is_empty() {
+            return self.id == other.id;
+        }
+        self.reviewer == other.reviewer
+            && self.verdict == other.verdict
+            && self.timestamp == other.timestamp
+    }
+
+    fn time(&self) -> (Option<DateTime<FixedOffset>>, &str) {
+        (
+            DateTime::parse_from_rfc3339(&self.timestamp).ok(),
+            &self.timestamp,
+        )
+    }
+}
+
+/// Resolved verdict for an address with every vote behind it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub address: String,
+    pub postnummer: String,
+    pub source: String,
+    pub verdict: Verdict,
+    /// Reviewer and time of the vote that decided the verdict
+    pub reviewer: String,
+    pub timestamp: String,
+    /// Reviewers disagree
+    pub conflict: bool,
+    pub votes: Vec<Vote>,
+}
+
+impl Label {
+    /// Each reviewer's latest vote counts; the most common verdict wins, ties go to
+    /// the most recent vote
+    fn resolve(&mut self) {
+        let mut latest: HashMap<&str, &Vote> = HashMap::new();
+        for vote in &self.votes {
+            let entry = latest.entry(&vote.reviewer).or_insert(vote);
+            if vote.time() > entry.time() {
+                *entry = vote;
+            }
+        }
+
+        let mut counts: HashMap<Verdict, usize> = HashMap::new();
+        for vote in latest.values() {
+            *counts.entry(vote.verdict).or_default() += 1;
+        }
+        let Some(winner) = latest
+            .values()
+            .max_by(|a, b| (counts[&a.verdict], a.time()).cmp(&(counts[&b.verdict], b.time())))
+        else {
+            return;
+        };
+
+        self.verdict = winner.verdict;
+        self.reviewer = winner.reviewer.clone();
+        self.timestamp = winner.timestamp.clone();
+        self.conflict = counts.len() > 1;
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LabelStats {
+    pub labels: usize,
+    pub by_verdict: BTreeMap<Verdict, usize>,
+    pub by_source: BTreeMap<String, usize>,
+    /// Votes per reviewer
+    pub reviewers: BTreeMap<String, usize>,
+    pub conflicts: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelSet {
+    pub version: u32,
+    pub updated_at: String,
+    pub labels: Vec<Label>,
+}
+
+impl Default for LabelSet {
+    fn default() -> Self {
+        Self {
+            version: LABELS_VERSION,
+            updated_at: Utc::now().to_rfc3339(),
+            labels: Vec::new(),
+        }
+    }
+}
+
+/// Addresses are the same when the address (case ignored) and postnummer digits match
+fn label_key(address: &str, postnummer: &str) -> String {
+    let digits: String = postnummer.chars().filter(char::is_ascii_digit).collect();
+    format!("{}|{}", address.trim().to_lowercase(), digits)
+}
+
+impl LabelSet {
+    /// Load a labels file that must exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Err(format!("Labels file {} not found", path.display()));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read labels {}: {}", path.display(), e))?;
+        let labels: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse labels {}: {}", path.display(), e))?;
+        if labels.version > LABELS_VERSION {
+            return Err(format!(
+                "Labels {} have version {}, newer than supported {}",
+                path.display(),
+                labels.version,
+                LABELS_VERSION
+            ));
+        }
+        Ok(labels)
+    }
+
+    /// Load the labels file written to, which is an empty set until it is first saved
+    pub fn load_or_default(path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Write sorted by address so label files diff cleanly
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        self.labels
+            .sort_by_cached_key(|l| label_key(&l.address, &l.postnummer));
+        self.updated_at = Utc::now().to_rfc3339();
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize labels: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Add a vote for an address; returns false if the vote is already present
+    pub fn add(&mut self, address: &str, postnummer: &str, source: &str, vote: Vote) -> bool {
+        let key = label_key(address, postnummer);
+        let label = match self
+            .labels
+            .iter()
+            .position(|l| label_key(&l.address, &l.postnummer) == key)
+        {
+            Some(i) => &mut self.labels[i],
+            None => {
+                self.labels.push(Label {
+                    address: address.trim().to_string(),
+                    postnummer: postnummer.to_string(),
+                    source: source.to_string(),
+                    verdict: vote.verdict,
+                    reviewer: vote.reviewer.clone(),
+                    timestamp: vote.timestamp.clone(),
+                    conflict: false,
+                    votes: Vec::new(),
+                });
+                self.labels.last_mut().unwrap()
+            }
+        };
+
+        if label.votes.iter().any(|v| v.same_as(&vote)) {
+            return false;
+        }
+        label.votes.push(vote);
+        label.resolve();
+        true
+    }
+
+    /// Add every vote of another label set; returns the number of new votes
+    pub fn merge(&mut self, other: LabelSet) -> usize {
+        let mut added = 0;
+        for label in other.labels {
+            for vote in label.votes {
+                added +=
+                    usize::from(self.add(&label.address, &label.postnummer, &label.source, vote));
+            }
+        }
+        added
+    }
+
+    /// Import a browser export (`amp_stadsatlas_classification_<ts>.json`), a classification
+    /// store file (`amp_stadsatlas_<category>.json`) or a review session
+    ///
+    /// Returns the number of new votes and the number of duplicates skipped.
+    pub fn import(&mut self, path: &Path, reviewer: &str) -> Result<(usize, usize), String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let json: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let origin = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        // (address, postnummer, source, vote)
+        let mut imported: Vec<(String, String, String, Vote)> = Vec::new();
+        let mut push_entries = |entries: &Value, verdict: Verdict| -> Result<(), String> {
+            let entries: Vec<ClassificationEntry> = serde_json::from_value(entries.clone())
+                .map_err(|e| format!("Invalid entries in {}: {}", path.display(), e))?;
+            for entry in entries {
+                let vote = Vote {
+                    verdict,
+                    reviewer: reviewer.to_string(),
+                    timestamp: entry.timestamp,
+                    id: entry.id,
+                    origin: origin.clone(),
+                };
+                imported.push((entry.address, entry.postal_code, entry.source, vote));
+            }
+            Ok(())
+        };
+
+        if json.get("items").is_some() {
+            let session: ReviewSession = serde_json::from_value(json)
+                .map_err(|e| format!("Invalid review session {}: {}", path.display(), e))?;
+            for item in session.items {
+                let Some(reviewed_at) = item.reviewed_at else {
+                    continue;
+                };
+                let mut votes: Vec<(Verdict, String)> = item
+                    .classifications
+                    .into_iter()
+                    .filter_map(|c| Some((Verdict::from_category(&c.category)?, c.id)))
+                    .collect();
+                if votes.is_empty() {
+                    votes.push((Verdict::Correct, String::new()));
+                }
+                for (verdict, id) in votes {
+                    let vote = Vote {
+                        verdict,
+                        reviewer: reviewer.to_string(),
+                        timestamp: reviewed_at.clone(),
+                        id,
+                        origin: origin.clone(),
+                    };
+                    imported.push((
+                        item.address.clone(),
+                        item.postnummer.clone(),
+                        item.source.clone(),
+                        vote,
+                    ));
+                }
+            }
+        } else if let Some(entries) = json.get("entries") {
+            let verdict = origin
+                .strip_prefix("amp_stadsatlas_")
+                .and_then(|rest| rest.strip_suffix(".json"))
+                .filter(|category| CATEGORIES.contains(category))
+                .and_then(Verdict::from_category)
+                .ok_or_else(|| {
+                    format!(
+                        "Cannot tell the category of {}: expected amp_stadsatlas_<category>.json",
+                        path.display()
+                    )
+                })?;
+            push_entries(entries, verdict)?;
+        } else if json.get("exported_at").is_some() {
+            for category in CATEGORIES {
+                if let (Some(entries), Some(verdict)) =
+                    (json.get(category), Verdict::from_category(category))
+                {
+                    push_entries(entries, verdict)?;
+                }
+            }
+        } else {
+            return Err(format!(
+                "{} is not a classification export, classification file or review session",
+                path.display()
+            ));
+        }
+
+        let total = imported.len();
+        let added = imported
+            .into_iter()
+            .filter(|(address, postnummer, source, vote)| {
+                self.add(address, postnummer, source, vote.clone())
+            })
+            .count();
+        Ok((added, total - added))
+    }
+
+    pub fn stats(&self) -> LabelStats {
+        let mut stats = LabelStats {
+            labels: self.labels.len(),
+            ..Default::default()
+        };
+        for label in &self.labels {
+            *stats.by_verdict.entry(label.verdict).or_default() += 1;
+            *stats.by_source.entry(label.source.clone()).or_default() += 1;
+            stats.conflicts += usize::from(label.conflict);
+            for vote in &label.votes {
+                *stats.reviewers.entry(vote.reviewer.clone()).or_default() += 1;
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(verdict: Verdict, reviewer: &str, timestamp: &str) -> Vote {
+        Vote {
+            verdict,
+            reviewer: reviewer.to_string(),
+            timestamp: timestamp.to_string(),
+            id: String::new(),
+            origin: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_import_formats_and_dedup() {
+        let dir = std::env::temp_dir().join(format!("amp_labels_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let entry = |id: &str, address: &str, postal: &str| {
+            serde_json::json!({
+                "id": id, "timestamp": "2026-03-01T10:00:00Z", "address": address,
+                "postal_code": postal, "source": "Miljödata only", "matches_html": ""
+            })
+        };
+
+        let export = dir.join("amp_stadsatlas_classification_1.json");
+        let body = serde_json::json!({
+            "exported_at": "2026-03-01T11:00:00Z",
+            "notMatching": [entry("notMatching-1", "Storgatan 1", "211 42")],
+            "invalid": [entry("invalid-2", "Lokgatan 11B", "211 20")],
+        });
+        fs::write(&export, body.to_string()).unwrap();
+
+        let store = dir.join("amp_stadsatlas_notMatching.json");
+        let body =
+            serde_json::json!({ "entries": [entry("notMatching-1", "storgatan 1", "21142")] });
+        fs::write(&store, body.to_string()).unwrap();
+
+        let mut labels = LabelSet::default();
+        assert_eq!(labels.import(&export, "anna").unwrap(), (2, 0));
+        // Same classification id from the server store is a duplicate
+        assert_eq!(labels.import(&store, "anna").unwrap(), (0, 1));
+        assert_eq!(labels.labels.len(), 2);
+
+        let stats = labels.stats();
+        assert_eq!(stats.by_verdict[&Verdict::NotMatching], 1);
+        assert_eq!(stats.by_verdict[&Verdict::Invalid], 1);
+        assert_eq!(stats.reviewers["anna"], 2);
+
+        let path = dir.join("labels.json");
+        labels.save(&path).unwrap();
+        let reloaded = LabelSet::load(&path).unwrap();
+        assert_eq!(reloaded.labels[0].address, "Lokgatan 11B");
+        assert!(labels.import(&path, "anna").is_err());
+
+        // Only a file that is written to may be missing
+        let missing = dir.join("missing.json");
+        assert!(LabelSet::load(&missing).is_err());
+        assert!(
+            LabelSet::load_or_default(&missing)
+                .unwrap()
+                .labels
+                .is_empty()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflicting_verdicts() {
+        let mut labels = LabelSet::default();
+        let add = |labels: &mut LabelSet, vote| labels.add("Storgatan 1", "21142", "", vote);
+
+        add(
+            &mut labels,
+            vote(Verdict::NotMatching, "anna", "2026-03-01T10:00:00Z"),
+        );
+        add(
+            &mut labels,
+            vote(Verdict::Correct, "bo", "2026-03-02T10:00:00+01:00"),
+        );
+        assert!(labels.labels[0].conflict);
+        // Tie: the most recent vote wins
+        assert_eq!(labels.labels[0].verdict, Verdict::Correct);
+
+        add(
+            &mut labels,
+            vote(Verdict::NotMatching, "cia", "2026-03-01T12:00:00Z"),
+        );
+        assert_eq!(labels.labels[0].verdict, Verdict::NotMatching);
+        assert_eq!(labels.labels[0].reviewer, "cia");
+
+        // A reviewer changing their mind replaces their earlier vote
+        add(
+            &mut labels,
+            vote(Verdict::Correct, "anna", "2026-03-03T10:00:00Z"),
+        );
+        assert_eq!(labels.labels[0].verdict, Verdict::Correct);
+        assert_eq!(labels.labels[0].votes.len(), 4);
+        assert!(!add(
+            &mut labels,
+            vote(Verdict::Correct, "anna", "2026-03-03T10:00:00Z")
+        ));
+    }
+}
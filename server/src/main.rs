@@ -1,22 +1,40 @@
 //! AMP Server - Address-Parking Correlation CLI
 //! Supports multiple correlation algorithms, benchmarking, testing with visual verification
 
-use amp_core::api::api;
+use amp_core::api::{DataLoader, api, api_from_dir};
 use amp_core::benchmark::Benchmarker;
-use amp_core::checksum::DataChecksum;
+use amp_core::checksum::{DataChecksum, DataSource, SourceStatus};
+use amp_core::compare::{Disagreement, DisagreementKind, classify, compare_algorithms};
 use amp_core::correlation_algorithms::{
     CorrelationAlgo, DistanceBasedAlgo, GridNearestAlgo, KDTreeSpatialAlgo, OverlappingChunksAlgo,
     RTreeSpatialAlgo, RaycastingAlgo,
 };
-use amp_core::structs::{AdressClean, CorrelationResult, MiljoeDataClean};
-use clap::{Parser, Subcommand};
+use amp_core::explain::candidates;
+use amp_core::fetch::Fetcher;
+use amp_core::ics::{RecurringRestriction, address_calendar};
+use amp_core::incremental::{
+    DEFAULT_METADATA_PATH, RunMetadata, address_key, patch_results, result_key,
+};
+use amp_core::overrides::{DEFAULT_OVERRIDES_PATH, OverrideSet};
+use amp_core::parquet::{
+    DEFAULT_RESULTS_PATH, read_correlation_parquet_from, write_correlation_parquet_to,
+};
+use amp_core::report::build_report;
+use amp_core::search::AddressIndex;
+use amp_core::structs::{AdressClean, CorrelationResult, MatchSource, MiljoeDataClean};
+use amp_core::synthetic::{CityOptions, generate_city, write_city};
+use amp_core::zone_index::ZoneIndex;
+use chrono::{Local, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rayon::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use sampling::{DEFAULT_SAMPLE_PATH, SampleArgs, SampleFile, Sampled};
+use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
@@ -24,6 +42,11 @@ use std::time::Duration;
 use std::time::Instant;
 
 mod classification;
+mod daemon;
+mod labels;
+mod review;
+mod sampling;
+mod serve;
 
 #[derive(Parser)]
 #[command(name = "amp-server")]
@@ -43,6 +66,32 @@ enum Commands {
 
         #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
         cutoff: f64,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(short, long, default_value = DEFAULT_RESULTS_PATH, help = "Parquet output file")]
+        output: String,
+
+        #[arg(long, default_value = DEFAULT_METADATA_PATH, help = "Run metadata file")]
+        metadata: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Only re-correlate addresses affected by changes since the previous run"
+        )]
+        incremental: bool,
+
+        #[arg(
+            long,
+            default_value = DEFAULT_OVERRIDES_PATH,
+            help = "Manual overrides applied after correlation, if the file exists"
+        )]
+        overrides: String,
+
+        #[command(flatten)]
+        sampling: SampleArgs,
     },
 
     /// Test correlation with visual browser verification
@@ -60,6 +109,66 @@ enum Commands {
             help = "Number of browser windows to open"
         )]
         windows: usize,
+
+        #[command(flatten)]
+        sampling: SampleArgs,
+    },
+
+    /// Review correlations in the browser, served from a local HTTP server
+    Review {
+        #[arg(short, long, value_enum, default_value_t = AlgorithmChoice::KDTree)]
+        algorithm: AlgorithmChoice,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 50,
+            help = "Number of addresses in a new review queue"
+        )]
+        count: usize,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(
+            short,
+            long,
+            default_value = "review_session.json",
+            help = "Session file; an existing session is resumed"
+        )]
+        session: String,
+
+        #[arg(
+            long,
+            help = "Classification directory (default: ~/Documents/amp_classifications)"
+        )]
+        classifications: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value = "127.0.0.1:8090",
+            help = "Address to listen on"
+        )]
+        bind: String,
+
+        #[arg(long, help = "Replace an existing session with a new queue")]
+        fresh: bool,
+
+        #[arg(long, help = "Open the review queue in the browser")]
+        open: bool,
+
+        #[command(flatten)]
+        sampling: SampleArgs,
+    },
+
+    /// Build a labelled dataset from review classifications
+    Labels {
+        #[command(subcommand)]
+        command: LabelsCommand,
     },
 
     /// Benchmark all algorithms
@@ -74,6 +183,9 @@ enum Commands {
 
         #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
         cutoff: f64,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
     },
 
     /// Check for data updates from Malmö open data portal
@@ -85,6 +197,288 @@ enum Commands {
             help = "Checksum file path"
         )]
         checksum_file: String,
+
+        #[arg(
+            short,
+            long,
+            default_value = "data",
+            help = "Data directory used as baseline for feature diffs"
+        )]
+        data_dir: String,
+    },
+
+    /// Download the source datasets into the data directory
+    Fetch {
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(
+            short,
+            long,
+            required_unless_present = "rollback",
+            help = "Base URL (http(s):// or file://) serving the dataset files"
+        )]
+        base_url: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value = "checksums.json",
+            help = "Checksum file path"
+        )]
+        checksum_file: String,
+
+        #[arg(long, help = "Restore the previous version of each dataset")]
+        rollback: bool,
+    },
+
+    /// Periodically refresh data, re-correlate and publish versioned results
+    Daemon {
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(
+            short,
+            long,
+            default_value = "published",
+            help = "Directory for versioned results, manifest and status file"
+        )]
+        output_dir: String,
+
+        #[arg(long, default_value = "checksums.json", help = "Checksum file path")]
+        checksum_file: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Base URL (http(s):// or file://) serving the dataset files"
+        )]
+        base_url: String,
+
+        #[arg(short, long, default_value_t = 360, help = "Minutes between cycles")]
+        interval: u64,
+
+        #[arg(short, long, value_enum, default_value_t = AlgorithmChoice::KDTree)]
+        algorithm: AlgorithmChoice,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 10,
+            help = "Number of published versions to keep"
+        )]
+        keep: usize,
+
+        #[arg(long, help = "Run a single cycle and exit")]
+        once: bool,
+
+        #[arg(
+            long,
+            default_value = DEFAULT_OVERRIDES_PATH,
+            help = "Manual overrides applied after correlation, if the file exists"
+        )]
+        overrides: String,
+    },
+
+    /// Search addresses with typo tolerance and autocompletion
+    Search {
+        #[arg(help = "Street and number, e.g. \"sodra forstadsg 12b\"")]
+        query: String,
+
+        #[arg(short, long, help = "Postnummer filter (may be partial)")]
+        postnummer: Option<String>,
+
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 10,
+            help = "Maximum number of suggestions"
+        )]
+        limit: usize,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+    },
+
+    /// Serve address and zone lookups over HTTP
+    Serve {
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(
+            short,
+            long,
+            default_value = "127.0.0.1:8080",
+            help = "Address to listen on"
+        )]
+        bind: String,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+    },
+
+    /// Write an offline HTML map and GeoJSON of correlation results
+    Report {
+        #[arg(short, long, default_value = DEFAULT_RESULTS_PATH, help = "Correlation results (Parquet)")]
+        input: String,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(short, long, default_value = "report.html", help = "HTML output file")]
+        output: String,
+
+        #[arg(long, default_value = "report.geojson", help = "GeoJSON output file")]
+        geojson: String,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 50.,
+            help = "Distance in meters shown as the end of the colour scale"
+        )]
+        cutoff: f64,
+
+        #[arg(long, help = "Open the report in the browser")]
+        open: bool,
+    },
+
+    /// Run several algorithms over all addresses and report where they disagree
+    Compare {
+        #[arg(
+            short,
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "distance-based,raycasting,overlapping-chunks,rtree,kdtree,grid",
+            help = "Algorithms to compare, comma separated; the first is the reference"
+        )]
+        algorithms: Vec<AlgorithmChoice>,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 0.5,
+            help = "Distance difference in meters tolerated between picks"
+        )]
+        tolerance: f64,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(
+            short,
+            long,
+            default_value = "disagreements.csv",
+            help = "CSV output file"
+        )]
+        output: String,
+
+        #[arg(
+            long,
+            default_value = "disagreements.geojson",
+            help = "GeoJSON output file"
+        )]
+        geojson: String,
+    },
+
+    /// Show the candidate zones around an address and what each algorithm picks
+    Explain {
+        #[arg(short, long, help = "Address, e.g. \"Storgatan 10\"")]
+        address: String,
+
+        #[arg(short, long, help = "Postnummer, needed when the address is ambiguous")]
+        postnummer: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 100.,
+            help = "Radius in meters to list candidates in"
+        )]
+        radius: f64,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+    },
+
+    /// Export an address's miljöparkering schedule as an iCalendar file
+    Ics {
+        #[arg(short, long, help = "Address, e.g. \"Lokgatan 11B\"")]
+        address: String,
+
+        #[arg(short, long, help = "Postnummer, needed when the address is ambiguous")]
+        postnummer: Option<String>,
+
+        #[arg(short, long, default_value = "restrictions.ics", help = "Output file")]
+        output: String,
+
+        #[arg(short, long, default_value = "data", help = "Data directory")]
+        data_dir: String,
+
+        #[arg(short, long, default_value_t = 50., help = "Distance cutoff in meters")]
+        cutoff: f64,
+    },
+
+    /// Generate a synthetic city as GeoJSON in the Malmö data schema
+    Gen {
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 10000,
+            help = "Number of addresses (100 to 1000000)"
+        )]
+        addresses: usize,
+
+        #[arg(short, long, help = "Random seed (default: random, printed)")]
+        seed: Option<u64>,
+
+        #[arg(
+            short,
+            long,
+            default_value = "synthetic",
+            help = "Output data directory"
+        )]
+        output: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum LabelsCommand {
+    /// Import classification exports, classification files or review sessions
+    Import {
+        #[arg(required = true, help = "Files to import")]
+        files: Vec<String>,
+
+        #[arg(short, long, default_value = labels::DEFAULT_LABELS_PATH, help = "Labels file")]
+        labels: String,
+
+        #[arg(short, long, help = "Reviewer name (default: $USER)")]
+        reviewer: Option<String>,
+    },
+
+    /// Merge labels files, e.g. from several reviewers
+    Merge {
+        #[arg(required = true, help = "Labels files to merge")]
+        files: Vec<String>,
+
+        #[arg(short, long, default_value = labels::DEFAULT_LABELS_PATH, help = "Output labels file")]
+        output: String,
+    },
+
+    /// Summarize a labels file
+    Stats {
+        #[arg(short, long, default_value = labels::DEFAULT_LABELS_PATH, help = "Labels file")]
+        labels: String,
     },
 }
 
@@ -108,27 +502,293 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Correlate { algorithm, cutoff } => {
-            run_correlation(algorithm, cutoff)?;
+        Commands::Correlate {
+            algorithm,
+            cutoff,
+            data_dir,
+            output,
+            metadata,
+            incremental,
+            overrides,
+            sampling,
+        } => {
+            run_correlation(
+                algorithm,
+                cutoff,
+                &data_dir,
+                &output,
+                &metadata,
+                incremental,
+                &overrides,
+                &sampling,
+            )?;
         }
         Commands::Test {
             algorithm,
             cutoff,
             windows,
+            sampling,
         } => {
-            run_test_mode(algorithm, cutoff, windows)?;
+            run_test_mode(algorithm, cutoff, windows, &sampling)?;
         }
+        Commands::Review {
+            algorithm,
+            cutoff,
+            count,
+            data_dir,
+            session,
+            classifications,
+            bind,
+            fresh,
+            open,
+            sampling,
+        } => {
+            let session_path = PathBuf::from(session);
+            let session = if session_path.exists() && !fresh {
+                let session = review::ReviewSession::load(&session_path)?;
+                println!(
+                    "↻ Resuming {}: {}/{} addresses reviewed",
+                    session_path.display(),
+                    session.reviewed(),
+                    session.items.len()
+                );
+                session
+            } else {
+                let session = new_review_session(algorithm, cutoff, count, &data_dir, &sampling)?;
+                session.save(&session_path)?;
+                println!(
+                    "📋 New review queue of {} addresses in {}",
+                    session.items.len(),
+                    session_path.display()
+                );
+                session
+            };
+
+            let classification_dir = match classifications {
+                Some(dir) => PathBuf::from(dir),
+                None => classification::classification_dir()?,
+            };
+            let state = review::ReviewState::new(session, session_path, classification_dir);
+            tokio::runtime::Runtime::new()?.block_on(review::serve(&bind, state, open))?
+        }
+        Commands::Labels { command } => run_labels(command)?,
         Commands::Benchmark {
             sample_size,
             cutoff,
+            data_dir,
+        } => {
+            run_benchmark(sample_size, cutoff, &data_dir)?;
+        }
+        Commands::CheckUpdates {
+            checksum_file,
+            data_dir,
+        } => tokio::runtime::Runtime::new()?.block_on(check_updates(&checksum_file, &data_dir))?,
+        Commands::Fetch {
+            data_dir,
+            base_url,
+            checksum_file,
+            rollback,
+        } => {
+            if rollback {
+                rollback_datasets(&data_dir)?;
+            } else {
+                tokio::runtime::Runtime::new()?.block_on(fetch_datasets(
+                    &data_dir,
+                    base_url,
+                    &checksum_file,
+                ))?
+            }
+        }
+        Commands::Daemon {
+            data_dir,
+            output_dir,
+            checksum_file,
+            base_url,
+            interval,
+            algorithm,
+            cutoff,
+            keep,
+            once,
+            overrides,
+        } => {
+            let config = daemon::DaemonConfig {
+                data_dir,
+                output_dir: output_dir.into(),
+                checksum_file,
+                base_url,
+                interval: Duration::from_secs(interval * 60),
+                algorithm,
+                cutoff,
+                keep,
+                once,
+                overrides,
+            };
+            tokio::runtime::Runtime::new()?.block_on(daemon::run(config))?
+        }
+        Commands::Search {
+            query,
+            postnummer,
+            limit,
+            data_dir,
+        } => {
+            search_addresses(&query, postnummer.as_deref(), limit, &data_dir)?;
+        }
+        Commands::Serve {
+            data_dir,
+            bind,
+            cutoff,
+        } => {
+            let state = serve::AppState::load(&data_dir, cutoff)?;
+            tokio::runtime::Runtime::new()?.block_on(serve::serve(&bind, state))?
+        }
+        Commands::Report {
+            input,
+            data_dir,
+            output,
+            geojson,
+            cutoff,
+            open,
         } => {
-            run_benchmark(sample_size, cutoff)?;
+            write_report(&input, &data_dir, &output, &geojson, cutoff, open)?;
         }
-        Commands::CheckUpdates { checksum_file } => {
-            tokio::runtime::Runtime::new()?.block_on(check_updates(&checksum_file))?
+        Commands::Compare {
+            algorithms,
+            cutoff,
+            tolerance,
+            data_dir,
+            output,
+            geojson,
+        } => {
+            run_compare(&algorithms, cutoff, tolerance, &data_dir, &output, &geojson)?;
         }
+        Commands::Explain {
+            address,
+            postnummer,
+            radius,
+            cutoff,
+            data_dir,
+        } => {
+            explain_address(&address, postnummer.as_deref(), radius, cutoff, &data_dir)?;
+        }
+        Commands::Ics {
+            address,
+            postnummer,
+            output,
+            data_dir,
+            cutoff,
+        } => {
+            export_calendar(&address, postnummer.as_deref(), &output, &data_dir, cutoff)?;
+        }
+        Commands::Gen {
+            addresses,
+            seed,
+            output,
+        } => {
+            generate_dataset(addresses, seed, &output)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a synthetic city into `output`, usable as `--data-dir` for the other commands
+fn generate_dataset(
+    addresses: usize,
+    seed: Option<u64>,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !(100..=1_000_000).contains(&addresses) {
+        return Err(format!("Address count must be 100 to 1000000, got {}", addresses).into());
+    }
+    let seed = sampling::resolve_seed(seed);
+    let start = Instant::now();
+    let city = generate_city(&CityOptions {
+        addresses,
+        seed,
+        ..CityOptions::default()
+    });
+    write_city(&city, Path::new(output))?;
+
+    println!(
+        "✓ Generated {} addresses, {} miljödata and {} parkering segments in {:.2?} (seed {})",
+        city.addresses.len(),
+        city.miljodata.len(),
+        city.parkering.len(),
+        start.elapsed(),
+        seed
+    );
+    println!("  Written to {}/", output);
+    Ok(())
+}
+
+/// Build the report features from stored results and render them into a single HTML file
+fn write_report(
+    input: &str,
+    data_dir: &str,
+    output: &str,
+    geojson_path: &str,
+    cutoff: f64,
+    open: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results = read_correlation_parquet_from(input)
+        .map_err(|e| format!("Failed to read {}: {}", input, e))?;
+    let (addresses, miljodata, parkering) = api_from_dir(data_dir)?;
+    let miljo_index = ZoneIndex::new(miljodata);
+    let parkering_index = ZoneIndex::new(parkering);
+
+    let (collection, summary) = build_report(&results, &addresses, &miljo_index, &parkering_index);
+    let geojson = serde_json::to_string(&collection)?;
+    fs::write(geojson_path, &geojson)?;
+
+    let mut html = load_asset_file("report.html")?;
+    let css = load_asset_file("report.css")?;
+    let mut js = load_asset_file("report.js")?;
+
+    // Escape "</" so info texts cannot close the inline script
+    js = js.replace("{CUTOFF}", &cutoff.to_string());
+    js = js.replace("{REPORT_DATA}", &geojson.replace("</", "<\\/"));
+
+    html = html.replace("{CUTOFF}", &cutoff.to_string());
+    html = html.replace(
+        "{REPORT_TITLE}",
+        &format!(
+            "{} addresses from {}, generated {}",
+            summary.addresses,
+            input,
+            Local::now().format("%Y-%m-%d %H:%M")
+        ),
+    );
+    html = html.replace(
+        "<link rel=\"stylesheet\" href=\"report.css\">",
+        &format!("<style>\n{}\n</style>", css),
+    );
+    html = html.replace(
+        "<script src=\"report.js\"></script>",
+        &format!("<script>\n{}\n</script>", js),
+    );
+    fs::write(output, html)?;
+
+    println!("🗺️  Report of {} addresses:", summary.addresses);
+    println!("   Matched: {}", summary.matched);
+    println!("   Matched segments: {}", summary.zones);
+    if summary.unresolved > 0 {
+        println!(
+            "   ⚠️  {} matches not found in the zone data (data changed since the run?)",
+            summary.unresolved
+        );
+    }
+    if summary.unlocated > 0 {
+        println!(
+            "   ⚠️  {} results without a known address location",
+            summary.unlocated
+        );
     }
+    println!("\n💾 Written to {} and {}", output, geojson_path);
 
+    if open {
+        let path = fs::canonicalize(output)?;
+        open_url(&format!("file://{}", path.display()));
+    }
     Ok(())
 }
 
@@ -139,6 +799,7 @@ fn load_asset_file(filename: &str) -> Result<String, Box<dyn std::error::Error>>
         format!("server/src/assets/{}", filename),
         format!("src/assets/{}", filename),
         format!("assets/{}", filename),
+        format!("{}/src/assets/{}", env!("CARGO_MANIFEST_DIR"), filename),
     ];
 
     for path in paths {
@@ -404,14 +1065,75 @@ fn merge_results(
                 postnummer: addr.postnummer.clone(),
                 miljo_match,
                 parkering_match,
+                source: MatchSource::Algorithm,
             }
         })
         .collect()
 }
 
+/// Apply the overrides file, if it exists, and print overrides that could not be applied
+fn apply_overrides(
+    path: &str,
+    results: &mut [CorrelationResult],
+    addresses: &[AdressClean],
+    miljodata: &[MiljoeDataClean],
+    parkering: &[MiljoeDataClean],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new(path).exists() {
+        return Ok(());
+    }
+    let overrides = OverrideSet::load_from_file(path)
+        .map_err(|e| format!("Failed to load overrides {}: {}", path, e))?;
+    let report = overrides.apply(results, addresses, miljodata, parkering);
+
+    println!(
+        "\n✏️  Applied {} of {} overrides from {}",
+        report.applied,
+        overrides.overrides.len(),
+        path
+    );
+    for warning in &report.warnings {
+        println!("   ⚠️  {}", warning);
+    }
+    Ok(())
+}
+
+/// Load the previous run if its results can be patched by this run
+fn load_previous_run(
+    metadata: &RunMetadata,
+    output: &str,
+    metadata_path: &str,
+) -> Option<(RunMetadata, Vec<CorrelationResult>)> {
+    let Ok(previous) = RunMetadata::load_from_file(metadata_path) else {
+        println!("   No previous run metadata found - running full correlation\n");
+        return None;
+    };
+    if !metadata.compatible_with(&previous) {
+        println!("   Algorithm or cutoff changed since previous run - running full correlation\n");
+        return None;
+    }
+    match read_correlation_parquet_from(output) {
+        Ok(results) => Some((previous, results)),
+        Err(e) => {
+            println!(
+                "   Could not read previous results ({}) - running full correlation\n",
+                e
+            );
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_correlation(
     algorithm: AlgorithmChoice,
     cutoff: f64,
+    data_dir: &str,
+    output: &str,
+    metadata_path: &str,
+    incremental: bool,
+    overrides_path: &str,
+    sampling: &SampleArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Load data with progress
     let pb = ProgressBar::new_spinner();
@@ -422,7 +1144,7 @@ fn run_correlation(
         Vec<AdressClean>,
         Vec<MiljoeDataClean>,
         Vec<MiljoeDataClean>,
-    ) = api()?;
+    ) = api_from_dir(data_dir)?;
     pb.finish_with_message(format!(
         "✓ Loaded {} addresses, {} miljödata zones, {} parkering zones",
         addresses.len(),
@@ -440,12 +1162,63 @@ fn run_correlation(
 
     // Setup algorithm
     let algo_name = format!("{:?}", algorithm);
+    let mut metadata = RunMetadata::new(&algo_name, cutoff, &addresses, &miljodata, &parkering);
+    let previous = if incremental {
+        load_previous_run(&metadata, output, metadata_path)
+    } else {
+        None
+    };
+
+    // Addresses to correlate in this run
+    let to_correlate: Vec<AdressClean> = match &previous {
+        Some((previous_metadata, previous_results)) => {
+            let mut changes = metadata.changes_since(previous_metadata);
+            let mut affected =
+                metadata.affected_addresses(previous_metadata, &mut changes, &addresses);
+
+            // Overridden rows are recomputed so edited or removed overrides take effect
+            let overridden: HashSet<String> = previous_results
+                .iter()
+                .filter(|r| r.source == MatchSource::Override)
+                .map(result_key)
+                .collect();
+            let already: HashSet<String> = affected.iter().map(|a| address_key(a)).collect();
+            affected.extend(addresses.iter().filter(|a| {
+                let key = address_key(a);
+                overridden.contains(&key) && !already.contains(&key)
+            }));
+
+            println!("🔁 Incremental run since {}:", previous_metadata.created);
+            println!(
+                "   Addresses: +{} / -{} / ~{}",
+                changes.added_addresses.len(),
+                changes.removed_addresses.len(),
+                changes.changed_addresses.len()
+            );
+            println!(
+                "   Zones: +{} / -{}",
+                changes.added_zones.len(),
+                changes.removed_zones.len()
+            );
+            println!(
+                "   Re-correlating {} addresses ({} near changed zones)\n",
+                affected.len(),
+                changes.nearby_addresses
+            );
+
+            let affected = affected.into_iter().cloned().collect();
+            metadata.changes = Some(changes);
+            affected
+        }
+        None => addresses.clone(),
+    };
+
     println!("🚀 Running correlation with {} algorithm", algo_name);
 
     let start = Instant::now();
 
     // Create progress bar
-    let pb = ProgressBar::new(addresses.len() as u64);
+    let pb = ProgressBar::new(to_correlate.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("[{bar:40.cyan/blue}] {pos}/{len} {percent}% {msg}")?
@@ -454,17 +1227,35 @@ fn run_correlation(
 
     // Correlate with miljödata
     pb.set_message("Correlating with miljödata...");
-    let miljo_results = correlate_dataset(&algorithm, &addresses, &miljodata, cutoff, &pb)?;
+    let miljo_results = correlate_dataset(&algorithm, &to_correlate, &miljodata, cutoff, &pb)?;
 
     // Correlate with parkering
     pb.set_message("Correlating with parkering...");
-    let parkering_results = correlate_dataset(&algorithm, &addresses, &parkering, cutoff, &pb)?;
+    let parkering_results = correlate_dataset(&algorithm, &to_correlate, &parkering, cutoff, &pb)?;
 
     let duration = start.elapsed();
     pb.finish_with_message(format!("✓ Completed in {:.2?}", duration));
 
-    // Merge results
-    let merged = merge_results(&addresses, &miljo_results, &parkering_results);
+    // Merge results, patching the previous output in incremental mode
+    let correlated = merge_results(&to_correlate, &miljo_results, &parkering_results);
+    let mut merged = match previous {
+        Some((_, previous_results)) => {
+            let removed = metadata
+                .changes
+                .as_ref()
+                .map(|c| c.removed_addresses.clone())
+                .unwrap_or_default();
+            patch_results(previous_results, correlated, &removed)
+        }
+        None => correlated,
+    };
+    apply_overrides(
+        overrides_path,
+        &mut merged,
+        &addresses,
+        &miljodata,
+        &parkering,
+    )?;
 
     // Calculate statistics
     let both = merged
@@ -514,22 +1305,21 @@ fn run_correlation(
     );
     println!(
         "   Average time per address: {:.2?}",
-        duration / addresses.len() as u32
+        duration / to_correlate.len().max(1) as u32
     );
 
     if total_matches == 0 {
         println!("\n⚠️  Warning: No matches found! Check data files.");
     } else {
-        // Show 10 random matches
-        let mut rng = thread_rng();
-        let mut random_results: Vec<_> = merged
+        // Show a sample of 10 matches
+        let matching: Vec<_> = merged
             .iter()
             .filter(|r: &&CorrelationResult| r.has_match())
             .collect();
-        random_results.shuffle(&mut rng);
+        let sample = draw_sample(&matching, 10, cutoff, &algorithm, sampling, None)?;
 
-        println!("\n🎲 10 Random Matches:");
-        for result in random_results.iter().take(10) {
+        println!("\n🎲 {} Sampled Matches:", sample.len());
+        for Sampled { result, .. } in &sample {
             println!("   {} ({})", result.address, result.dataset_source());
             if let Some((dist, _)) = &result.miljo_match {
                 println!("      ├─ Miljödata: {:.2}m", dist);
@@ -583,6 +1373,13 @@ fn run_correlation(
         }
     }
 
+    write_correlation_parquet_to(output, merged)?;
+    metadata.save_to_file(metadata_path)?;
+    println!(
+        "\n💾 Results written to {} (run metadata: {})",
+        output, metadata_path
+    );
+
     Ok(())
 }
 
@@ -590,6 +1387,7 @@ fn run_test_mode(
     algorithm: AlgorithmChoice,
     cutoff: f64,
     num_windows: usize,
+    sampling: &SampleArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Load data with progress
     let pb = ProgressBar::new_spinner();
@@ -650,11 +1448,18 @@ fn run_test_mode(
         matching_addresses.len()
     );
 
-    // Random sampling
-    let mut rng = thread_rng();
-    let mut sampled = matching_addresses.clone();
-    sampled.shuffle(&mut rng);
-    let selected: Vec<_> = sampled.iter().take(actual_windows).collect();
+    let selected: Vec<_> = draw_sample(
+        &matching_addresses,
+        actual_windows,
+        cutoff,
+        &algorithm,
+        sampling,
+        Some(DEFAULT_SAMPLE_PATH),
+    )?
+    .into_iter()
+    .map(|s| s.result)
+    .collect();
+    let actual_windows = selected.len();
 
     println!("\n🌐 Opening {} browser windows...", actual_windows);
     println!("   Each window has 4 integrated tabs with nested StadsAtlas map:");
@@ -691,6 +1496,102 @@ fn run_test_mode(
     Ok(())
 }
 
+/// Correlate the data directory and queue a sample of matched addresses for review
+fn new_review_session(
+    algorithm: AlgorithmChoice,
+    cutoff: f64,
+    count: usize,
+    data_dir: &str,
+    sampling: &SampleArgs,
+) -> Result<review::ReviewSession, Box<dyn std::error::Error>> {
+    let (addresses, miljodata, parkering) = api_from_dir(data_dir)?;
+
+    let pb = ProgressBar::new(addresses.len() as u64 * 2);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("[{bar:40.cyan/blue}] {pos}/{len} {percent}%")?
+            .progress_chars("█▓▒░ "),
+    );
+    let miljo_results = correlate_dataset(&algorithm, &addresses, &miljodata, cutoff, &pb)?;
+    let parkering_results = correlate_dataset(&algorithm, &addresses, &parkering, cutoff, &pb)?;
+    pb.finish_and_clear();
+
+    let merged = merge_results(&addresses, &miljo_results, &parkering_results);
+    let matching: Vec<&CorrelationResult> = merged.iter().filter(|r| r.has_match()).collect();
+    if matching.is_empty() {
+        return Err("No matching addresses found for review".into());
+    }
+
+    let items = draw_sample(
+        &matching,
+        count,
+        cutoff,
+        &algorithm,
+        sampling,
+        Some(DEFAULT_SAMPLE_PATH),
+    )?
+    .into_iter()
+    .map(|s| review::ReviewItem::from(s.result))
+    .collect();
+    Ok(review::ReviewSession::new(
+        items,
+        format!("{:?}", algorithm),
+        cutoff,
+    ))
+}
+
+/// Replay a sample file, or draw a new seeded sample and write it to the sample file
+/// A new sample is written to `--sample-file`, else to `default_file` if given
+fn draw_sample<'a>(
+    matching: &[&'a CorrelationResult],
+    count: usize,
+    cutoff: f64,
+    algorithm: &AlgorithmChoice,
+    sampling: &SampleArgs,
+    default_file: Option<&str>,
+) -> Result<Vec<Sampled<'a>>, Box<dyn std::error::Error>> {
+    if let Some(path) = &sampling.replay {
+        let file = SampleFile::load(Path::new(path))?;
+        let (mut sample, missing) = file.replay(matching);
+        sample.truncate(count);
+        println!(
+            "\n🔁 Replaying {} of {} sampled addresses from {} (seed {}, {})",
+            sample.len(),
+            file.entries.len(),
+            path,
+            file.seed,
+            file.mode.as_str()
+        );
+        for address in &missing {
+            println!("   ⚠️  {} no longer has a match", address);
+        }
+        return Ok(sample);
+    }
+
+    let seed = sampling::resolve_seed(sampling.seed);
+    let sample = sampling::select(matching, sampling.sample, count, cutoff, seed);
+    let file = sampling.sample_file.as_deref().or(default_file);
+    if let Some(file) = file {
+        SampleFile::new(
+            &sample,
+            seed,
+            sampling.sample,
+            format!("{:?}", algorithm),
+            cutoff,
+        )
+        .save(Path::new(file))?;
+    }
+    println!(
+        "\n🎲 Sampled {} addresses ({}, seed {}){}",
+        sample.len(),
+        sampling.sample.as_str(),
+        seed,
+        file.map(|f| format!("; written to {}", f))
+            .unwrap_or_default()
+    );
+    Ok(sample)
+}
+
 /// Get the browser executable to use on Linux
 fn get_browser_executable() -> String {
     // Check BROWSER environment variable first
@@ -750,32 +1651,43 @@ fn format_matches_html(result: &CorrelationResult) -> String {
 
 /// Create HTML page by loading template and inlining CSS/JS, with origo_map.html as embedded data URI
 fn create_tabbed_interface_page(
-    address: &str,
     result: &CorrelationResult,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Create data URI for origo_map.html using base64 encoding
+    let origo_data_uri = create_data_uri(&load_asset_file("origo_map.html")?);
+    render_interface_page(&review::ReviewItem::from(result), &origo_data_uri, None)
+}
+
+/// Fill the interface template for an address; `review_index` is its position in the
+/// queue when served by the review server, which then receives the verdicts
+fn render_interface_page(
+    item: &review::ReviewItem,
+    origo_map_url: &str,
+    review_index: Option<usize>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     // Load base HTML template
     let mut html = load_asset_file("stadsatlas_interface.html")?;
     let css = load_asset_file("stadsatlas_interface.css")?;
     let mut js = load_asset_file("stadsatlas_interface.js")?;
-    let origo_map_html = load_asset_file("origo_map.html")?;
 
-    let matches_html = format_matches_html(result);
-    let address_escaped = address.replace('"', "&quot;");
+    let address_escaped = item.address.replace('"', "&quot;");
 
-    // Step 1: Create data URI for origo_map.html using base64 encoding
-    let origo_data_uri = create_data_uri(&origo_map_html);
-
-    // Step 2: Replace placeholder in JavaScript BEFORE inlining it
-    js = js.replace("{ORIGO_DATA_URI}", &origo_data_uri);
+    // Replace placeholders in JavaScript BEFORE inlining it
+    js = js.replace("{ORIGO_DATA_URI}", origo_map_url);
+    js = js.replace(
+        "{REVIEW_API}",
+        if review_index.is_some() { "/api" } else { "" },
+    );
+    js = js.replace("{REVIEW_INDEX}", &review_index.unwrap_or(0).to_string());
 
-    // Step 3: Replace placeholders in HTML
+    // Replace placeholders in HTML
     html = html.replace("{ADDRESS}", &address_escaped);
-    html = html.replace("{RESULT_ADDRESS}", &result.address);
-    html = html.replace("{RESULT_POSTNUMMER}", &result.postnummer);
-    html = html.replace("{RESULT_SOURCE}", &result.dataset_source());
-    html = html.replace("{RESULT_MATCHES}", &matches_html);
+    html = html.replace("{RESULT_ADDRESS}", &item.address);
+    html = html.replace("{RESULT_POSTNUMMER}", &item.postnummer);
+    html = html.replace("{RESULT_SOURCE}", &item.source);
+    html = html.replace("{RESULT_MATCHES}", &item.matches_html);
 
-    // Step 4: Inline CSS and JS
+    // Inline CSS and JS
     html = html.replace(
         "<link rel=\"stylesheet\" href=\"stadsatlas_interface.css\">",
         &format!("<style>\n{}\n</style>", css),
@@ -793,10 +1705,8 @@ fn open_browser_window(
     result: &&CorrelationResult,
     window_idx: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let address = &result.address;
-
     // Create the complete tabbed HTML page
-    let tabbed_page = create_tabbed_interface_page(address, result)?;
+    let tabbed_page = create_tabbed_interface_page(result)?;
 
     // Write to temporary file with unique name
     let temp_dir = env::temp_dir();
@@ -805,13 +1715,16 @@ fn open_browser_window(
 
     fs::write(&temp_file, &tabbed_page)?;
 
-    let file_url = format!("file://{}", temp_file.display());
+    open_url(&format!("file://{}", temp_file.display()));
+    Ok(())
+}
 
-    // Try to open window using different methods depending on OS
+/// Open a URL in the browser, using different methods depending on OS
+fn open_url(url: &str) {
     #[cfg(target_os = "windows")]
     {
         std::process::Command::new("cmd")
-            .args(&["/C", &format!("start chrome \"{}\"", file_url)])
+            .args(&["/C", &format!("start chrome \"{}\"", url)])
             .output()
             .ok();
     }
@@ -819,7 +1732,7 @@ fn open_browser_window(
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("bash")
-            .args(&["-c", &format!("open '{}'", file_url)])
+            .args(&["-c", &format!("open '{}'", url)])
             .output()
             .ok();
     }
@@ -827,22 +1740,78 @@ fn open_browser_window(
     #[cfg(target_os = "linux")]
     {
         let browser = get_browser_executable();
-        std::process::Command::new(&browser)
-            .arg(&file_url)
-            .spawn()
-            .ok();
+        std::process::Command::new(&browser).arg(url).spawn().ok();
     }
+}
 
+fn run_labels(command: LabelsCommand) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        LabelsCommand::Import {
+            files,
+            labels: labels_path,
+            reviewer,
+        } => {
+            let reviewer = reviewer
+                .or_else(|| env::var("USER").ok())
+                .unwrap_or_else(|| "unknown".to_string());
+            let mut labels = labels::LabelSet::load_or_default(Path::new(&labels_path))?;
+            for file in &files {
+                let (added, duplicates) = labels.import(Path::new(file), &reviewer)?;
+                println!(
+                    "📥 {}: {} new verdicts, {} duplicates skipped",
+                    file, added, duplicates
+                );
+            }
+            labels.save(Path::new(&labels_path))?;
+            println!(
+                "✓ {} labelled addresses in {}",
+                labels.labels.len(),
+                labels_path
+            );
+        }
+        LabelsCommand::Merge { files, output } => {
+            let mut merged = labels::LabelSet::load_or_default(Path::new(&output))?;
+            for file in &files {
+                let added = merged.merge(labels::LabelSet::load(Path::new(file))?);
+                println!("🔀 {}: {} new verdicts", file, added);
+            }
+            merged.save(Path::new(&output))?;
+            println!("✓ {} labelled addresses in {}", merged.labels.len(), output);
+        }
+        LabelsCommand::Stats {
+            labels: labels_path,
+        } => {
+            let stats = labels::LabelSet::load(Path::new(&labels_path))?.stats();
+            println!("🏷️  {}: {} labelled addresses", labels_path, stats.labels);
+            println!("\n   Verdicts:");
+            for (verdict, count) in &stats.by_verdict {
+                println!("     {:<12} {}", verdict.as_str(), count);
+            }
+            println!("\n   Dataset source:");
+            for (source, count) in &stats.by_source {
+                println!("     {:<30} {}", source, count);
+            }
+            println!("\n   Votes per reviewer:");
+            for (reviewer, count) in &stats.reviewers {
+                println!("     {:<20} {}", reviewer, count);
+            }
+            println!("\n   Conflicting verdicts: {}", stats.conflicts);
+        }
+    }
     Ok(())
 }
 
-fn run_benchmark(sample_size: usize, cutoff: f64) -> Result<(), Box<dyn std::error::Error>> {
+fn run_benchmark(
+    sample_size: usize,
+    cutoff: f64,
+    data_dir: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Load data
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}")?);
     pb.set_message("Loading data for benchmarking...");
 
-    let (addresses, zones) = amp_core::api::api_miljo_only()?;
+    let (addresses, zones, _) = api_from_dir(data_dir)?;
 
     // Validate sample size
     let actual_sample_size = sample_size.min(addresses.len());
@@ -1091,38 +2060,569 @@ fn run_single_benchmark<A: CorrelationAlgo + Sync>(
     pb.set_position(addresses.len() as u64);
 }
 
-async fn check_updates(checksum_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn check_updates(
+    checksum_file: &str,
+    data_dir: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🔍 Checking for data updates...\n");
 
     let old_checksums = DataChecksum::load_from_file(checksum_file).ok();
-
-    let mut new_checksums = DataChecksum::new(
-        "https://opendata.malmo.se/@fastighets-och-gatukontoret/miljoparkering/73490f00-0d71-4b17-903c-f77ab7664a53".to_string(),
-        "https://opendata.malmo.se/@fastighets-och-gatukontoret/parkeringsavgifter/1a6bd68b-30ca-40a5-9d62-01e2a566982e".to_string(),
-        "https://opendata.malmo.se/@stadsbyggnadskontoret/adresser/caf1cee8-9af2-4a75-8fb7-f1d7cb11daeb".to_string(),
-    );
+    let has_baseline = old_checksums.is_some();
+    let mut checksums = old_checksums.unwrap_or_else(DataChecksum::malmo);
 
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}")?);
     pb.set_message("Fetching remote data...");
 
-    new_checksums.update_from_remote().await?;
+    let reports = checksums.check_remote(Some(Path::new(data_dir))).await;
     pb.finish_with_message("✓ Data fetched");
 
-    if let Some(old) = old_checksums {
-        if new_checksums.has_changed(&old) {
-            println!("\n✓ Data has changed!");
-            println!("  Old checksums from: {}", old.last_checked);
-            println!("  New checksums from: {}", new_checksums.last_checked);
-        } else {
-            println!("\n✓ Data is up to date (no changes detected)");
+    println!();
+    for report in &reports {
+        match &report.status {
+            SourceStatus::Changed => {
+                println!("   ✓ {}: changed", report.source.name());
+                if let Some(diff) = &report.diff {
+                    println!(
+                        "      └─ {} added, {} removed, {} modified features (vs {}/)",
+                        diff.added, diff.removed, diff.modified, data_dir
+                    );
+                }
+            }
+            SourceStatus::Unchanged => println!("   = {}: unchanged", report.source.name()),
+            SourceStatus::Error(e) => println!("   ✗ {}: {}", report.source.name(), e),
         }
-    } else {
+    }
+
+    let changed = reports.iter().any(|r| r.status == SourceStatus::Changed);
+    if !has_baseline {
         println!("\n✓ No previous checksums found - created new baseline");
+    } else if changed {
+        println!("\n✓ Data has changed!");
+    } else {
+        println!("\n✓ Data is up to date (no changes detected)");
     }
 
-    new_checksums.save_to_file(checksum_file)?;
+    checksums.save_to_file(checksum_file)?;
     println!("✓ Checksums saved to {}\n", checksum_file);
 
     Ok(())
 }
+
+async fn fetch_datasets(
+    data_dir: &str,
+    base_url: Option<String>,
+    checksum_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("\n📥 Fetching datasets into {}/\n", data_dir);
+
+    let mut checksums =
+        DataChecksum::load_from_file(checksum_file).unwrap_or_else(|_| DataChecksum::malmo());
+    let fetcher = Fetcher::new(data_dir, base_url);
+
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}")?);
+    pb.set_message("Downloading...");
+
+    let reports = fetcher.fetch_all(&mut checksums).await?;
+    pb.finish_with_message("✓ Download complete");
+
+    for report in &reports {
+        if report.updated {
+            println!(
+                "   ✓ {}: {} features, {} bytes → {}",
+                report.source.name(),
+                report.features,
+                report.bytes,
+                report.path.display()
+            );
+        } else {
+            println!("   = {}: unchanged", report.source.name());
+        }
+    }
+
+    checksums.save_to_file(checksum_file)?;
+    println!("\n✓ Checksums saved to {}\n", checksum_file);
+
+    Ok(())
+}
+
+fn rollback_datasets(data_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let fetcher = Fetcher::new(data_dir, None);
+
+    println!("\n⏪ Rolling back datasets in {}/\n", data_dir);
+    for source in DataSource::ALL {
+        if fetcher.rollback(source)? {
+            println!("   ✓ {}: restored previous version", source.name());
+        } else {
+            println!("   - {}: no previous version", source.name());
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+fn search_addresses(
+    query: &str,
+    postnummer: Option<&str>,
+    limit: usize,
+    data_dir: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(data_dir).join(DataSource::Adresser.file_name());
+    let addresses = DataLoader::load_addresses(&path.to_string_lossy())?;
+    let index = AddressIndex::new(&addresses);
+
+    let suggestions = index.search(query, postnummer, limit);
+    println!("\n🔎 {} suggestions for \"{}\"\n", suggestions.len(), query);
+    for suggestion in &suggestions {
+        println!(
+            "   {:<40} {:<8} (score {})",
+            suggestion.adress, suggestion.postnummer, suggestion.score
+        );
+    }
+
+    if suggestions.is_empty() {
+        let streets = index.complete_street(query, 5);
+        if !streets.is_empty() {
+            println!("   Streets matching: {}", streets.join(", "));
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Find an address by name, case ignored; unknown addresses get suggestions
+fn find_address<'a>(
+    addresses: &'a [AdressClean],
+    address: &str,
+    postnummer: Option<&str>,
+) -> Result<&'a AdressClean, Box<dyn std::error::Error>> {
+    let digits = |p: &str| p.chars().filter(|c| c.is_ascii_digit()).collect::<String>();
+
+    let wanted = address.trim().to_lowercase();
+    let mut found: Vec<&AdressClean> = addresses
+        .iter()
+        .filter(|a| a.adress.trim().to_lowercase() == wanted)
+        .filter(|a| postnummer.is_none_or(|p| digits(&a.postnummer) == digits(p)))
+        .collect();
+    found.sort_by(|a, b| a.postnummer.cmp(&b.postnummer));
+    found.dedup_by(|a, b| a.postnummer == b.postnummer);
+
+    match found.as_slice() {
+        [] => {
+            let suggestions: Vec<String> = AddressIndex::new(addresses)
+                .search(address, postnummer, 3)
+                .into_iter()
+                .map(|s| format!("{} ({})", s.adress, s.postnummer))
+                .collect();
+            let hint = if suggestions.is_empty() {
+                String::new()
+            } else {
+                format!(". Did you mean: {}?", suggestions.join(", "))
+            };
+            Err(format!("Address not found: {}{}", address, hint).into())
+        }
+        [target] => Ok(*target),
+        several => {
+            let options: Vec<&str> = several.iter().map(|a| a.postnummer.as_str()).collect();
+            Err(format!(
+                "{} exists in several postnummer ({}); pass --postnummer",
+                address,
+                options.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+/// `[lon, lat]` of an address
+fn address_point(address: &AdressClean) -> Result<[f64; 2], Box<dyn std::error::Error>> {
+    Ok([
+        address.coordinates[0]
+            .to_f64()
+            .ok_or("Invalid address coordinates")?,
+        address.coordinates[1]
+            .to_f64()
+            .ok_or("Invalid address coordinates")?,
+    ])
+}
+
+fn run_compare(
+    algorithms: &[AlgorithmChoice],
+    cutoff: f64,
+    tolerance: f64,
+    data_dir: &str,
+    output: &str,
+    geojson_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if algorithms.len() < 2 {
+        return Err("Compare needs at least two algorithms".into());
+    }
+    let (addresses, miljodata, parkering) = api_from_dir(data_dir)?;
+    let labels: Vec<String> = algorithms.iter().map(algorithm_label).collect();
+
+    println!(
+        "\n⚖️  Comparing {} over {} addresses",
+        labels.join(", "),
+        addresses.len()
+    );
+    println!(
+        "   Cutoff: {} m, tolerance: {} m, reference: {}",
+        cutoff, tolerance, labels[0]
+    );
+
+    let mut csv = String::from("address,postnummer,dataset,kind,spread_m");
+    for label in &labels {
+        csv.push_str(&format!(",{0}_distance_m,{0}_zone_id,{0}_info", label));
+    }
+    csv.push('\n');
+    let mut features = Vec::new();
+    let mut total = 0;
+
+    for (dataset, name, zones) in [
+        ("miljo", "Miljödata", &miljodata),
+        ("parkering", "Parkering", &parkering),
+    ] {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}")?);
+        pb.set_message(format!("Comparing on {}...", name));
+        pb.enable_steady_tick(Duration::from_millis(100));
+
+        let start = Instant::now();
+        let built: Vec<Box<dyn CorrelationAlgo + Sync>> = algorithms
+            .iter()
+            .map(|algorithm| build_algorithm(algorithm, zones))
+            .collect();
+        let mut found = compare_algorithms(&built, &addresses, zones, cutoff, tolerance);
+        pb.finish_and_clear();
+        found.sort_by(|a, b| a.kind.cmp(&b.kind).then(b.spread.total_cmp(&a.spread)));
+        total += found.len();
+
+        let count = |kind: DisagreementKind| found.iter().filter(|d| d.kind == kind).count();
+        println!(
+            "\n{}: {} disagreements in {:.2?}",
+            name,
+            found.len(),
+            start.elapsed()
+        );
+        println!("   ├─ Match / no match: {}", count(DisagreementKind::Match));
+        println!("   ├─ Different zone: {}", count(DisagreementKind::Zone));
+        println!(
+            "   └─ Different distance: {}",
+            count(DisagreementKind::Distance)
+        );
+        println!("   Differences from {}:", labels[0]);
+        for (i, label) in labels.iter().enumerate().skip(1) {
+            let differing = found
+                .iter()
+                .filter(|d| classify(&[d.picks[0], d.picks[i]], zones, tolerance).is_some())
+                .count();
+            println!(
+                "   {} {:<20} {}",
+                if i + 1 == labels.len() {
+                    "└─"
+                } else {
+                    "├─"
+                },
+                label,
+                differing
+            );
+        }
+
+        for disagreement in &found {
+            let address = &addresses[disagreement.address];
+            csv.push_str(&format!(
+                "{},{},{},{},{:.2}",
+                csv_field(&address.adress),
+                csv_field(&address.postnummer),
+                dataset,
+                disagreement.kind.as_str(),
+                disagreement.spread
+            ));
+            for pick in &disagreement.picks {
+                match pick.and_then(|(i, d)| Some((zones.get(i)?, d))) {
+                    Some((zone, distance)) => csv.push_str(&format!(
+                        ",{:.2},{},{}",
+                        distance,
+                        zone.zone_id(),
+                        csv_field(&zone.info)
+                    )),
+                    None => csv.push_str(",,,"),
+                }
+            }
+            csv.push('\n');
+            features.extend(disagreement_features(
+                address,
+                dataset,
+                disagreement,
+                zones,
+                &labels,
+            ));
+        }
+    }
+
+    fs::write(output, csv)?;
+    let collection = serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    fs::write(geojson_path, serde_json::to_string(&collection)?)?;
+    println!(
+        "\n💾 {} disagreements written to {} and {}",
+        total, output, geojson_path
+    );
+    Ok(())
+}
+
+/// Quote a CSV field if needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Address point with every pick, and one line per picked segment naming the algorithms
+fn disagreement_features(
+    address: &AdressClean,
+    dataset: &str,
+    disagreement: &Disagreement,
+    zones: &[MiljoeDataClean],
+    labels: &[String],
+) -> Vec<serde_json::Value> {
+    let Ok(point) = address_point(address) else {
+        return Vec::new();
+    };
+    let mut picks = serde_json::Map::new();
+    let mut segments: Vec<(usize, f64, Vec<&str>)> = Vec::new();
+    for (label, pick) in labels.iter().zip(&disagreement.picks) {
+        let value = match pick.and_then(|(i, d)| Some((i, zones.get(i)?, d))) {
+            Some((index, zone, distance)) => {
+                match segments.iter_mut().find(|(i, _, _)| *i == index) {
+                    Some((_, _, by)) => by.push(label),
+                    None => segments.push((index, distance, vec![label.as_str()])),
+                }
+                serde_json::json!({
+                    "distance": distance,
+                    "zone_id": zone.zone_id(),
+                    "info": zone.info,
+                })
+            }
+            None => serde_json::Value::Null,
+        };
+        picks.insert(label.clone(), value);
+    }
+
+    let mut features = vec![serde_json::json!({
+        "type": "Feature",
+        "geometry": { "type": "Point", "coordinates": point },
+        "properties": {
+            "kind": disagreement.kind.as_str(),
+            "address": address.adress,
+            "postnummer": address.postnummer,
+            "dataset": dataset,
+            "spread": disagreement.spread,
+            "picks": picks,
+        },
+    })];
+    for (index, distance, by) in segments {
+        let zone = &zones[index];
+        let coordinates: Vec<[f64; 2]> = zone
+            .coordinates
+            .iter()
+            .filter_map(|c| Some([c[0].to_f64()?, c[1].to_f64()?]))
+            .collect();
+        features.push(serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "LineString", "coordinates": coordinates },
+            "properties": {
+                "kind": "pick",
+                "address": address.adress,
+                "dataset": dataset,
+                "algorithms": by,
+                "distance": distance,
+                "zone_id": zone.zone_id(),
+                "info": zone.info,
+            },
+        }));
+    }
+    features
+}
+
+fn explain_address(
+    address: &str,
+    postnummer: Option<&str>,
+    radius: f64,
+    cutoff: f64,
+    data_dir: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (addresses, miljodata, parkering) = api_from_dir(data_dir)?;
+    let target = find_address(&addresses, address, postnummer)?;
+    let point = address_point(target)?;
+
+    println!(
+        "\n🔎 {} ({}) at {:.6}, {:.6}",
+        target.adress, target.postnummer, point[0], point[1]
+    );
+    println!("   Radius: {} m, cutoff: {} m", radius, cutoff);
+
+    explain_dataset("Miljödata", target, point, miljodata, radius, cutoff);
+    explain_dataset("Parkering", target, point, parkering, radius, cutoff);
+    Ok(())
+}
+
+fn build_algorithm(
+    algorithm: &AlgorithmChoice,
+    zones: &[MiljoeDataClean],
+) -> Box<dyn CorrelationAlgo + Sync> {
+    match algorithm {
+        AlgorithmChoice::DistanceBased => Box::new(DistanceBasedAlgo),
+        AlgorithmChoice::Raycasting => Box::new(RaycastingAlgo),
+        AlgorithmChoice::OverlappingChunks => Box::new(OverlappingChunksAlgo::new(zones)),
+        AlgorithmChoice::RTree => Box::new(RTreeSpatialAlgo::new(zones)),
+        AlgorithmChoice::KDTree => Box::new(KDTreeSpatialAlgo::new(zones)),
+        AlgorithmChoice::Grid => Box::new(GridNearestAlgo::new(zones)),
+    }
+}
+
+/// Name of an algorithm as given on the command line
+fn algorithm_label(algorithm: &AlgorithmChoice) -> String {
+    algorithm
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn explain_dataset(
+    name: &str,
+    address: &AdressClean,
+    point: [f64; 2],
+    zones: Vec<MiljoeDataClean>,
+    radius: f64,
+    cutoff: f64,
+) {
+    let picks: Vec<(String, Option<(usize, f64)>)> = AlgorithmChoice::value_variants()
+        .iter()
+        .map(|algorithm| {
+            let pick = build_algorithm(algorithm, &zones).correlate(address, &zones);
+            (algorithm_label(algorithm), pick)
+        })
+        .collect();
+    let index = ZoneIndex::new(zones);
+    let found = candidates(&index, point, radius);
+
+    println!("\n{} — {} segments within {} m:", name, found.len(), radius);
+    for (n, candidate) in found.iter().enumerate() {
+        let zone = candidate.zone;
+        println!(
+            "   [{}] {:.2} m {}   zone {}",
+            n + 1,
+            candidate.distance,
+            if candidate.distance <= cutoff {
+                "✓ within cutoff"
+            } else {
+                "✗ beyond cutoff"
+            },
+            zone.zone_id()
+        );
+        println!(
+            "       Projection: {:.6}, {:.6}{}",
+            candidate.projection[0],
+            candidate.projection[1],
+            if candidate.at_end {
+                " (end of segment)"
+            } else {
+                ""
+            }
+        );
+        println!(
+            "       Side: {}, address lies {} of the segment",
+            candidate.side.as_str(),
+            candidate.direction
+        );
+        println!("       Info: {}", zone.info);
+        println!(
+            "       Tid: {}   Dag: {}",
+            if zone.tid.is_empty() { "-" } else { &zone.tid },
+            zone.dag
+        );
+        let picked_by: Vec<&str> = picks
+            .iter()
+            .filter(|(_, pick)| pick.is_some_and(|(i, _)| i == candidate.index))
+            .map(|(label, _)| label.as_str())
+            .collect();
+        if !picked_by.is_empty() {
+            println!("       Picked by: {}", picked_by.join(", "));
+        }
+    }
+
+    println!("   Algorithm picks:");
+    for (n, (label, pick)) in picks.iter().enumerate() {
+        let branch = if n + 1 == picks.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        let Some((zone_index, distance)) = pick else {
+            println!("   {} {:<20} no candidate", branch, label);
+            continue;
+        };
+        let zone = match found.iter().position(|c| c.index == *zone_index) {
+            Some(n) => format!("[{}]", n + 1),
+            None => index
+                .zones()
+                .get(*zone_index)
+                .map(|z| format!("zone {} (outside radius)", z.zone_id()))
+                .unwrap_or_else(|| format!("zone #{}", zone_index)),
+        };
+        println!(
+            "   {} {:<20} {} {:.2} m {}",
+            branch,
+            label,
+            zone,
+            distance,
+            if *distance <= cutoff {
+                "✓ matched"
+            } else {
+                "✗ rejected by cutoff"
+            }
+        );
+    }
+}
+
+fn export_calendar(
+    address: &str,
+    postnummer: Option<&str>,
+    output: &str,
+    data_dir: &str,
+    cutoff: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (addresses, miljodata, _) = api_from_dir(data_dir)?;
+    let target = find_address(&addresses, address, postnummer)?;
+    let point = address_point(target)?;
+    let index = ZoneIndex::new(miljodata);
+    let restrictions: Vec<RecurringRestriction> = index
+        .within(point, cutoff)
+        .iter()
+        .map(|m| RecurringRestriction::from(m.zone))
+        .collect();
+
+    let calendar = address_calendar(
+        &target.adress,
+        &restrictions,
+        Local::now().naive_local(),
+        Utc::now(),
+    );
+    let events = calendar.matches("BEGIN:VEVENT").count();
+    fs::write(output, calendar)?;
+
+    println!(
+        "\n📅 {} ({}): {} recurring restrictions within {} m",
+        target.adress, target.postnummer, events, cutoff
+    );
+    println!("✓ Wrote {}\n", output);
+    Ok(())
+}
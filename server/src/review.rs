@@ -0,0 +1,432 @@
+//! Local review server
+//! Serves a queue of correlated addresses in the StadsAtlas interface and persists the
+//! reviewers' verdicts through the classification store. The queue and progress live in
+//! a session file, so a review can be stopped and resumed.
+
+use crate::classification::{
+    ClassificationData, ClassificationRequest, ClassificationResponse, add_classification,
+    undo_classification,
+};
+use crate::{format_matches_html, load_asset_file, render_interface_page};
+use amp_core::structs::CorrelationResult;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+const SESSION_VERSION: u32 = 1;
+
+/// A classification filed for a queued address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filed {
+    pub category: String,
+    pub id: String,
+}
+
+/// An address in the review queue with the correlation shown to the reviewer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub address: String,
+    pub postnummer: String,
+    pub source: String,
+    pub matches_html: String,
+    #[serde(default)]
+    pub classifications: Vec<Filed>,
+    /// Set when the reviewer moves on; no classifications means the match was correct
+    #[serde(default)]
+    pub reviewed_at: Option<String>,
+}
+
+impl From<&CorrelationResult> for ReviewItem {
+    fn from(result: &CorrelationResult) -> Self {
+        Self {
+            address: result.address.clone(),
+            postnummer: result.postnummer.clone(),
+            source: result.dataset_source(),
+            matches_html: format_matches_html(result),
+            classifications: Vec::new(),
+            reviewed_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewSession {
+    pub version: u32,
+    pub created_at: String,
+    pub algorithm: String,
+    pub cutoff: f64,
+    pub items: Vec<ReviewItem>,
+}
+
+impl ReviewSession {
+    pub fn new(items: Vec<ReviewItem>, algorithm: String, cutoff: f64) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            created_at: Utc::now().to_rfc3339(),
+            algorithm,
+            cutoff,
+            items,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read session {}: {}", path.display(), e))?;
+        let session: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse session {}: {}", path.display(), e))?;
+        if session.version > SESSION_VERSION {
+            return Err(format!(
+                "Session {} has version {}, newer than supported {}",
+                path.display(),
+                session.version,
+                SESSION_VERSION
+            ));
+        }
+        Ok(session)
+    }
+
+    /// Write via a temporary file so an interrupted save keeps the previous session
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize session: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to write session {}: {}", path.display(), e))
+    }
+
+    pub fn reviewed(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.reviewed_at.is_some())
+            .count()
+    }
+
+    /// First unreviewed item after `after`, wrapping around to earlier skipped ones
+    pub fn next_pending(&self, after: Option<usize>) -> Option<usize> {
+        let start = after.map_or(0, |i| i + 1);
+        (start..self.items.len())
+            .chain(0..start.min(self.items.len()))
+            .find(|&i| self.items[i].reviewed_at.is_none())
+    }
+}
+
+pub struct ReviewState {
+    session: Mutex<ReviewSession>,
+    session_path: PathBuf,
+    classification_dir: PathBuf,
+}
+
+impl ReviewState {
+    pub fn new(session: ReviewSession, session_path: PathBuf, classification_dir: PathBuf) -> Self {
+        Self {
+            session: Mutex::new(session),
+            session_path,
+            classification_dir,
+        }
+    }
+
+    fn session(&self) -> MutexGuard<'_, ReviewSession> {
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewAction {
+    index: usize,
+    #[serde(default)]
+    category: String,
+}
+
+type ActionResult = (StatusCode, Json<ClassificationResponse>);
+
+fn respond(status: StatusCode, message: String, id: Option<String>) -> ActionResult {
+    let response = ClassificationResponse {
+        success: status.is_success(),
+        message,
+        id,
+    };
+    (status, Json(response))
+}
+
+async fn index(State(state): State<Arc<ReviewState>>) -> Response {
+    let session = state.session();
+    match session.next_pending(None) {
+        Some(i) => Redirect::to(&format!("/review/{}", i)).into_response(),
+        None => Html(format!(
+            "<!DOCTYPE html><html><body><h1>✅ All {} addresses reviewed</h1>\
+             <p>Classifications are in {}</p></body></html>",
+            session.items.len(),
+            state.classification_dir.display()
+        ))
+        .into_response(),
+    }
+}
+
+async fn page(State(state): State<Arc<ReviewState>>, UrlPath(index): UrlPath<usize>) -> Response {
+    let session = state.session();
+    let Some(item) = session.items.get(index) else {
+        return (StatusCode::NOT_FOUND, "No such queue item").into_response();
+    };
+    match render_interface_page(item, "/origo_map.html", Some(index)) {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn origo_map() -> Response {
+    match load_asset_file("origo_map.html") {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn session_status(State(state): State<Arc<ReviewState>>) -> Json<serde_json::Value> {
+    let session = state.session();
+    let items: Vec<_> = session
+        .items
+        .iter()
+        .map(|item| {
+            serde_json::json!({
+                "address": item.address,
+                "postnummer": item.postnummer,
+                "categories": item.classifications.iter().map(|c| &c.category).collect::<Vec<_>>(),
+                "reviewed": item.reviewed_at.is_some(),
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "created_at": session.created_at,
+        "algorithm": session.algorithm,
+        "total": session.items.len(),
+        "reviewed": session.reviewed(),
+        "next": session.next_pending(None),
+        "items": items,
+    }))
+}
+
+async fn classify(
+    State(state): State<Arc<ReviewState>>,
+    Json(action): Json<ReviewAction>,
+) -> ActionResult {
+    let mut session = state.session();
+    let Some(item) = session.items.get_mut(action.index) else {
+        return respond(
+            StatusCode::NOT_FOUND,
+            "No such queue item".to_string(),
+            None,
+        );
+    };
+
+    let request = ClassificationRequest {
+        category: action.category.clone(),
+        data: ClassificationData {
+            address: item.address.clone(),
+            postal_code: item.postnummer.clone(),
+            source: item.source.clone(),
+            matches_html: item.matches_html.clone(),
+        },
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    let id = match add_classification(&state.classification_dir, &request) {
+        Ok(id) => id,
+        Err(e) => return respond(StatusCode::BAD_REQUEST, e, None),
+    };
+
+    let message = format!(
+        "Saved classification for \"{}\" under \"{}\"",
+        item.address, action.category
+    );
+    item.classifications.push(Filed {
+        category: action.category,
+        id: id.clone(),
+    });
+    match session.save(&state.session_path) {
+        Ok(()) => respond(StatusCode::OK, message, Some(id)),
+        Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e, Some(id)),
+    }
+}
+
+async fn undo(
+    State(state): State<Arc<ReviewState>>,
+    Json(action): Json<ReviewAction>,
+) -> ActionResult {
+    let mut session = state.session();
+    let Some(item) = session.items.get_mut(action.index) else {
+        return respond(
+            StatusCode::NOT_FOUND,
+            "No such queue item".to_string(),
+            None,
+        );
+    };
+    let Some(position) = item
+        .classifications
+        .iter()
+        .rposition(|c| c.category == action.category)
+    else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Nothing to undo for \"{}\" in \"{}\"",
+                item.address, action.category
+            ),
+            None,
+        );
+    };
+
+    let id = &item.classifications[position].id;
+    let message = match undo_classification(&state.classification_dir, &action.category, id) {
+        Ok(message) => message,
+        Err(e) => return respond(StatusCode::BAD_REQUEST, e, None),
+    };
+    let removed = item.classifications.remove(position);
+    match session.save(&state.session_path) {
+        Ok(()) => respond(StatusCode::OK, message, Some(removed.id)),
+        Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e, None),
+    }
+}
+
+async fn next(
+    State(state): State<Arc<ReviewState>>,
+    Json(action): Json<ReviewAction>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let mut session = state.session();
+    let Some(item) = session.items.get_mut(action.index) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "success": false, "message": "No such queue item" })),
+        );
+    };
+    item.reviewed_at
+        .get_or_insert_with(|| Utc::now().to_rfc3339());
+
+    let next = session.next_pending(Some(action.index));
+    match session.save(&state.session_path) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "success": true, "next": next })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        ),
+    }
+}
+
+pub fn router(state: Arc<ReviewState>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/review/{index}", get(page))
+        .route("/origo_map.html", get(origo_map))
+        .route("/api/session", get(session_status))
+        .route("/api/classify", post(classify))
+        .route("/api/undo", post(undo))
+        .route("/api/next", post(next))
+        .with_state(state)
+}
+
+pub async fn serve(
+    bind: &str,
+    state: ReviewState,
+    open: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    println!(
+        "🌐 Review queue at {} (Ctrl+C to stop, progress is saved)",
+        url
+    );
+    if open {
+        crate::open_url(&url);
+    }
+    axum::serve(listener, router(Arc::new(state))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amp_core::structs::MatchSource;
+
+    fn item(address: &str) -> ReviewItem {
+        ReviewItem::from(&CorrelationResult {
+            address: address.to_string(),
+            postnummer: "211 42".to_string(),
+            miljo_match: Some((3.5, "Städning".to_string())),
+            parkering_match: None,
+            source: MatchSource::Algorithm,
+        })
+    }
+
+    async fn post_json(url: &str, body: serde_json::Value) -> (u16, serde_json::Value) {
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_review_flow_and_resume() {
+        let dir = std::env::temp_dir().join(format!("amp_review_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let session_path = dir.join("session.json");
+        let session = ReviewSession::new(
+            vec![item("Storgatan 1"), item("Lokgatan 11B")],
+            "KDTree".to_string(),
+            50.0,
+        );
+        session.save(&session_path).unwrap();
+
+        let state = ReviewState::new(session, session_path.clone(), dir.join("classifications"));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/api", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, router(Arc::new(state)))
+                .await
+                .unwrap();
+        });
+
+        let (status, body) = post_json(
+            &format!("{}/classify", base),
+            serde_json::json!({ "index": 0, "category": "notMatching" }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert!(body["id"].as_str().unwrap().starts_with("notMatching-"));
+
+        let (status, _) = post_json(
+            &format!("{}/classify", base),
+            serde_json::json!({ "index": 0, "category": "correct" }),
+        )
+        .await;
+        assert_eq!(status, 400);
+
+        let (status, body) =
+            post_json(&format!("{}/next", base), serde_json::json!({ "index": 0 })).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["next"], 1);
+
+        // A restarted server picks up where the reviewer left off
+        let resumed = ReviewSession::load(&session_path).unwrap();
+        assert_eq!(resumed.reviewed(), 1);
+        assert_eq!(resumed.next_pending(None), Some(1));
+        assert_eq!(resumed.items[0].classifications[0].category, "notMatching");
+        let filed = fs::read_to_string(dir.join("classifications/amp_stadsatlas_notMatching.json"))
+            .unwrap();
+        assert!(filed.contains("Storgatan 1"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
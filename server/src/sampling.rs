@@ -0,0 +1,382 @@
+//! Reproducible sampling of correlation results for manual checks
+//! Samples are drawn with a seeded RNG, optionally stratified, and written to a sample
+//! file so the same addresses can be replayed by `test`, `correlate` and `review`
+
+use amp_core::incremental::result_key;
+use amp_core::structs::CorrelationResult;
+use chrono::Utc;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_SAMPLE_PATH: &str = "sample.json";
+const SAMPLE_VERSION: u32 = 1;
+
+/// Number of distance bands between zero and the cutoff
+const DISTANCE_BANDS: usize = 4;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SampleMode {
+    /// Uniform over all matches
+    Random,
+    /// Evenly across Miljödata only, Parkering only and both
+    Source,
+    /// Evenly across distance bands up to the cutoff
+    Distance,
+    /// Evenly across postnummer
+    Postnummer,
+    /// Largest match distances first
+    LowConfidence,
+}
+
+impl SampleMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SampleMode::Random => "random",
+            SampleMode::Source => "source",
+            SampleMode::Distance => "distance",
+            SampleMode::Postnummer => "postnummer",
+            SampleMode::LowConfidence => "low-confidence",
+        }
+    }
+
+    /// Stratum a result is drawn from
+    fn stratum(&self, result: &CorrelationResult, cutoff: f64) -> String {
+        match self {
+            SampleMode::Random | SampleMode::LowConfidence => String::new(),
+            SampleMode::Source => result.dataset_source(),
+            SampleMode::Distance => distance_band(result.closest_distance(), cutoff),
+            SampleMode::Postnummer => result.postnummer.clone(),
+        }
+    }
+}
+
+/// Band label such as "12.5–25 m"; distances beyond the cutoff share the last band
+fn distance_band(distance: Option<f64>, cutoff: f64) -> String {
+    let Some(distance) = distance else {
+        return "no match".to_string();
+    };
+    let width = cutoff / DISTANCE_BANDS as f64;
+    let band = ((distance / width) as usize).min(DISTANCE_BANDS - 1);
+    format!("{}–{} m", band as f64 * width, (band + 1) as f64 * width)
+}
+
+/// Sampling arguments shared by the commands that show a sample
+#[derive(clap::Args, Debug, Clone)]
+pub struct SampleArgs {
+    #[arg(
+        long,
+        help = "Seed for the sample; a random seed is drawn and printed if omitted"
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(long, value_enum, default_value_t = SampleMode::Random, help = "How the sample is drawn")]
+    pub sample: SampleMode,
+
+    #[arg(
+        long,
+        help = "File the selected sample is written to (test and review: sample.json)"
+    )]
+    pub sample_file: Option<String>,
+
+    #[arg(
+        long,
+        help = "Replay the addresses of a sample file instead of drawing a new sample"
+    )]
+    pub replay: Option<String>,
+}
+
+/// Seed to use, drawing a fresh one when none was given so it can still be reported
+pub fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(rand::random)
+}
+
+/// A sampled result and the stratum it was drawn from
+pub struct Sampled<'a> {
+    pub result: &'a CorrelationResult,
+    pub stratum: String,
+}
+
+/// Draw up to `count` results. Strata are shuffled independently and taken round-robin,
+/// so small strata are represented as well as large ones. Results are sorted by key
+/// first, which makes the sample depend only on the seed and the data
+pub fn select<'a>(
+    results: &[&'a CorrelationResult],
+    mode: SampleMode,
+    count: usize,
+    cutoff: f64,
+    seed: u64,
+) -> Vec<Sampled<'a>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sorted = results.to_vec();
+    sorted.sort_by_cached_key(|r| result_key(r));
+
+    if mode == SampleMode::LowConfidence {
+        // Shuffle before the stable sort so equal distances come in random order
+        sorted.shuffle(&mut rng);
+        sorted.sort_by(|a, b| {
+            let distance = |r: &CorrelationResult| r.closest_distance().unwrap_or(-1.0);
+            distance(b).total_cmp(&distance(a))
+        });
+        return sorted
+            .into_iter()
+            .take(count)
+            .map(|result| Sampled {
+                stratum: distance_band(result.closest_distance(), cutoff),
+                result,
+            })
+            .collect();
+    }
+
+    let mut strata: BTreeMap<String, Vec<&CorrelationResult>> = BTreeMap::new();
+    for result in sorted {
+        strata
+            .entry(mode.stratum(result, cutoff))
+            .or_default()
+            .push(result);
+    }
+    let mut queues: Vec<_> = strata
+        .into_iter()
+        .map(|(stratum, mut members)| {
+            members.shuffle(&mut rng);
+            (stratum, members.into_iter())
+        })
+        .collect();
+
+    let mut sample = Vec::new();
+    while sample.len() < count {
+        let before = sample.len();
+        for (stratum, members) in queues.iter_mut() {
+            if sample.len() == count {
+                break;
+            }
+            if let Some(result) = members.next() {
+                sample.push(Sampled {
+                    result,
+                    stratum: stratum.clone(),
+                });
+            }
+        }
+        if sample.len() == before {
+            break;
+        }
+    }
+    sample
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleEntry {
+    pub address: String,
+    pub postnummer: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stratum: String,
+    pub distance: Option<f64>,
+}
+
+/// Selected sample and how it was drawn, for replaying the same addresses later
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SampleFile {
+    pub version: u32,
+    pub created_at: String,
+    pub seed: u64,
+    pub mode: SampleMode,
+    pub algorithm: String,
+    pub cutoff: f64,
+    pub entries: Vec<SampleEntry>,
+}
+
+impl SampleFile {
+    pub fn new(
+        sample: &[Sampled],
+        seed: u64,
+        mode: SampleMode,
+        algorithm: String,
+        cutoff: f64,
+    ) -> Self {
+        Self {
+            version: SAMPLE_VERSION,
+            created_at: Utc::now().to_rfc3339(),
+            seed,
+            mode,
+            algorithm,
+            cutoff,
+            entries: sample
+                .iter()
+                .map(|s| SampleEntry {
+                    address: s.result.address.clone(),
+                    postnummer: s.result.postnummer.clone(),
+                    stratum: s.stratum.clone(),
+                    distance: s.result.closest_distance(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read sample {}: {}", path.display(), e))?;
+        let file: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse sample {}: {}", path.display(), e))?;
+        if file.version > SAMPLE_VERSION {
+            return Err(format!(
+                "Sample {} has version {}, newer than supported {}",
+                path.display(),
+                file.version,
+                SAMPLE_VERSION
+            ));
+        }
+        Ok(file)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize sample: {}", e))?;
+        fs::write(path, json)
+            .map_err(|e| format!("Failed to write sample {}: {}", path.display(), e))
+    }
+
+    /// Current results for the sampled addresses, in sample order, and the addresses
+    /// that are no longer in the results
+    pub fn replay<'a>(&self, results: &[&'a CorrelationResult]) -> (Vec<Sampled<'a>>, Vec<String>) {
+        let by_key: HashMap<String, &CorrelationResult> =
+            results.iter().map(|r| (result_key(r), *r)).collect();
+        let mut sample = Vec::new();
+        let mut missing = Vec::new();
+        for entry in &self.entries {
+            match by_key.get(&format!("{}|{}", entry.address, entry.postnummer)) {
+                Some(result) => sample.push(Sampled {
+                    result,
+                    stratum: entry.stratum.clone(),
+                }),
+                None => missing.push(format!("{} ({})", entry.address, entry.postnummer)),
+            }
+        }
+        (sample, missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amp_core::structs::MatchSource;
+
+    fn result(
+        address: &str,
+        postnummer: &str,
+        miljo: Option<f64>,
+        parkering: Option<f64>,
+    ) -> CorrelationResult {
+        CorrelationResult {
+            address: address.to_string(),
+            postnummer: postnummer.to_string(),
+            miljo_match: miljo.map(|d| (d, "Tisdag 08-12".to_string())),
+            parkering_match: parkering.map(|d| (d, "Taxa C".to_string())),
+            source: MatchSource::Algorithm,
+        }
+    }
+
+    fn results() -> Vec<CorrelationResult> {
+        (0..40)
+            .map(|i| {
+                let postnummer = if i % 10 == 0 { "21120" } else { "21142" };
+                match i % 8 {
+                    0 => result(
+                        &format!("Storgatan {}", i),
+                        postnummer,
+                        Some(3.0),
+                        Some(48.0),
+                    ),
+                    1..=5 => result(
+                        &format!("Storgatan {}", i),
+                        postnummer,
+                        Some(i as f64),
+                        None,
+                    ),
+                    _ => result(&format!("Storgatan {}", i), postnummer, None, Some(30.0)),
+                }
+            })
+            .collect()
+    }
+
+    fn keys(sample: &[Sampled]) -> Vec<String> {
+        sample.iter().map(|s| result_key(s.result)).collect()
+    }
+
+    #[test]
+    fn test_seeded_stratified_selection() {
+        let results = results();
+        let refs: Vec<&CorrelationResult> = results.iter().collect();
+        let mut reversed = refs.clone();
+        reversed.reverse();
+
+        // Same seed, same sample regardless of input order
+        let first = select(&refs, SampleMode::Random, 10, 50.0, 7);
+        assert_eq!(
+            keys(&first),
+            keys(&select(&reversed, SampleMode::Random, 10, 50.0, 7))
+        );
+        assert_ne!(
+            keys(&first),
+            keys(&select(&refs, SampleMode::Random, 10, 50.0, 8))
+        );
+
+        // Small strata are represented
+        let by_source = select(&refs, SampleMode::Source, 3, 50.0, 7);
+        let mut strata: Vec<_> = by_source.iter().map(|s| s.stratum.as_str()).collect();
+        strata.sort();
+        assert_eq!(
+            strata,
+            [
+                "Both (Miljödata + Parkering)",
+                "Miljödata only",
+                "Parkering only"
+            ]
+        );
+        let by_postnummer = select(&refs, SampleMode::Postnummer, 2, 50.0, 7);
+        assert_eq!(by_postnummer[0].result.postnummer, "21120");
+        assert_eq!(by_postnummer[1].result.postnummer, "21142");
+        assert_eq!(select(&refs, SampleMode::Distance, 100, 50.0, 7).len(), 40);
+
+        let low = select(&refs, SampleMode::LowConfidence, 5, 50.0, 7);
+        let distances: Vec<_> = low
+            .iter()
+            .filter_map(|s| s.result.closest_distance())
+            .collect();
+        assert_eq!(distances, [37.0, 36.0, 35.0, 34.0, 33.0]);
+        assert_eq!(low[0].stratum, "25–37.5 m");
+    }
+
+    #[test]
+    fn test_sample_file_replay() {
+        let results = results();
+        let refs: Vec<&CorrelationResult> = results.iter().collect();
+        let sample = select(&refs, SampleMode::Distance, 6, 50.0, 42);
+        let file = SampleFile::new(
+            &sample,
+            42,
+            SampleMode::Distance,
+            "KDTree".to_string(),
+            50.0,
+        );
+
+        let path = std::env::temp_dir().join(format!("amp_sample_{}.json", std::process::id()));
+        file.save(&path).unwrap();
+        let loaded = SampleFile::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.seed, 42);
+        assert_eq!(loaded.mode, SampleMode::Distance);
+
+        let (replayed, missing) = loaded.replay(&refs[1..]);
+        let mut expected = keys(&sample);
+        let dropped = result_key(refs[0]);
+        let was_sampled = expected.contains(&dropped);
+        expected.retain(|k| *k != dropped);
+        assert_eq!(keys(&replayed), expected);
+        assert_eq!(missing.len(), usize::from(was_sampled));
+    }
+}
@@ -0,0 +1,449 @@
+//! HTTP lookup service
+//! Loads the datasets and builds spatial indexes once, then answers address,
+//! point and bounding box queries as JSON
+
+use amp_core::api::api_from_dir;
+use amp_core::geocode::Geocoder;
+use amp_core::schedule::next_window;
+use amp_core::structs::{AdressClean, MiljoeDataClean};
+use amp_core::zone_index::{ZoneIndex, ZoneMatch};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Local, NaiveDateTime};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Maximum number of zones per dataset returned by `/zones`
+const MAX_BBOX_ZONES: usize = 1000;
+
+pub struct AppState {
+    geocoder: Geocoder,
+    /// lowercased address → indices into the geocoder's addresses
+    by_address: HashMap<String, Vec<usize>>,
+    loaded_at: String,
+}
+
+impl AppState {
+    pub fn new(
+        addresses: Vec<AdressClean>,
+        miljodata: Vec<MiljoeDataClean>,
+        parkering: Vec<MiljoeDataClean>,
+        cutoff: f64,
+    ) -> Self {
+        let mut by_address: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, address) in addresses.iter().enumerate() {
+            by_address
+                .entry(address.adress.trim().to_lowercase())
+                .or_default()
+                .push(i);
+        }
+
+        Self {
+            geocoder: Geocoder::new(addresses, miljodata, parkering).with_cutoff(cutoff),
+            by_address,
+            loaded_at: Local::now().to_rfc3339(),
+        }
+    }
+
+    pub fn load(data_dir: &str, cutoff: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        let (addresses, miljodata, parkering) = api_from_dir(data_dir)?;
+        Ok(Self::new(addresses, miljodata, parkering, cutoff))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RestrictionWindow {
+    start: String,
+    end: String,
+    active: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ZoneJson {
+    info: String,
+    tid: String,
+    dag: u8,
+    /// `[[lon, lat], [lon, lat]]`
+    coordinates: Option<[[f64; 2]; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance: Option<f64>,
+    next_window: Option<RestrictionWindow>,
+}
+
+impl ZoneJson {
+    fn new(index: &ZoneIndex, zone_index: usize, distance: Option<f64>, at: NaiveDateTime) -> Self {
+        let zone = &index.zones()[zone_index];
+        let next_window = next_window(zone.dag, &zone.tid, at).map(|w| RestrictionWindow {
+            start: w.start.format("%Y-%m-%dT%H:%M").to_string(),
+            end: w.end.format("%Y-%m-%dT%H:%M").to_string(),
+            active: w.is_active(at),
+        });
+
+        Self {
+            info: zone.info.clone(),
+            tid: zone.tid.clone(),
+            dag: zone.dag,
+            coordinates: index.segment(zone_index),
+            distance: distance.map(|d| (d * 100.0).round() / 100.0),
+            next_window,
+        }
+    }
+
+    fn from_match(index: &ZoneIndex, found: ZoneMatch, at: NaiveDateTime) -> Self {
+        Self::new(index, found.index, Some(found.distance), at)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AddressJson {
+    address: String,
+    postnummer: String,
+    lon: Option<f64>,
+    lat: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance: Option<f64>,
+}
+
+impl AddressJson {
+    fn new(address: &AdressClean) -> Self {
+        Self {
+            address: address.adress.clone(),
+            postnummer: address.postnummer.clone(),
+            lon: address.coordinates[0].to_f64(),
+            lat: address.coordinates[1].to_f64(),
+            distance: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LookupResult {
+    address: AddressJson,
+    miljo: Option<ZoneJson>,
+    parkering: Option<ZoneJson>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LookupParams {
+    address: String,
+    postnummer: Option<String>,
+    at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NearestParams {
+    lat: f64,
+    lon: f64,
+    radius: Option<f64>,
+    at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZonesParams {
+    /// `min_lon,min_lat,max_lon,max_lat`
+    bbox: String,
+    at: Option<String>,
+}
+
+/// JSON error response
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, rejection.body_text())
+    }
+}
+
+type ApiResult = Result<Json<serde_json::Value>, ApiError>;
+
+/// Evaluation time for restriction windows: `at` (YYYY-MM-DDTHH:MM) or local now
+fn evaluation_time(at: Option<&str>) -> Result<NaiveDateTime, ApiError> {
+    match at {
+        None => Ok(Local::now().naive_local()),
+        Some(value) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+            .map_err(|_| {
+                ApiError(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid 'at' time: {} (expected YYYY-MM-DDTHH:MM)", value),
+                )
+            }),
+    }
+}
+
+fn normalize_postnummer(postnummer: &str) -> String {
+    postnummer.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn lookup_point(
+    state: &AppState,
+    point: [f64; 2],
+    radius: f64,
+    at: NaiveDateTime,
+) -> serde_json::Value {
+    let geocoder = &state.geocoder;
+    let miljo = geocoder
+        .miljo()
+        .nearest(point, radius)
+        .map(|m| ZoneJson::from_match(geocoder.miljo(), m, at));
+    let parkering = geocoder
+        .parkering()
+        .nearest(point, radius)
+        .map(|m| ZoneJson::from_match(geocoder.parkering(), m, at));
+    let address = geocoder.nearest_address(point[1], point[0]).map(|nearest| {
+        let mut address = AddressJson::new(nearest.address);
+        address.distance = Some((nearest.distance * 100.0).round() / 100.0);
+        address
+    });
+    serde_json::json!({ "address": address, "miljo": miljo, "parkering": parkering })
+}
+
+async fn health(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "addresses": state.geocoder.addresses().len(),
+        "miljo_zones": state.geocoder.miljo().len(),
+        "parkering_zones": state.geocoder.parkering().len(),
+        "cutoff": state.geocoder.cutoff(),
+        "loaded_at": state.loaded_at,
+    }))
+}
+
+async fn lookup(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<LookupParams>, QueryRejection>,
+) -> ApiResult {
+    let Query(params) = params?;
+    let at = evaluation_time(params.at.as_deref())?;
+    let postnummer = params.postnummer.as_deref().map(normalize_postnummer);
+
+    let results: Vec<LookupResult> = state
+        .by_address
+        .get(&params.address.trim().to_lowercase())
+        .into_iter()
+        .flatten()
+        .map(|&i| &state.geocoder.addresses()[i])
+        .filter(|a| {
+            postnummer
+                .as_ref()
+                .is_none_or(|p| normalize_postnummer(&a.postnummer) == *p)
+        })
+        .map(|a| {
+            let lon = a.coordinates[0].to_f64();
+            let lat = a.coordinates[1].to_f64();
+            let nearest = |index: &ZoneIndex| {
+                let point = [lon?, lat?];
+                let found = index.nearest(point, state.geocoder.cutoff())?;
+                Some(ZoneJson::from_match(index, found, at))
+            };
+
+            LookupResult {
+                miljo: nearest(state.geocoder.miljo()),
+                parkering: nearest(state.geocoder.parkering()),
+                address: AddressJson::new(a),
+            }
+        })
+        .collect();
+
+    if results.is_empty() {
+        return Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("Address not found: {}", params.address),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+async fn nearest(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<NearestParams>, QueryRejection>,
+) -> ApiResult {
+    let Query(params) = params?;
+    let at = evaluation_time(params.at.as_deref())?;
+    if !(-90.0..=90.0).contains(&params.lat) || !(-180.0..=180.0).contains(&params.lon) {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "lat must be between -90 and 90 and lon between -180 and 180".to_string(),
+        ));
+    }
+    let radius = params.radius.unwrap_or(state.geocoder.cutoff());
+    if !(radius > 0.0 && radius <= 1000.0) {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "radius must be between 0 and 1000 meters".to_string(),
+        ));
+    }
+
+    let mut response = lookup_point(&state, [params.lon, params.lat], radius, at);
+    response["radius"] = radius.into();
+    Ok(Json(response))
+}
+
+async fn zones(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<ZonesParams>, QueryRejection>,
+) -> ApiResult {
+    let Query(params) = params?;
+    let at = evaluation_time(params.at.as_deref())?;
+
+    let values: Vec<f64> = params
+        .bbox
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ApiError(StatusCode::BAD_REQUEST, "Invalid bbox".to_string()))?;
+    let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "bbox must be min_lon,min_lat,max_lon,max_lat".to_string(),
+        ));
+    };
+
+    let mut truncated = false;
+    let mut collect = |index: &ZoneIndex| -> Vec<ZoneJson> {
+        let found = index.in_bbox([min_lon, min_lat], [max_lon, max_lat]);
+        truncated |= found.len() > MAX_BBOX_ZONES;
+        found
+            .into_iter()
+            .take(MAX_BBOX_ZONES)
+            .map(|(i, _)| ZoneJson::new(index, i, None, at))
+            .collect()
+    };
+    let miljo = collect(state.geocoder.miljo());
+    let parkering = collect(state.geocoder.parkering());
+
+    Ok(Json(serde_json::json!({
+        "miljo": miljo,
+        "parkering": parkering,
+        "truncated": truncated,
+    })))
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/lookup", get(lookup))
+        .route("/nearest", get(nearest))
+        .route("/zones", get(zones))
+        .with_state(state)
+}
+
+pub async fn serve(bind: &str, state: AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    println!("🌐 Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(Arc::new(state))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn state() -> AppState {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        let address = AdressClean {
+            coordinates: [d("13.0005"), d("55.6001")],
+            postnummer: "211 42".to_string(),
+            adress: "Storgatan 1".to_string(),
+            gata: "Storgatan".to_string(),
+            gatunummer: "1".to_string(),
+        };
+        let zone = |info: &str, tid: &str, dag| MiljoeDataClean {
+            coordinates: [[d("13.0000"), d("55.6000")], [d("13.0010"), d("55.6000")]],
+            info: info.to_string(),
+            tid: tid.to_string(),
+            dag,
+        };
+
+        AppState::new(
+            vec![address],
+            vec![zone("Städning", "0800 - 1200", 15)],
+            vec![zone("Taxa 5", "08:00–20:00", 0)],
+            50.0,
+        )
+    }
+
+    async fn spawn_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router(Arc::new(state())))
+                .await
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn get_json(url: &str) -> (u16, serde_json::Value) {
+        let response = reqwest::get(url).await.unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_endpoints() {
+        let base = spawn_server().await;
+
+        let (status, body) = get_json(&format!("{}/health", base)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["addresses"], 1);
+
+        let (status, body) = get_json(&format!(
+            "{}/lookup?address=storgatan%201&postnummer=21142&at=2026-03-15T09:00",
+            base
+        ))
+        .await;
+        assert_eq!(status, 200);
+        let result = &body["results"][0];
+        assert_eq!(result["miljo"]["info"], "Städning");
+        assert_eq!(result["miljo"]["next_window"]["start"], "2026-03-15T08:00");
+        assert_eq!(result["miljo"]["next_window"]["active"], true);
+        assert_eq!(
+            result["parkering"]["next_window"]["end"],
+            "2026-03-15T20:00"
+        );
+        assert!(result["miljo"]["distance"].as_f64().unwrap() < 15.0);
+
+        let (status, body) = get_json(&format!("{}/lookup?address=Okänd%209", base)).await;
+        assert_eq!(status, 404);
+        assert!(body["error"].is_string());
+
+        let (status, body) = get_json(&format!(
+            "{}/nearest?lat=55.6002&lon=13.0005&radius=30",
+            base
+        ))
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body["parkering"]["info"], "Taxa 5");
+        assert_eq!(body["address"]["address"], "Storgatan 1");
+
+        let (status, body) = get_json(&format!("{}/nearest?lat=NaN&lon=13.0", base)).await;
+        assert_eq!(status, 400);
+        assert!(body["error"].is_string());
+        let (status, _) = get_json(&format!("{}/nearest?lat=55.6&lon=inf", base)).await;
+        assert_eq!(status, 400);
+
+        let (status, body) =
+            get_json(&format!("{}/zones?bbox=12.99,55.59,13.01,55.61", base)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["miljo"].as_array().unwrap().len(), 1);
+
+        let (status, _) = get_json(&format!("{}/zones?bbox=1,2,3", base)).await;
+        assert_eq!(status, 400);
+    }
+}
@@ -0,0 +1,162 @@
+//! Address dataset bundled with the app
+//! Validates user-entered addresses and looks up their cleaning schedule
+
+use amp_core::ics::RecurringRestriction;
+use amp_core::schedule::{Window, next_window};
+use amp_core::search::{AddressIndex, fold};
+use amp_core::structs::{AdressClean, AdressInfo};
+use amp_core::zone_index::haversine_distance;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+/// How many suggestions to offer for an unknown address
+const SUGGESTIONS: usize = 3;
+
+pub struct AddressData {
+    rows: Vec<AdressInfo>,
+    /// Folded `adress` → rows of that address, one per restriction
+    by_address: HashMap<String, Vec<usize>>,
+    index: AddressIndex,
+}
+
+impl AddressData {
+    pub fn new(rows: Vec<AdressInfo>) -> Self {
+        let mut by_address: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, row) in rows.iter().enumerate() {
+            by_address.entry(fold(&row.adress)).or_default().push(i);
+        }
+        let clean: Vec<AdressClean> = rows.iter().map(AdressInfo::to_adress_clean).collect();
+
+        Self {
+            index: AddressIndex::new(&clean),
+            by_address,
+            rows,
+        }
+    }
+
+    /// Rows of an address; rows without a postnummer match any `postal`
+    fn rows_for<'a>(
+        &'a self,
+        street: &str,
+        postal: &'a str,
+    ) -> impl Iterator<Item = &'a AdressInfo> {
+        let postal = digits(postal);
+        self.by_address
+            .get(&fold(street))
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.rows[i])
+            .filter(move |row| {
+                postal.is_empty() || row.postnummer.is_empty() || digits(&row.postnummer) == postal
+            })
+    }
+
+    /// Dataset spelling of an address, or a message with suggestions if it is unknown
+    ///
+    /// Returns the street and postnummer to store; the postnummer is empty when
+    /// neither the input nor the dataset has one.
+    pub fn validate(&self, street: &str, postal: &str) -> Result<(String, String), String> {
+        // Exact matches come from the rows; the search only ranks the first few hits
+        if let Some(found) = self.rows_for(street, postal).next() {
+            let postnummer = if found.postnummer.is_empty() {
+                postal.to_string()
+            } else {
+                found.postnummer.clone()
+            };
+            return Ok((found.adress.clone(), postnummer));
+        }
+
+        let suggestions = self.index.search(street, None, SUGGESTIONS);
+        let names: Vec<String> = suggestions
+            .iter()
+            .map(|s| match s.postnummer.as_str() {
+                "" => s.adress.clone(),
+                postnummer => format!("{} ({})", s.adress, postnummer),
+            })
+            .collect();
+        if names.is_empty() {
+            Err(format!("Hittade inte {} i Malmö", street.trim()))
+        } else {
+            Err(format!(
+                "Hittade inte {}. Menade du {}?",
+                street.trim(),
+                names.join(", ")
+            ))
+        }
+    }
+
+    /// Address closest to a position, if within `max_distance` meters
+    pub fn nearest(&self, lat: f64, lon: f64, max_distance: f64) -> Option<&AdressInfo> {
+        self.rows
+            .iter()
+            .map(|row| (haversine_distance([lon, lat], row.coordinates), row))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, row)| row)
+    }
+
+    /// Recurring restrictions of an address, e.g. for a calendar export
+    pub fn restrictions(&self, street: &str, postal: &str) -> Vec<RecurringRestriction> {
+        self.rows_for(street, postal)
+            .filter(|row| row.has_restriction())
+            .map(RecurringRestriction::from)
+            .collect()
+    }
+
+    /// Earliest current or upcoming restriction window of an address at `now`
+    pub fn next_window(&self, street: &str, postal: &str, now: NaiveDateTime) -> Option<Window> {
+        self.rows_for(street, postal)
+            .filter(|row| row.has_restriction())
+            .filter_map(|row| next_window(row.dag, &row.tid, now))
+            .min_by_key(|window| window.start)
+    }
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(adress: &str, postnummer: &str) -> AdressInfo {
+        AdressInfo {
+            coordinates: [13.0, 55.6],
+            postnummer: postnummer.to_string(),
+            adress: adress.to_string(),
+            gata: adress.split(' ').next().unwrap_or_default().to_string(),
+            gatunummer: "1".to_string(),
+            dag: 1,
+            tid: "0800 - 1200".to_string(),
+            info: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_street_in_many_postnummer() {
+        let rows = ["211 11", "211 12", "211 13", "211 14", "211 15"]
+            .iter()
+            .map(|postnummer| row("Storgatan 1", postnummer))
+            .collect();
+        let data = AddressData::new(rows);
+
+        assert_eq!(
+            data.validate("storgatan 1", "21115"),
+            Ok(("Storgatan 1".to_string(), "211 15".to_string()))
+        );
+        let error = data.validate("Storgatan 1", "21199").unwrap_err();
+        assert!(error.contains("Menade du Storgatan 1"), "{}", error);
+        assert!(data.validate("Lillgatan 9", "21111").is_err());
+    }
+
+    #[test]
+    fn test_validate_without_postnummer() {
+        let data = AddressData::new(vec![row("Storgatan 1", "211 11")]);
+
+        assert_eq!(
+            data.validate("Storgatan 1", ""),
+            Ok(("Storgatan 1".to_string(), "211 11".to_string()))
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Data files of the app: the bundled dataset and the user's saved addresses
+
+use crate::components::dataset::AddressData;
+use crate::components::notification::DEFAULT_LEAD_TIMES;
+use crate::ui::ScheduleType;
+use amp_core::parquet::{read_address_info_parquet_bytes, read_saved_addresses_parquet_from};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+static ADDRESS_DATA: Asset = asset!("/assets/data/adress_info.parquet");
+
+/// Read and index the bundled address dataset
+pub async fn load_address_data() -> Result<AddressData, String> {
+    let bytes = dioxus::asset_resolver::read_asset_bytes(&ADDRESS_DATA)
+        .await
+        .map_err(|e| format!("Failed to read address data: {}", e))?;
+    let rows = read_address_info_parquet_bytes(bytes)
+        .map_err(|e| format!("Failed to parse address data: {}", e))?;
+    Ok(AddressData::new(rows))
+}
+
+/// Current layout of the saved addresses file
+pub const STORAGE_VERSION: u32 = 2;
+
+const STORAGE_FILE: &str = "addresses.json";
+/// Saved addresses of earlier app versions, migrated on first load
+const LEGACY_FILE: &str = "local.parquet";
+
+/// A saved address with its last known category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedAddress {
+    pub street: String,
+    pub postal: String,
+    pub active: bool,
+    pub kind: ScheduleType,
+    /// Unix seconds
+    pub deadline: Option<u64>,
+}
+
+/// User preferences, stored next to the addresses since version 2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Minutes before a cleaning window starts to send a reminder
+    pub lead_times: Vec<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            lead_times: DEFAULT_LEAD_TIMES.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredFile {
+    version: u32,
+    addresses: Vec<SavedAddress>,
+    settings: Settings,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredFileV1 {
+    addresses: Vec<SavedAddress>,
+}
+
+/// Saved addresses and settings in a directory provided by the platform
+pub struct Storage {
+    dir: PathBuf,
+}
+
+impl Storage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(STORAGE_FILE)
+    }
+
+    /// Saved addresses and settings; migrates a legacy file if no current one exists
+    pub fn load(&self) -> Result<(Vec<SavedAddress>, Settings), String> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok((self.migrate_legacy()?, Settings::default()));
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let file = upgrade(value)?;
+        Ok((file.addresses, file.settings))
+    }
+
+    pub fn save(&self, addresses: &[SavedAddress], settings: &Settings) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+
+        let file = StoredFile {
+            version: STORAGE_VERSION,
+            addresses: addresses.to_vec(),
+            settings: settings.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize addresses: {}", e))?;
+
+        // Write to a temporary file first so a crash never leaves a truncated file
+        let path = self.path();
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+
+    /// Convert `local.parquet` to the current format and keep it as `local.parquet.migrated`
+    fn migrate_legacy(&self) -> Result<Vec<SavedAddress>, String> {
+        let legacy = self.dir.join(LEGACY_FILE);
+        if !legacy.exists() {
+            return Ok(Vec::new());
+        }
+
+        let rows = read_saved_addresses_parquet_from(&legacy.to_string_lossy())
+            .map_err(|e| format!("Failed to migrate {}: {}", legacy.display(), e))?;
+
+        let mut addresses: Vec<SavedAddress> = Vec::new();
+        for (row, active) in rows {
+            if addresses
+                .iter()
+                .any(|a| a.street == row.adress && a.postal == row.postnummer)
+            {
+                continue;
+            }
+            addresses.push(SavedAddress {
+                street: row.adress,
+                postal: row.postnummer,
+                active,
+                kind: ScheduleType::None,
+                deadline: None,
+            });
+        }
+
+        self.save(&addresses, &Settings::default())?;
+        fs::rename(&legacy, legacy.with_extension("parquet.migrated"))
+            .map_err(|e| format!("Failed to rename {}: {}", legacy.display(), e))?;
+        Ok(addresses)
+    }
+}
+
+/// Bring a stored file of any known version to the current layout
+fn upgrade(value: serde_json::Value) -> Result<StoredFile, String> {
+    let version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or("Saved addresses have no version")?;
+
+    let invalid = |e: serde_json::Error| format!("Invalid saved addresses: {}", e);
+    match version {
+        1 => {
+            let file: StoredFileV1 = serde_json::from_value(value).map_err(invalid)?;
+            Ok(StoredFile {
+                version: STORAGE_VERSION,
+                addresses: file.addresses,
+                settings: Settings::default(),
+            })
+        }
+        2 => serde_json::from_value(value).map_err(invalid),
+        v => Err(format!(
+            "Saved addresses version {} is newer than supported ({})",
+            v, STORAGE_VERSION
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage(name: &str) -> Storage {
+        let dir = std::env::temp_dir().join(format!("amp-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Storage::new(dir)
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let storage = temp_storage("roundtrip");
+        assert!(storage.load().unwrap().0.is_empty());
+
+        let saved = vec![SavedAddress {
+            street: "Lokgatan 11B".to_string(),
+            postal: "211 20".to_string(),
+            active: false,
+            kind: ScheduleType::Month,
+            deadline: Some(1_790_000_000),
+        }];
+        let settings = Settings {
+            lead_times: vec![60],
+        };
+        storage.save(&saved, &settings).unwrap();
+        assert_eq!(storage.load().unwrap(), (saved, settings));
+
+        // Version 1 files had no settings
+        fs::write(
+            storage.path(),
+            r#"{"version": 1, "addresses": [{"street": "Lokgatan 11B", "postal": "211 20",
+                "active": true, "kind": "Month", "deadline": null}]}"#,
+        )
+        .unwrap();
+        let (addresses, settings) = storage.load().unwrap();
+        assert_eq!(addresses.len(), 1);
+        assert_eq!(settings, Settings::default());
+
+        fs::write(storage.path(), r#"{"version": 9, "addresses": []}"#).unwrap();
+        assert!(storage.load().is_err());
+    }
+
+    #[test]
+    fn test_migrates_legacy_parquet() {
+        let storage = temp_storage("legacy");
+        fs::create_dir_all(&storage.dir).unwrap();
+        let legacy = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/local.parquet");
+        fs::copy(legacy, storage.dir.join(LEGACY_FILE)).unwrap();
+
+        let (loaded, _) = storage.load().unwrap();
+        assert_eq!(loaded.len(), 7);
+        assert!(loaded.iter().all(|a| a.active));
+        assert!(storage.path().exists());
+        assert!(!storage.dir.join(LEGACY_FILE).exists());
+        assert_eq!(storage.load().unwrap().0, loaded);
+    }
+}
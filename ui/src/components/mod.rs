@@ -1,3 +1,3 @@
+pub mod dataset;
 pub mod file;
-pub mod geo;
 pub mod notification;
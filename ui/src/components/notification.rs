@@ -0,0 +1,195 @@
+//! Reminders before street cleaning starts
+//! Reminders are planned from the dataset's schedules and handed to a platform backend
+
+use crate::components::dataset::AddressData;
+use amp_core::schedule::Window;
+use chrono::{NaiveDateTime, TimeDelta};
+
+/// Minutes before a cleaning window starts: 24 h, 6 h and 1 h
+pub const DEFAULT_LEAD_TIMES: [u32; 3] = [24 * 60, 6 * 60, 60];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    /// Stable for the same address, window and lead time
+    pub id: String,
+    pub street: String,
+    pub postal: String,
+    pub at: NaiveDateTime,
+    /// Minutes before the window starts
+    pub lead_time: u32,
+    pub window: Window,
+}
+
+impl Reminder {
+    pub fn title(&self) -> String {
+        format!("Gatustädning om {}", format_lead_time(self.lead_time))
+    }
+
+    pub fn body(&self) -> String {
+        format!(
+            "{}: städning {} kl {}–{}. Flytta bilen innan dess.",
+            self.street,
+            self.window.start.format("%-d/%-m"),
+            self.window.start.format("%H:%M"),
+            self.window.end.format("%H:%M"),
+        )
+    }
+}
+
+/// "24 h", "1 h", "30 min"
+pub fn format_lead_time(minutes: u32) -> String {
+    if minutes.is_multiple_of(60) {
+        format!("{} h", minutes / 60)
+    } else {
+        format!("{} min", minutes)
+    }
+}
+
+/// Reminders for the next cleaning window of each `(street, postal)` address, earliest first
+/// Reminders whose time has already passed are skipped
+pub fn plan_reminders<'a>(
+    data: &AddressData,
+    addresses: impl IntoIterator<Item = (&'a str, &'a str)>,
+    lead_times: &[u32],
+    now: NaiveDateTime,
+) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+
+    for (street, postal) in addresses {
+        let Some(window) = data.next_window(street, postal, now) else {
+            continue;
+        };
+        for &lead_time in lead_times {
+            let at = window.start - TimeDelta::minutes(i64::from(lead_time));
+            if at <= now {
+                continue;
+            }
+            reminders.push(Reminder {
+                id: format!("{}-{}-{}-{}", street, postal, window.start, lead_time),
+                street: street.to_string(),
+                postal: postal.to_string(),
+                at,
+                lead_time,
+                window,
+            });
+        }
+    }
+
+    reminders.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.id.cmp(&b.id)));
+    reminders
+}
+
+/// Delivers reminders on a platform
+pub trait Notifier {
+    /// Replace every pending reminder
+    fn schedule(&mut self, reminders: Vec<Reminder>) -> Result<(), String>;
+
+    /// Reminders due at `now` that the app itself must deliver
+    /// Backends whose reminders are delivered by the OS return nothing
+    fn poll(&mut self, _now: NaiveDateTime) -> Vec<Reminder> {
+        Vec::new()
+    }
+}
+
+/// Keeps reminders in memory and logs them when due
+/// Used on platforms without a native backend and in tests
+#[derive(Debug, Default)]
+pub struct LogNotifier {
+    pending: Vec<Reminder>,
+}
+
+impl LogNotifier {
+    pub fn pending(&self) -> &[Reminder] {
+        &self.pending
+    }
+}
+
+impl Notifier for LogNotifier {
+    fn schedule(&mut self, reminders: Vec<Reminder>) -> Result<(), String> {
+        if let Some(next) = reminders.first() {
+            eprintln!(
+                "Scheduled {} reminders, next at {}",
+                reminders.len(),
+                next.at
+            );
+        }
+        self.pending = reminders;
+        Ok(())
+    }
+
+    fn poll(&mut self, now: NaiveDateTime) -> Vec<Reminder> {
+        let (due, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|r| r.at <= now);
+        self.pending = pending;
+
+        for reminder in &due {
+            println!("{}: {}", reminder.title(), reminder.body());
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amp_core::structs::AdressInfo;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn data() -> AddressData {
+        let row = |adress: &str, dag: u8, tid: &str| AdressInfo {
+            coordinates: [13.0, 55.6],
+            postnummer: "211 20".to_string(),
+            adress: adress.to_string(),
+            gata: adress.split(' ').next().unwrap().to_string(),
+            gatunummer: adress.split(' ').nth(1).unwrap().to_string(),
+            dag,
+            tid: tid.to_string(),
+            info: String::new(),
+        };
+        AddressData::new(vec![
+            row("Lokgatan 11B", 16, "0800 - 1200"),
+            row("Skeppsbron 1C", 0, ""),
+        ])
+    }
+
+    #[test]
+    fn test_plan_reminders() {
+        let now = at("2026-03-15 10:00");
+        let reminders = plan_reminders(
+            &data(),
+            [("Lokgatan 11B", "211 20"), ("Skeppsbron 1C", "211 20")],
+            &DEFAULT_LEAD_TIMES,
+            now,
+        );
+
+        // 24 h before 2026-03-16 08:00 has already passed
+        let times: Vec<_> = reminders.iter().map(|r| r.at).collect();
+        assert_eq!(times, vec![at("2026-03-16 02:00"), at("2026-03-16 07:00")]);
+        assert!(reminders.iter().all(|r| r.street == "Lokgatan 11B"));
+        assert_eq!(reminders[0].title(), "Gatustädning om 6 h");
+    }
+
+    #[test]
+    fn test_log_notifier_delivers_due_reminders() {
+        let now = at("2026-03-15 10:00");
+        let mut notifier = LogNotifier::default();
+        notifier
+            .schedule(plan_reminders(
+                &data(),
+                [("Lokgatan 11B", "211 20")],
+                &[6 * 60, 60],
+                now,
+            ))
+            .unwrap();
+        assert_eq!(notifier.pending().len(), 2);
+
+        assert!(notifier.poll(now).is_empty());
+        let due = notifier.poll(at("2026-03-16 02:30"));
+        assert_eq!(due.len(), 1);
+        assert_eq!(notifier.pending().len(), 1);
+    }
+}
@@ -0,0 +1,8 @@
+//! Dioxus components and app state shared by the Android and iOS apps
+//! The platform binaries only implement [`Platform`] and call [`launch`]
+
+pub mod components;
+pub mod platform;
+pub mod ui;
+
+pub use platform::{Platform, launch};
@@ -0,0 +1,50 @@
+//! Services the platform binaries provide to the shared UI
+
+use crate::components::notification::Notifier;
+use crate::ui::App;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub trait Platform: Send + Sync + 'static {
+    /// Directory for saved addresses and settings
+    fn data_dir(&self) -> PathBuf;
+
+    /// Backend delivering cleaning reminders
+    fn notifier(&self) -> Box<dyn Notifier>;
+
+    /// Current position as `(lat, lon)`; None when unavailable or not permitted
+    fn location(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Make an exported file available to the user, e.g. a calendar
+    /// The default saves it in the data directory and returns its path
+    fn export(&self, file_name: &str, contents: &str) -> Result<PathBuf, String> {
+        let dir = self.data_dir();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(file_name);
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// The platform, available to components through `use_context`
+#[derive(Clone)]
+pub struct PlatformContext(pub Arc<dyn Platform>);
+
+/// `AMP_DATA_DIR`, which overrides the platform data directory during development
+pub fn data_dir_override() -> Option<PathBuf> {
+    std::env::var_os("AMP_DATA_DIR").map(PathBuf::from)
+}
+
+/// Launch the app with the services of a platform
+// `LaunchBuilder::new` is deprecated while no renderer is enabled; the binaries enable one
+#[allow(deprecated)]
+pub fn launch(platform: impl Platform) {
+    dioxus::LaunchBuilder::new()
+        .with_context(PlatformContext(Arc::new(platform)))
+        .launch(App);
+}
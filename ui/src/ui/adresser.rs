@@ -0,0 +1,54 @@
+use crate::ui::Address;
+use dioxus::prelude::*;
+
+#[component]
+pub fn Adresser(
+    addresses: Vec<(Address, bool)>,
+    on_toggle: EventHandler<usize>,
+    on_remove: EventHandler<usize>,
+    on_export: EventHandler<usize>,
+    status: Option<String>,
+) -> Element {
+    rsx! {
+
+        div { class: "stored-addresses",
+            h2 { "Adresser" }
+            if let Some(message) = status {
+                div { class: "export-status", "{message}" }
+            }
+            div { id: "addressList",
+                if addresses.is_empty() {
+                    div { class: "empty-message", "Inga sparade adresser" }
+                }
+                for (index, (address, active)) in addresses.into_iter().enumerate() {
+                    div {
+                        class: "address-item",
+                        key: "{address.street}-{address.postal}",
+                        span { class: "address-text", "{address.street}, {address.postal}" }
+                        div { class: "address-actions",
+                            label { class: "switch",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: active,
+                                    onchange: move |_| on_toggle.call(index),
+                                }
+                                span { class: "slider" }
+                            }
+                            button {
+                                class: "btn-export",
+                                title: "Exportera till kalender",
+                                onclick: move |_| on_export.call(index),
+                                "📅"
+                            }
+                            button {
+                                class: "btn-remove",
+                                onclick: move |_| on_remove.call(index),
+                                "Ta bort"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
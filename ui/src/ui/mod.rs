@@ -0,0 +1,510 @@
+pub mod adresser;
+pub mod paminnelser;
+pub mod paneler;
+pub mod topbar;
+
+use crate::{
+    components::{
+        dataset::AddressData,
+        file::{SavedAddress, Settings, Storage, load_address_data},
+        notification::plan_reminders,
+    },
+    platform::PlatformContext,
+    ui::{
+        adresser::Adresser,
+        paminnelser::Paminnelser,
+        paneler::{Active, Day, Month, NotValid, Six, TimerEntry},
+        topbar::TopBar,
+    },
+};
+
+use amp_core::ics::address_calendar;
+use chrono::{Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static CSS: Asset = asset!("/assets/style.css");
+
+/// How far from the device position the GPS button looks for an address, in meters
+const LOCATE_DISTANCE: f64 = 100.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Address {
+    pub street: String,
+    pub postal: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScheduleType {
+    Now,
+    SixHours,
+    TwentyFourHours,
+    Month,
+    None,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Schedule {
+    kind: ScheduleType,
+    deadline: Option<SystemTime>,
+    active: bool,
+}
+
+fn schedule_key(addr: &Address) -> String {
+    format!("{}-{}", addr.street, addr.postal)
+}
+
+fn format_postal_code(postal: &str) -> String {
+    let digits: String = postal.chars().filter(|c| c.is_ascii_digit()).collect();
+    let mut padded = digits;
+    padded.push_str("00000");
+    let padded = &padded[..5];
+    format!("{} {}", &padded[0..3], &padded[3..5])
+}
+
+fn to_system_time(at: NaiveDateTime) -> Option<SystemTime> {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// Category of an address from its next cleaning window
+/// The deadline is the end of an ongoing window, otherwise the start of the next one;
+/// a window more than 31 days away is re-checked once it is 31 days away
+fn compute_schedule(data: &AddressData, addr: &Address, active: bool) -> Schedule {
+    let now = Local::now().naive_local();
+    let (kind, deadline) = match data.next_window(&addr.street, &addr.postal, now) {
+        None => (ScheduleType::None, None),
+        Some(window) if window.is_active(now) => (ScheduleType::Now, to_system_time(window.end)),
+        Some(window) => {
+            let until = window.start - now;
+            if until <= TimeDelta::hours(6) {
+                (ScheduleType::SixHours, to_system_time(window.start))
+            } else if until <= TimeDelta::hours(24) {
+                (ScheduleType::TwentyFourHours, to_system_time(window.start))
+            } else if until <= TimeDelta::days(31) {
+                (ScheduleType::Month, to_system_time(window.start))
+            } else {
+                (
+                    ScheduleType::None,
+                    to_system_time(window.start - TimeDelta::days(31)),
+                )
+            }
+        }
+    };
+
+    Schedule {
+        kind,
+        deadline,
+        active,
+    }
+}
+
+fn to_saved(addr: &Address, schedule: Option<&Schedule>) -> SavedAddress {
+    SavedAddress {
+        street: addr.street.clone(),
+        postal: addr.postal.clone(),
+        active: schedule.is_none_or(|s| s.active),
+        kind: schedule.map_or(ScheduleType::None, |s| s.kind.clone()),
+        deadline: schedule
+            .and_then(|s| s.deadline)
+            .and_then(|d| d.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+    }
+}
+
+fn from_saved(saved: SavedAddress) -> (Address, Schedule) {
+    (
+        Address {
+            street: saved.street,
+            postal: saved.postal,
+        },
+        Schedule {
+            kind: saved.kind,
+            deadline: saved
+                .deadline
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            active: saved.active,
+        },
+    )
+}
+
+fn format_time(ms: u128) -> String {
+    let total_seconds = (ms / 1000) as u64;
+    let days = total_seconds / 86_400;
+    let hours = (total_seconds % 86_400) / 3_600;
+    let minutes = (total_seconds % 3_600) / 60;
+    let seconds = total_seconds % 60;
+
+    format!("{:02}:{:02}:{:02}:{:02}", days, hours, minutes, seconds)
+}
+
+#[component]
+pub fn App() -> Element {
+    let platform = use_context::<PlatformContext>();
+
+    // A file that cannot be read is left untouched rather than overwritten
+    let (storage, saved, loaded_settings) = use_hook(|| {
+        let storage = Storage::new(platform.0.data_dir());
+        match storage.load() {
+            Ok((saved, settings)) => (Some(Rc::new(storage)), saved, settings),
+            Err(e) => {
+                eprintln!("{}; changes will not be saved", e);
+                (None, Vec::new(), Settings::default())
+            }
+        }
+    });
+
+    let addresses = use_signal(|| {
+        saved
+            .iter()
+            .map(|s| from_saved(s.clone()).0)
+            .collect::<Vec<_>>()
+    });
+    let mut schedules = use_signal(|| {
+        saved
+            .iter()
+            .map(|s| {
+                let (addr, schedule) = from_saved(s.clone());
+                (schedule_key(&addr), schedule)
+            })
+            .collect::<HashMap<_, _>>()
+    });
+    let mut settings = use_signal(|| loaded_settings);
+    let mut now = use_signal(SystemTime::now);
+    let mut export_status = use_signal::<Option<String>>(|| None);
+    let data = use_resource(load_address_data);
+    let notifier = use_hook(|| Rc::new(RefCell::new(platform.0.notifier())));
+
+    // Save whenever addresses, categories, active flags or settings change
+    use_effect(move || {
+        let settings = settings.read();
+        let scheds = schedules.read();
+        let saved: Vec<SavedAddress> = addresses
+            .read()
+            .iter()
+            .map(|addr| to_saved(addr, scheds.get(&schedule_key(addr))))
+            .collect();
+        if let Some(Err(e)) = storage
+            .as_ref()
+            .map(|storage| storage.save(&saved, &settings))
+        {
+            eprintln!("{}", e);
+        }
+    });
+
+    // Reschedule reminders for active addresses when the data, addresses or lead times change
+    use_effect({
+        let notifier = notifier.clone();
+        move || {
+            let data = data.read();
+            let Some(Ok(data)) = &*data else {
+                return;
+            };
+            let addrs = addresses.read();
+            let scheds = schedules.read();
+            let active = addrs
+                .iter()
+                .filter(|addr| scheds.get(&schedule_key(addr)).is_none_or(|s| s.active))
+                .map(|addr| (addr.street.as_str(), addr.postal.as_str()));
+
+            let reminders = plan_reminders(
+                data,
+                active,
+                &settings.read().lead_times,
+                Local::now().naive_local(),
+            );
+            if let Err(e) = notifier.borrow_mut().schedule(reminders) {
+                eprintln!("Failed to schedule reminders: {}", e);
+            }
+        }
+    });
+
+    // Recompute every schedule once the bundled dataset has loaded
+    use_effect(move || match &*data.read() {
+        Some(Ok(data)) => {
+            let addrs = addresses.peek();
+            let mut scheds = schedules.write();
+            for addr in addrs.iter() {
+                let key = schedule_key(addr);
+                let active = scheds.get(&key).is_none_or(|s| s.active);
+                scheds.insert(key, compute_schedule(data, addr, active));
+            }
+        }
+        Some(Err(e)) => eprintln!("{}", e),
+        None => {}
+    });
+
+    // addAddressManual equivalent, called from the TopBar add button
+    // Only addresses present in the dataset are accepted; the error is shown to the user
+    let mut add_address_manual = {
+        let mut addresses = addresses.to_owned();
+        let mut schedules = schedules.to_owned();
+        move |street: String, postal_input: String| -> Result<(), String> {
+            if street.trim().is_empty() {
+                return Err("Ange gata och nummer".to_string());
+            }
+
+            let data = data.read();
+            let data = match &*data {
+                Some(Ok(data)) => data,
+                Some(Err(_)) => return Err("Adressdata kunde inte läsas".to_string()),
+                None => return Err("Adressdata laddas fortfarande".to_string()),
+            };
+
+            let postal = if postal_input.trim().is_empty() {
+                String::new()
+            } else {
+                format_postal_code(&postal_input)
+            };
+            let (street, postal) = data.validate(&street, &postal)?;
+            let addr = Address { street, postal };
+            let key = schedule_key(&addr);
+
+            if schedules.read().contains_key(&key) {
+                return Err(format!("{} finns redan", addr.street));
+            }
+
+            schedules
+                .write()
+                .insert(key, compute_schedule(data, &addr, true));
+            addresses.write().push(addr);
+            Ok(())
+        }
+    };
+
+    // removeAddress equivalent
+    let remove_address = {
+        let mut addresses = addresses.to_owned();
+        let mut schedules = schedules.to_owned();
+        move |index: usize| {
+            let mut addrs = addresses.write();
+            if index >= addrs.len() {
+                return;
+            }
+            let addr = addrs.remove(index);
+            let key = schedule_key(&addr);
+            schedules.write().remove(&key);
+        }
+    };
+
+    // toggleAddress equivalent
+    let toggle_address = {
+        let addresses = addresses.to_owned();
+        let mut schedules = schedules.to_owned();
+        move |index: usize| {
+            let addrs = addresses.read();
+            if index >= addrs.len() {
+                return;
+            }
+            let addr = &addrs[index];
+            let key = schedule_key(addr);
+            if let Some(s) = schedules.write().get_mut(&key) {
+                s.active = !s.active;
+            }
+        }
+    };
+
+    // updateSchedule equivalent: re-derive the category from the dataset
+    let mut update_schedule = {
+        let addresses = addresses.to_owned();
+        let mut schedules = schedules.to_owned();
+        move |index: usize| {
+            let addrs = addresses.read();
+            let Some(addr) = addrs.get(index) else {
+                return;
+            };
+            if let Some(Ok(data)) = &*data.read() {
+                let key = schedule_key(addr);
+                let active = schedules.read().get(&key).is_none_or(|s| s.active);
+                schedules
+                    .write()
+                    .insert(key, compute_schedule(data, addr, active));
+            }
+        }
+    };
+
+    // Save the address's cleaning schedule as an iCalendar file through the platform
+    let export_calendar = {
+        let platform = platform.clone();
+        move |index: usize| {
+            let addrs = addresses.read();
+            let Some(addr) = addrs.get(index) else {
+                return;
+            };
+            let data = data.read();
+            let Some(Ok(data)) = &*data else {
+                export_status.set(Some("Adressdata laddas fortfarande".to_string()));
+                return;
+            };
+
+            let restrictions = data.restrictions(&addr.street, &addr.postal);
+            if restrictions.is_empty() {
+                export_status.set(Some(format!("Ingen städning för {}", addr.street)));
+                return;
+            }
+            let calendar = address_calendar(
+                &addr.street,
+                &restrictions,
+                Local::now().naive_local(),
+                Utc::now(),
+            );
+            let file_name: String = addr
+                .street
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            let status = match platform.0.export(&format!("{}.ics", file_name), &calendar) {
+                Ok(path) => format!("Kalender sparad: {}", path.display()),
+                Err(e) => e,
+            };
+            export_status.set(Some(status));
+        }
+    };
+
+    // Nearest dataset address to the device position, for the GPS button
+    let locate = move |_: ()| -> Result<(String, String), String> {
+        let (lat, lon) = platform
+            .0
+            .location()
+            .ok_or("Positionen är inte tillgänglig")?;
+        let data = data.read();
+        let Some(Ok(data)) = &*data else {
+            return Err("Adressdata laddas fortfarande".to_string());
+        };
+        let nearest = data
+            .nearest(lat, lon, LOCATE_DISTANCE)
+            .ok_or("Ingen adress hittades i närheten")?;
+        Ok((nearest.adress.clone(), nearest.postnummer.clone()))
+    };
+
+    let toggle_lead_time = move |minutes: u32| {
+        let mut settings = settings.write();
+        let lead_times = &mut settings.lead_times;
+        match lead_times.iter().position(|&m| m == minutes) {
+            Some(pos) => {
+                lead_times.remove(pos);
+            }
+            None => {
+                lead_times.push(minutes);
+                lead_times.sort_unstable_by(|a, b| b.cmp(a));
+            }
+        }
+    };
+
+    // Tick every second; addresses whose deadline passed move on to their next window
+    use_future(move || {
+        let notifier = notifier.clone();
+        async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                now.set(SystemTime::now());
+                notifier.borrow_mut().poll(Local::now().naive_local());
+
+                let expired: Vec<usize> = {
+                    let scheds = schedules.peek();
+                    addresses
+                        .peek()
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, addr)| {
+                            scheds
+                                .get(&schedule_key(addr))
+                                .and_then(|s| s.deadline)
+                                .is_some_and(|deadline| deadline <= now())
+                        })
+                        .map(|(index, _)| index)
+                        .collect()
+                };
+                for index in expired {
+                    update_schedule(index);
+                }
+            }
+        }
+    });
+
+    // updateTimers equivalent: derive formatted time for each active scheduled address
+    let timers: Vec<(ScheduleType, TimerEntry)> = {
+        let now = now();
+        let addrs = addresses.read();
+        let scheds = schedules.read();
+        addrs
+            .iter()
+            .filter_map(|addr| {
+                let key = schedule_key(addr);
+                let schedule = scheds.get(&key)?;
+                if !schedule.active {
+                    return None;
+                }
+                let remaining = schedule
+                    .deadline
+                    .and_then(|deadline| deadline.duration_since(now).ok())
+                    .map(|dur| format_time(dur.as_millis()));
+                Some((
+                    schedule.kind.clone(),
+                    TimerEntry {
+                        address: addr.clone(),
+                        remaining,
+                    },
+                ))
+            })
+            .collect()
+    };
+    let entries = |kind: ScheduleType| -> Vec<TimerEntry> {
+        timers
+            .iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    };
+
+    let stored: Vec<(Address, bool)> = {
+        let scheds = schedules.read();
+        addresses
+            .read()
+            .iter()
+            .map(|addr| {
+                let active = scheds.get(&schedule_key(addr)).is_none_or(|s| s.active);
+                (addr.clone(), active)
+            })
+            .collect()
+    };
+
+    rsx! {
+        Stylesheet { href: CSS }
+        div {
+            class: "app-wrapper",
+            TopBar {
+                on_add: move |(street, postal): (String, String)| add_address_manual(street, postal),
+                on_locate: locate,
+            },
+            div {
+                class: "app-container",
+                Adresser {
+                    addresses: stored,
+                    on_toggle: toggle_address,
+                    on_remove: remove_address,
+                    on_export: export_calendar,
+                    status: export_status(),
+                }
+                Paminnelser {
+                    lead_times: settings.read().lead_times.clone(),
+                    on_toggle: toggle_lead_time,
+                }
+                div {
+                    class: "categories-section",
+                    Active { entries: entries(ScheduleType::Now) },
+                    Six { entries: entries(ScheduleType::SixHours) },
+                    Day { entries: entries(ScheduleType::TwentyFourHours) },
+                    Month { entries: entries(ScheduleType::Month) },
+                    NotValid { entries: entries(ScheduleType::None) },
+                }
+            }
+        }
+    }
+}
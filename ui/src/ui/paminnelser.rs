@@ -0,0 +1,27 @@
+use crate::components::notification::format_lead_time;
+use dioxus::prelude::*;
+
+/// Lead times offered, in minutes before cleaning starts
+const OPTIONS: [u32; 5] = [24 * 60, 12 * 60, 6 * 60, 60, 30];
+
+#[component]
+pub fn Paminnelser(lead_times: Vec<u32>, on_toggle: EventHandler<u32>) -> Element {
+    rsx! {
+
+        div { class: "reminder-settings",
+            h2 { "Påminnelser" }
+            div { class: "reminder-options",
+                for option in OPTIONS {
+                    label { class: "reminder-option", key: "{option}",
+                        input {
+                            r#type: "checkbox",
+                            checked: lead_times.contains(&option),
+                            onchange: move |_| on_toggle.call(option),
+                        }
+                        "{format_lead_time(option)} före"
+                    }
+                }
+            }
+        }
+    }
+}
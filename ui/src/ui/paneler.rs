@@ -0,0 +1,105 @@
+use crate::ui::Address;
+use dioxus::prelude::*;
+
+/// An address listed in a category panel with the time left until its deadline
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimerEntry {
+    pub address: Address,
+    pub remaining: Option<String>,
+}
+
+#[component]
+fn CategoryContent(suffix: &'static str, entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+        div {
+            class: "category-content",
+            div {
+                class: "category-addresses", id: "container-{suffix}",
+                if entries.is_empty() {
+                    div { class: "empty-message", "Inga adresser" }
+                }
+                for entry in entries {
+                    div {
+                        class: "address-row",
+                        key: "{entry.address.street}-{entry.address.postal}",
+                        span { class: "address-name", "{entry.address.street}, {entry.address.postal}" }
+                        span {
+                            class: "timer-row",
+                            {entry.remaining.unwrap_or_else(|| "–".to_string())}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Active(entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+
+        div {
+            class: "category-container category-active",
+            div {
+                class: "category-title", "Städas nu"
+            }
+            CategoryContent { suffix: "active", entries }
+        }
+    }
+}
+
+#[component]
+pub fn Six(entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+
+        div {
+            class: "category-container category-6h",
+            div {
+                class: "category-title", "Inom 6 timmar"
+            }
+            CategoryContent { suffix: "6h", entries }
+        }
+    }
+}
+
+#[component]
+pub fn Day(entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+
+        div {
+            class: "category-container category-24h",
+            div {
+                class: "category-title", "Inom 1 dag"
+            }
+            CategoryContent { suffix: "24h", entries }
+        }
+    }
+}
+
+#[component]
+pub fn Month(entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+
+        div {
+            class: "category-container category-month",
+            div {
+                class: "category-title", "Inom 1 månad"
+            }
+            CategoryContent { suffix: "month", entries }
+        }
+    }
+}
+
+#[component]
+pub fn NotValid(entries: Vec<TimerEntry>) -> Element {
+    rsx! {
+
+        div {
+            class: "category-container category-invalid",
+            div {
+                class: "category-title", "Ingen städning"
+            }
+            CategoryContent { suffix: "none", entries }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use dioxus::prelude::*;
+
+/// `on_add` receives the street and postal code and returns an error to show on failure
+/// `on_locate` returns the street and postal code at the device position
+#[component]
+pub fn TopBar(
+    on_add: Callback<(String, String), Result<(), String>>,
+    on_locate: Callback<(), Result<(String, String), String>>,
+) -> Element {
+    let mut street = use_signal(String::new);
+    let mut postal = use_signal(String::new);
+    let mut feedback = use_signal::<Option<String>>(|| None);
+
+    let mut add = move || match on_add.call((street(), postal())) {
+        Ok(()) => {
+            street.set(String::new());
+            postal.set(String::new());
+            feedback.set(None);
+        }
+        Err(message) => feedback.set(Some(message)),
+    };
+
+    rsx! {
+
+        div { class: "top-bar",
+            div { class: "input-section",
+                div { class: "input-group",
+                    input {
+                        r#type: "text",
+                        id: "streetInput",
+                        placeholder: "Gata och nummer",
+                        value: "{street}",
+                        oninput: move |evt| street.set(evt.value()),
+                        onkeydown: move |evt| {
+                            if evt.key() == Key::Enter {
+                                add();
+                            }
+                        },
+                    }
+                    input {
+                        r#type: "text",
+                        id: "postalInput",
+                        placeholder: "Postnummer",
+                        value: "{postal}",
+                        oninput: move |evt| postal.set(evt.value()),
+                        onkeydown: move |evt| {
+                            if evt.key() == Key::Enter {
+                                add();
+                            }
+                        },
+                    }
+                }
+                div { class: "btn-group",
+                    button {
+                        class: "btn btn-add",
+                        onclick: move |_| add(),
+                        "➕ Lägg till"
+                    }
+                    button {
+                        class: "btn btn-gps",
+                        onclick: move |_| match on_locate.call(()) {
+                            Ok((found_street, found_postal)) => {
+                                street.set(found_street);
+                                postal.set(found_postal);
+                                feedback.set(None);
+                            }
+                            Err(message) => feedback.set(Some(message)),
+                        },
+                        "📍 Använd GPS"
+                    }
+                }
+            }
+            if let Some(message) = feedback() {
+                div { class: "input-feedback", "{message}" }
+            }
+        }
+    }
+}